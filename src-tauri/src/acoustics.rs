@@ -0,0 +1,212 @@
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use rodio::Source;
+
+use crate::audio_engine::ListenerZone;
+use crate::dsp::{ Compressor, DspSourceExt };
+
+/// Soundpacks can override the acoustics with this file in their root.
+pub const ACOUSTICS_FILE: &str = "acoustics.json";
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerSettings {
+    pub low_cut_hz: f32,
+    pub high_cut_hz: f32,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverbSettings {
+    /// Room size; 1.0 is a narrow-body cabin
+    pub size: f32,
+    /// Comb feedback, 0..0.95
+    pub decay: f32,
+    /// Wet share of the output, 0..1
+    pub mix: f32,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompressorSettings {
+    pub threshold_db: f32,
+    pub ratio: f32,
+    pub attack_ms: u64,
+    pub release_ms: u64,
+    pub makeup_db: f32,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MuffleSettings {
+    pub cutoff_hz: f32,
+    pub gain_db: f32,
+}
+
+/// DSP chain for PAs heard in one zone. A missing stage is skipped.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AcousticProfile {
+    pub speaker: Option<SpeakerSettings>,
+    pub compressor: Option<CompressorSettings>,
+    pub reverb: Option<ReverbSettings>,
+    /// Outside, this always applies; in the cockpit only while the door is closed
+    pub muffle: Option<MuffleSettings>,
+}
+
+const PA_SPEAKER: SpeakerSettings = SpeakerSettings { low_cut_hz: 250.0, high_cut_hz: 6000.0 };
+const PA_COMPRESSOR: CompressorSettings = CompressorSettings {
+    threshold_db: -18.0,
+    ratio: 3.0,
+    attack_ms: 5,
+    release_ms: 150,
+    makeup_db: 4.0,
+};
+
+/// Acoustic profiles for every listener zone.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ZoneAcoustics {
+    pub outside: AcousticProfile,
+    pub jetway: AcousticProfile,
+    pub cabin: AcousticProfile,
+    pub cockpit: AcousticProfile,
+}
+
+impl Default for ZoneAcoustics {
+    /// Mirrors the zone reverbs of the frontend's WebAudio processor.
+    fn default() -> Self {
+        let profile = |reverb: ReverbSettings, muffle: Option<MuffleSettings>| AcousticProfile {
+            speaker: Some(PA_SPEAKER),
+            compressor: Some(PA_COMPRESSOR),
+            reverb: Some(reverb),
+            muffle,
+        };
+        ZoneAcoustics {
+            outside: profile(
+                ReverbSettings { size: 0.5, decay: 0.5, mix: 0.15 },
+                Some(MuffleSettings { cutoff_hz: 1200.0, gain_db: -6.0 })
+            ),
+            jetway: profile(ReverbSettings { size: 0.8, decay: 0.6, mix: 0.2 }, None),
+            cabin: profile(ReverbSettings { size: 1.0, decay: 0.75, mix: 0.25 }, None),
+            cockpit: profile(
+                ReverbSettings { size: 0.6, decay: 0.6, mix: 0.15 },
+                Some(MuffleSettings { cutoff_hz: 800.0, gain_db: -8.0 })
+            ),
+        }
+    }
+}
+
+impl ZoneAcoustics {
+    /// Reads a soundpack's overrides on top of the defaults. Zones, stages and settings
+    /// it doesn't mention keep their defaults; a stage set to `null` is switched off.
+    pub fn load(soundpack_root: &Path) -> Result<Self, String> {
+        let path = soundpack_root.join(ACOUSTICS_FILE);
+        if !path.is_file() {
+            return Ok(ZoneAcoustics::default());
+        }
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        ZoneAcoustics::with_overrides(&text)
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e))
+    }
+
+    fn with_overrides(json: &str) -> Result<Self, serde_json::Error> {
+        let overrides: Value = serde_json::from_str(json)?;
+        let mut merged = serde_json::to_value(ZoneAcoustics::default())?;
+        merge(&mut merged, overrides);
+        serde_json::from_value(merged)
+    }
+
+    pub fn get_mut(&mut self, zone: ListenerZone) -> &mut AcousticProfile {
+        match zone {
+            ListenerZone::Outside => &mut self.outside,
+            ListenerZone::Jetway => &mut self.jetway,
+            ListenerZone::Cabin => &mut self.cabin,
+            ListenerZone::Cockpit => &mut self.cockpit,
+        }
+    }
+
+    /// The chain to use for `zone`, leaving out cockpit muffling while the door is open.
+    pub fn profile(&self, zone: ListenerZone, cockpit_door_closed: bool) -> AcousticProfile {
+        match zone {
+            ListenerZone::Outside => self.outside,
+            ListenerZone::Jetway => self.jetway,
+            ListenerZone::Cabin => self.cabin,
+            ListenerZone::Cockpit if cockpit_door_closed => self.cockpit,
+            ListenerZone::Cockpit => AcousticProfile { muffle: None, ..self.cockpit },
+        }
+    }
+}
+
+/// Overlays `overrides` onto `base`, object by object.
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) if value.is_object() => merge(existing, value),
+                    _ => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Wraps a source in the profile's DSP chain: speaker, compressor, room, then the wall.
+pub fn apply<S>(source: S, profile: &AcousticProfile) -> Box<dyn Source<Item = f32> + Send>
+where
+    S: Source<Item = f32> + Send + 'static,
+{
+    let mut chain: Box<dyn Source<Item = f32> + Send> = Box::new(source);
+    if let Some(speaker) = profile.speaker {
+        chain = Box::new(chain.speaker_eq(speaker.low_cut_hz, speaker.high_cut_hz));
+    }
+    if let Some(c) = profile.compressor {
+        chain = Box::new(chain.compress(Compressor::new(
+            c.threshold_db,
+            c.ratio,
+            Duration::from_millis(c.attack_ms),
+            Duration::from_millis(c.release_ms),
+            c.makeup_db
+        )));
+    }
+    if let Some(reverb) = profile.reverb {
+        chain = Box::new(chain.cabin_reverb(reverb.size, reverb.decay, reverb.mix));
+    }
+    if let Some(muffle) = profile.muffle {
+        chain = Box::new(chain.muffle(muffle.cutoff_hz, muffle.gain_db));
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_override_keeps_the_other_stages() {
+        let acoustics = ZoneAcoustics::with_overrides(r#"{ "cabin": { "reverb": { "mix": 0.4 } } }"#).unwrap();
+        let defaults = ZoneAcoustics::default();
+
+        let reverb = acoustics.cabin.reverb.unwrap();
+        assert_eq!(reverb.mix, 0.4);
+        assert_eq!(reverb.size, defaults.cabin.reverb.unwrap().size);
+        assert!(acoustics.cabin.speaker.is_some());
+        assert!(acoustics.cabin.compressor.is_some());
+        assert!(acoustics.cockpit.muffle.is_some());
+    }
+
+    #[test]
+    fn null_switches_a_stage_off() {
+        let acoustics = ZoneAcoustics::with_overrides(r#"{ "outside": { "muffle": null } }"#).unwrap();
+        assert!(acoustics.outside.muffle.is_none());
+        assert!(acoustics.outside.reverb.is_some());
+    }
+}
@@ -0,0 +1,48 @@
+use serde_json::{ json, Value };
+
+use crate::cabin_chime::AircraftFamily;
+use crate::interphone::{ self, CallDefinition, CallProfile };
+
+/// What simpa adapts to the loaded aircraft, picked from its TITLE or ATC MODEL.
+#[derive(Clone, Debug)]
+pub struct AircraftProfile {
+    pub title: String,
+    /// The .air file from the last AircraftLoaded event, if one came
+    pub air_path: Option<String>,
+    pub family: AircraftFamily,
+    /// Add-on whose cabin call buttons we know, if any
+    pub call_profile: Option<&'static CallProfile>,
+}
+
+impl AircraftProfile {
+    /// Picks the profile by title, falling back to the .air path for the call profile
+    /// of liveries whose title doesn't name the add-on.
+    pub fn select(title: &str, air_path: Option<&str>) -> Self {
+        AircraftProfile {
+            title: title.to_string(),
+            air_path: air_path.map(str::to_string),
+            family: AircraftFamily::from_title(title),
+            call_profile: interphone::profile_for(title).or_else(|| air_path.and_then(interphone::profile_for)),
+        }
+    }
+
+    /// Whether this profile was picked for the given title and .air path.
+    pub fn is_for(&self, title: &str, air_path: Option<&str>) -> bool {
+        self.title == title && self.air_path.as_deref() == air_path
+    }
+
+    /// The cabin call L-vars to watch in this aircraft.
+    pub fn call_definition(&self, custom_lvar: Option<&str>) -> CallDefinition {
+        interphone::call_definition(self.call_profile, custom_lvar)
+    }
+
+    /// Payload of the `aircraft-changed` event.
+    pub fn report(&self) -> Value {
+        json!({
+            "title": self.title,
+            "airPath": self.air_path,
+            "family": self.family.as_str(),
+            "callProfile": self.call_profile.map(|profile| profile.name)
+        })
+    }
+}
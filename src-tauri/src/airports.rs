@@ -0,0 +1,391 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+use tauri::{ AppHandle, Manager, State };
+
+use crate::flight_context::FlightContextState;
+use crate::flight_phase::FlightPhase;
+
+/// Default location of the airport database, in the app data dir.
+pub const AIRPORTS_FILE: &str = "airports.csv";
+
+const EARTH_RADIUS_NM: f64 = 3440.065;
+// Nearest-airport searches give up this many 1° grid cells out
+const MAX_SEARCH_RING: i32 = 10;
+// The aircraft counts as at an airport within this distance
+const AT_AIRPORT_NM: f64 = 5.0;
+
+/// OurAirports `type`s we keep; heliports, seaplane bases and closed fields are skipped.
+const AIRPORT_TYPES: [&str; 3] = ["large_airport", "medium_airport", "small_airport"];
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Airport {
+    /// ICAO code where the airport has one, else the OurAirports ident
+    pub ident: String,
+    pub iata: Option<String>,
+    pub name: String,
+    pub kind: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation_ft: Option<f64>,
+    /// ISO 3166-1 alpha-2
+    pub country: String,
+    /// ISO 3166-2, e.g. CA-QC
+    pub region: Option<String>,
+    pub municipality: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NearbyAirport {
+    #[serde(flatten)]
+    pub airport: Airport,
+    pub distance_nm: f64,
+}
+
+/// Great-circle distance in nautical miles.
+pub fn distance_nm(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_NM * a.sqrt().asin()
+}
+
+/// Splits one CSV line, honouring quotes and `""` escapes.
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn cell(latitude: f64, longitude: f64) -> (i32, i32) {
+    (latitude.floor() as i32, longitude.floor() as i32)
+}
+
+/// Airports with a 1° grid index for nearest-airport lookups.
+pub struct AirportDatabase {
+    airports: Vec<Airport>,
+    grid: HashMap<(i32, i32), Vec<usize>>,
+    by_code: HashMap<String, usize>,
+}
+
+impl AirportDatabase {
+    /// Reads an OurAirports-format `airports.csv`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut lines = text.lines();
+        let header = parse_csv_line(lines.next().unwrap_or_default());
+        let column = |name: &str| header.iter().position(|h| h == name);
+        let required = |name: &str| column(name)
+            .ok_or_else(|| format!("{} has no '{}' column", path.display(), name));
+
+        let ident = required("ident")?;
+        let kind = required("type")?;
+        let name = required("name")?;
+        let latitude = required("latitude_deg")?;
+        let longitude = required("longitude_deg")?;
+        let country = required("iso_country")?;
+        let elevation = column("elevation_ft");
+        let region = column("iso_region");
+        let municipality = column("municipality");
+        let iata = column("iata_code");
+        // Newer exports have `icao_code`; older ones only `gps_code`
+        let icao = column("icao_code").or(column("gps_code"));
+
+        let mut database = AirportDatabase {
+            airports: Vec::new(),
+            grid: HashMap::new(),
+            by_code: HashMap::new(),
+        };
+        for line in lines {
+            let fields = parse_csv_line(line);
+            let get = |index: Option<usize>| index
+                .and_then(|i| fields.get(i))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+                .map(str::to_string);
+
+            if !get(Some(kind)).is_some_and(|k| AIRPORT_TYPES.contains(&k.as_str())) {
+                continue;
+            }
+            let (Some(lat), Some(lon)) = (
+                get(Some(latitude)).and_then(|v| v.parse::<f64>().ok()),
+                get(Some(longitude)).and_then(|v| v.parse::<f64>().ok()),
+            ) else {
+                continue;
+            };
+            let Some(ident) = get(icao).filter(|code| code.len() == 4).or(get(Some(ident))) else {
+                continue;
+            };
+
+            let airport = Airport {
+                ident,
+                iata: get(iata),
+                name: get(Some(name)).unwrap_or_default(),
+                kind: get(Some(kind)).unwrap_or_default(),
+                latitude: lat,
+                longitude: lon,
+                elevation_ft: get(elevation).and_then(|v| v.parse().ok()),
+                country: get(Some(country)).unwrap_or_default(),
+                region: get(region),
+                municipality: get(municipality),
+            };
+            let index = database.airports.len();
+            database.grid.entry(cell(lat, lon)).or_default().push(index);
+            database.by_code.insert(airport.ident.to_ascii_uppercase(), index);
+            if let Some(iata) = &airport.iata {
+                database.by_code.entry(iata.to_ascii_uppercase()).or_insert(index);
+            }
+            database.airports.push(airport);
+        }
+
+        if database.airports.is_empty() {
+            return Err(format!("No airports found in {}", path.display()));
+        }
+        Ok(database)
+    }
+
+    pub fn airport_count(&self) -> usize {
+        self.airports.len()
+    }
+
+    /// Looks an airport up by ICAO (or ident) or IATA code.
+    pub fn find(&self, code: &str) -> Option<&Airport> {
+        self.by_code.get(&code.to_ascii_uppercase()).map(|&index| &self.airports[index])
+    }
+
+    /// The closest airport, searching the grid outward ring by ring.
+    pub fn nearest(&self, latitude: f64, longitude: f64) -> Option<NearbyAirport> {
+        let (row, col) = cell(latitude, longitude);
+        let mut best: Option<(usize, f64)> = None;
+
+        for ring in 0..=MAX_SEARCH_RING {
+            // Everything in this ring is at least (ring - 1) cells away; a cell of longitude
+            // shrinks towards the poles
+            if let Some((_, best_distance)) = best {
+                let narrowest = (latitude.abs() + ring as f64).min(89.0).to_radians().cos();
+                if (ring - 1) as f64 * 60.0 * narrowest > best_distance {
+                    break;
+                }
+            }
+
+            for d_row in -ring..=ring {
+                for d_col in -ring..=ring {
+                    if d_row.abs() != ring && d_col.abs() != ring {
+                        continue;
+                    }
+                    // Wrap around the antimeridian
+                    let key = (row + d_row, (col + d_col + 180).rem_euclid(360) - 180);
+                    for &index in self.grid.get(&key).into_iter().flatten() {
+                        let airport = &self.airports[index];
+                        let distance = distance_nm(latitude, longitude, airport.latitude, airport.longitude);
+                        if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+                            best = Some((index, distance));
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(index, distance_nm)| NearbyAirport {
+            airport: self.airports[index].clone(),
+            distance_nm,
+        })
+    }
+}
+
+pub struct AirportState {
+    pub database: Mutex<Option<Arc<AirportDatabase>>>,
+}
+
+impl AirportState {
+    pub fn new() -> Self {
+        AirportState {
+            database: Mutex::new(None),
+        }
+    }
+
+    pub fn get(&self) -> Option<Arc<AirportDatabase>> {
+        self.database.lock().unwrap().clone()
+    }
+}
+
+fn default_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir()
+        .map(|dir| dir.join(AIRPORTS_FILE))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+/// Loads the database from the app data dir, if one was put there.
+pub fn load_default(app: &AppHandle) {
+    let Ok(path) = default_path(app) else {
+        return;
+    };
+    if !path.is_file() {
+        println!("No airport database at {}", path.display());
+        return;
+    }
+    match AirportDatabase::load(&path) {
+        Ok(database) => {
+            println!("Loaded {} airports from {}", database.airport_count(), path.display());
+            *app.state::<Arc<AirportState>>().database.lock().unwrap() = Some(Arc::new(database));
+        },
+        Err(e) => println!("Failed to load airport database: {}", e),
+    }
+}
+
+/// Updates the nearest airport and the departure/arrival airports of the flight context.
+/// OFP airports win, then those of the sim's flight plan; otherwise the airport we're
+/// at before takeoff is the departure and the one we land at the arrival.
+pub fn update_context(
+    context: &FlightContextState,
+    database: &AirportDatabase,
+    latitude: f64,
+    longitude: f64,
+    phase: FlightPhase
+) {
+    let nearest = database.nearest(latitude, longitude);
+    let at_airport = nearest.as_ref()
+        .filter(|nearby| nearby.distance_nm <= AT_AIRPORT_NM)
+        .map(|nearby| nearby.airport.clone());
+
+    let mut context = context.context.lock().unwrap();
+    let (origin, destination) = match (&context.ofp, &context.sim_flight_plan) {
+        (Some(ofp), _) => (Some(ofp.origin.icao.clone()), Some(ofp.destination.icao.clone())),
+        (None, Some(plan)) => (plan.origin.clone(), plan.destination.clone()),
+        (None, None) => (None, None),
+    };
+
+    match origin.and_then(|code| database.find(&code)) {
+        Some(airport) => context.departure_airport = Some(airport.clone()),
+        None if matches!(phase, FlightPhase::Preflight | FlightPhase::TaxiOut) && at_airport.is_some() => {
+            context.departure_airport = at_airport.clone();
+        },
+        None => {},
+    }
+    match destination.and_then(|code| database.find(&code)) {
+        Some(airport) => context.arrival_airport = Some(airport.clone()),
+        None if matches!(phase, FlightPhase::Landing | FlightPhase::TaxiIn | FlightPhase::Arrived) && at_airport.is_some() => {
+            context.arrival_airport = at_airport;
+        },
+        None => {},
+    }
+    context.nearest_airport = nearest;
+}
+
+/// Loads an OurAirports-format airports.csv, or the one in the app data dir when `path`
+/// is None. Returns the number of airports.
+#[tauri::command]
+pub fn load_airport_database(app: AppHandle, state: State<Arc<AirportState>>, path: Option<String>) -> Result<usize, String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => default_path(&app)?,
+    };
+    let database = AirportDatabase::load(&path)?;
+    let count = database.airport_count();
+    println!("Loaded {} airports from {}", count, path.display());
+    *state.database.lock().unwrap() = Some(Arc::new(database));
+    Ok(count)
+}
+
+/// The closest airport to a position.
+#[tauri::command]
+pub fn get_nearest_airport(state: State<Arc<AirportState>>, latitude: f64, longitude: f64) -> Result<Option<NearbyAirport>, String> {
+    let database = state.get().ok_or_else(|| "No airport database loaded".to_string())?;
+    Ok(database.nearest(latitude, longitude))
+}
+
+#[tauri::command]
+pub fn find_airport(state: State<Arc<AirportState>>, code: String) -> Result<Option<Airport>, String> {
+    let database = state.get().ok_or_else(|| "No airport database loaded".to_string())?;
+    Ok(database.find(&code).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(airports: &[(&str, f64, f64)]) -> AirportDatabase {
+        let mut database = AirportDatabase {
+            airports: Vec::new(),
+            grid: HashMap::new(),
+            by_code: HashMap::new(),
+        };
+        for &(ident, latitude, longitude) in airports {
+            let index = database.airports.len();
+            database.grid.entry(cell(latitude, longitude)).or_default().push(index);
+            database.by_code.insert(ident.to_string(), index);
+            database.airports.push(Airport {
+                ident: ident.to_string(),
+                iata: None,
+                name: ident.to_string(),
+                kind: "large_airport".to_string(),
+                latitude,
+                longitude,
+                elevation_ft: None,
+                country: String::new(),
+                region: None,
+                municipality: None,
+            });
+        }
+        database
+    }
+
+    fn nearest_ident(database: &AirportDatabase, latitude: f64, longitude: f64) -> Option<String> {
+        database.nearest(latitude, longitude).map(|nearby| nearby.airport.ident)
+    }
+
+    #[test]
+    fn csv_fields_keep_quoted_commas_and_escaped_quotes() {
+        assert_eq!(
+            parse_csv_line(r#"1,"EGLL","London Heathrow, ""LHR""",,51.47"#),
+            vec!["1", "EGLL", r#"London Heathrow, "LHR""#, "", "51.47"]
+        );
+        assert_eq!(parse_csv_line(r#""""#), vec![""]);
+        assert_eq!(parse_csv_line("a,"), vec!["a", ""]);
+    }
+
+    #[test]
+    fn nearest_wraps_around_the_antimeridian() {
+        let database = database(&[("NZCI", -43.8, -176.5), ("NZNV", -46.4, 168.3), ("WEST", -43.8, 175.0)]);
+        // Just west of the antimeridian, the airport across it is closer than the one to the west
+        assert_eq!(nearest_ident(&database, -43.8, 179.9).as_deref(), Some("NZCI"));
+        assert_eq!(nearest_ident(&database, -43.8, -179.9).as_deref(), Some("NZCI"));
+    }
+
+    #[test]
+    fn ring_search_keeps_going_while_narrow_cells_can_be_closer() {
+        // At 60°N a degree of longitude is about 30nm, so the airport three cells east
+        // is closer than the one two cells north
+        let database = database(&[("EAST", 60.5, 3.9), ("NRTH", 62.2, 0.5)]);
+        let nearby = database.nearest(60.5, 0.5).unwrap();
+        assert_eq!(nearby.airport.ident, "EAST");
+        assert!((nearby.distance_nm - 100.4).abs() < 1.0, "{}", nearby.distance_nm);
+    }
+
+    #[test]
+    fn nearest_gives_up_past_the_search_radius() {
+        let database = database(&[("FAR", 0.5, 0.5)]);
+        assert_eq!(nearest_ident(&database, 0.5, MAX_SEARCH_RING as f64 + 1.5), None);
+        assert_eq!(nearest_ident(&database, 0.5, 5.5).as_deref(), Some("FAR"));
+    }
+}
@@ -0,0 +1,194 @@
+use serde::{ Deserialize, Serialize };
+use serde_json::{ json, Value };
+
+/// Trigger events the frontend has announcements for; others play `announcements/<event>`
+/// from the soundpack.
+pub const FRONTEND_EVENTS: [&str; 3] = ["10k_feet", "arrive_soon", "landing_soon"];
+
+// The landing lights announcements wait for the descent through this height above the
+// destination, whatever triggers are configured
+const TEN_THOUSAND_FT: f64 = 10000.0;
+
+/// A trigger crossed in the other direction re-arms once it is this far back past its
+/// threshold, so hovering around a level doesn't repeat the announcement.
+const REARM_FT: f64 = 1000.0;
+
+/// What a trigger's threshold is measured against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AltitudeReference {
+    /// Indicated altitude above mean sea level
+    Msl,
+    /// Radio height above whatever is below the aircraft
+    Agl,
+    /// Height above the departure field; MSL while the field elevation is unknown
+    AboveDeparture,
+    /// Height above the destination field; MSL while the field elevation is unknown
+    AboveDestination,
+    /// Pressure altitude above the transition altitude, MSL below it
+    FlightLevel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Climbing,
+    Descending,
+}
+
+/// Fires an audio event when the aircraft crosses `threshold_ft` in `direction`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AltitudeTrigger {
+    /// The `audio-event` type to emit
+    pub event: String,
+    pub reference: AltitudeReference,
+    pub threshold_ft: f64,
+    pub direction: Direction,
+}
+
+impl AltitudeTrigger {
+    fn new(event: &str, reference: AltitudeReference, threshold_ft: f64, direction: Direction) -> Self {
+        AltitudeTrigger {
+            event: event.to_string(),
+            reference,
+            threshold_ft,
+            direction,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AltitudeTriggerSettings {
+    pub enabled: bool,
+    /// Above this MSL altitude `FlightLevel` triggers use pressure altitude
+    pub transition_altitude_ft: f64,
+    pub triggers: Vec<AltitudeTrigger>,
+}
+
+impl Default for AltitudeTriggerSettings {
+    /// The announcements that used to fire on raw MSL altitude, measured from the field
+    /// they relate to so high airports like La Paz or Denver don't misfire.
+    fn default() -> Self {
+        AltitudeTriggerSettings {
+            enabled: true,
+            transition_altitude_ft: 18000.0,
+            triggers: vec![
+                AltitudeTrigger::new("10k_feet", AltitudeReference::AboveDeparture, 10000.0, Direction::Climbing),
+                AltitudeTrigger::new("arrive_soon", AltitudeReference::AboveDestination, 18000.0, Direction::Descending),
+                AltitudeTrigger::new("landing_soon", AltitudeReference::AboveDestination, 10000.0, Direction::Descending),
+            ],
+        }
+    }
+}
+
+/// One reading of the aircraft's altitudes and the elevations of the flight's fields.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AltitudeSample {
+    pub msl_ft: f64,
+    pub agl_ft: f64,
+    pub pressure_altitude_ft: f64,
+    pub departure_elevation_ft: Option<f64>,
+    pub destination_elevation_ft: Option<f64>,
+}
+
+impl AltitudeSample {
+    pub fn height(&self, reference: AltitudeReference, transition_altitude_ft: f64) -> f64 {
+        match reference {
+            AltitudeReference::Msl => self.msl_ft,
+            AltitudeReference::Agl => self.agl_ft,
+            AltitudeReference::AboveDeparture => self.msl_ft - self.departure_elevation_ft.unwrap_or(0.0),
+            AltitudeReference::AboveDestination => self.msl_ft - self.destination_elevation_ft.unwrap_or(0.0),
+            AltitudeReference::FlightLevel if self.msl_ft >= transition_altitude_ft => self.pressure_altitude_ft,
+            AltitudeReference::FlightLevel => self.msl_ft,
+        }
+    }
+
+    /// The altitudes in each reference for the `simconnect-data` payload.
+    pub fn report(&self, transition_altitude_ft: f64) -> Value {
+        let flight_level = (self.msl_ft >= transition_altitude_ft)
+            .then(|| (self.pressure_altitude_ft / 100.0).round());
+        json!({
+            "msl": self.msl_ft.round(),
+            "agl": self.agl_ft.round(),
+            "aboveDeparture": self.departure_elevation_ft.map(|elevation| (self.msl_ft - elevation).round()),
+            "aboveDestination": self.destination_elevation_ft.map(|elevation| (self.msl_ft - elevation).round()),
+            "flightLevel": flight_level,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TriggerState {
+    last_height: Option<f64>,
+    armed: bool,
+}
+
+/// Tracks which altitude triggers have fired.
+pub struct AltitudeTriggers {
+    states: Vec<TriggerState>,
+    last_sample: Option<AltitudeSample>,
+    descended_through_10k: bool,
+}
+
+impl AltitudeTriggers {
+    pub fn new() -> Self {
+        AltitudeTriggers {
+            states: Vec::new(),
+            last_sample: None,
+            descended_through_10k: false,
+        }
+    }
+
+    /// Whether the aircraft has descended through 10,000 ft above the destination since it
+    /// last climbed through 10,000 ft above the departure field.
+    pub fn descended_through_10k(&self) -> bool {
+        self.descended_through_10k
+    }
+
+    /// Whether the trigger for `event` has fired and not yet re-armed.
+    pub fn has_fired(&self, settings: &AltitudeTriggerSettings, event: &str) -> bool {
+        settings.triggers.iter().zip(&self.states)
+            .any(|(trigger, state)| trigger.event == event && !state.armed)
+    }
+
+    /// Feeds a sample and returns the triggers crossed since the last one. The first
+    /// sample after start or a settings change only records the heights.
+    pub fn update<'a>(&mut self, settings: &'a AltitudeTriggerSettings, sample: &AltitudeSample) -> Vec<&'a AltitudeTrigger> {
+        if self.states.len() != settings.triggers.len() {
+            self.states = vec![TriggerState { last_height: None, armed: true }; settings.triggers.len()];
+        }
+
+        if let Some(last) = self.last_sample {
+            let destination = |sample: &AltitudeSample| sample.height(AltitudeReference::AboveDestination, settings.transition_altitude_ft);
+            let departure = |sample: &AltitudeSample| sample.height(AltitudeReference::AboveDeparture, settings.transition_altitude_ft);
+            if destination(&last) > TEN_THOUSAND_FT && destination(sample) <= TEN_THOUSAND_FT {
+                self.descended_through_10k = true;
+            } else if departure(&last) < TEN_THOUSAND_FT && departure(sample) >= TEN_THOUSAND_FT {
+                self.descended_through_10k = false;
+            }
+        }
+        self.last_sample = Some(*sample);
+
+        let mut fired = Vec::new();
+        for (trigger, state) in settings.triggers.iter().zip(self.states.iter_mut()) {
+            let height = sample.height(trigger.reference, settings.transition_altitude_ft);
+            let threshold = trigger.threshold_ft;
+            if let Some(last) = state.last_height {
+                let (crossed, rearm) = match trigger.direction {
+                    Direction::Climbing => (last < threshold && height >= threshold, height < threshold - REARM_FT),
+                    Direction::Descending => (last > threshold && height <= threshold, height > threshold + REARM_FT),
+                };
+                if crossed && state.armed && settings.enabled {
+                    state.armed = false;
+                    fired.push(trigger);
+                } else if rearm {
+                    state.armed = true;
+                }
+            }
+            state.last_height = Some(height);
+        }
+        fired
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::audio_engine::ListenerZone;
+use crate::flight_phase::FlightPhase;
+
+/// Looping background layers, each an `ambience/<name>` clip in the soundpack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmbienceLayer {
+    Chatter,
+    Bins,
+    Galley,
+    Hum,
+}
+
+pub const LAYERS: [AmbienceLayer; 4] = [
+    AmbienceLayer::Chatter,
+    AmbienceLayer::Bins,
+    AmbienceLayer::Galley,
+    AmbienceLayer::Hum,
+];
+
+impl AmbienceLayer {
+    pub fn clip_key(&self) -> &'static str {
+        match self {
+            AmbienceLayer::Chatter => "ambience/chatter",
+            AmbienceLayer::Bins => "ambience/bins",
+            AmbienceLayer::Galley => "ambience/galley",
+            AmbienceLayer::Hum => "ambience/hum",
+        }
+    }
+}
+
+// Without GSX, assume boarding takes this long from jetway attach
+const ASSUMED_BOARDING: Duration = Duration::from_secs(20 * 60);
+
+/// How far boarding has got, 0..1. Uses the GSX passenger counts when GSX is boarding,
+/// otherwise the time since the jetway attached.
+pub fn boarding_progress(gsx_passengers: Option<(f64, f64)>, since_jetway: Option<Duration>) -> f32 {
+    match (gsx_passengers, since_jetway) {
+        (Some((boarded, total)), _) if total > 0.0 => (boarded / total).clamp(0.0, 1.0) as f32,
+        (_, Some(since)) => (since.as_secs_f32() / ASSUMED_BOARDING.as_secs_f32()).min(1.0),
+        _ => 0.0,
+    }
+}
+
+/// Target level of each layer (in `LAYERS` order) before the master volume.
+pub fn levels(phase: FlightPhase, boarding_progress: f32, zone: ListenerZone) -> [f32; 4] {
+    let [chatter, bins, galley, hum] = match phase {
+        // Passengers fill up the cabin; bin activity peaks halfway through boarding
+        FlightPhase::Preflight => {
+            let p = boarding_progress;
+            [0.15 + 0.6 * p, 4.0 * p * (1.0 - p), 0.4, 0.5]
+        },
+        FlightPhase::TaxiOut => [0.35, 0.05, 0.15, 0.5],
+        FlightPhase::Takeoff | FlightPhase::Landing => [0.1, 0.0, 0.0, 0.3],
+        FlightPhase::Climb | FlightPhase::Descent | FlightPhase::Approach => [0.3, 0.0, 0.1, 0.6],
+        FlightPhase::Cruise => [0.5, 0.1, 0.6, 0.7],
+        // Everyone stands up and grabs their bags
+        FlightPhase::TaxiIn => [0.6, 0.3, 0.2, 0.5],
+        FlightPhase::Arrived => [0.75, 0.8, 0.2, 0.5],
+    };
+
+    let zone_gain = match zone {
+        ListenerZone::Cabin => 1.0,
+        ListenerZone::Jetway => 0.5,
+        ListenerZone::Cockpit => 0.25,
+        ListenerZone::Outside => 0.1,
+    };
+    [chatter, bins, galley, hum].map(|level| level.clamp(0.0, 1.0) * zone_gain)
+}
@@ -0,0 +1,264 @@
+//! Builds announcements by concatenating pre-recorded clips from a soundpack.
+//!
+//! A template is a whitespace-separated list of tokens:
+//!
+//! - `phrases/flight_time_will_be` - a clip key, played as-is
+//! - `{number:var}` - the value spoken as a number ("two hundred fifteen", "minus three")
+//! - `{digits:var}` - the value spoken digit by digit ("three five zero")
+//! - `{flight_level:var}` - "flight level" followed by the digits
+//! - `{duration:var}` - minutes spoken as "two hours fifteen minutes"
+//! - `{clip:var}` - the variable holds a clip key, e.g. `cities/lemd`
+//! - `[pause:300]` - 300 ms of silence
+//!
+//! Example: `phrases/cruising_at {flight_level:cruise_level} [pause:200] phrases/flight_time_will_be {duration:block_time}`
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use rodio::{ buffer::SamplesBuffer, Source };
+use serde::Deserialize;
+use tauri::State;
+
+use crate::audio_engine::AudioEngineState;
+use crate::flight_context::FlightContextState;
+use crate::languages;
+use crate::soundpack::{ Soundpack, SoundpackState, CHANNELS, SAMPLE_RATE };
+
+/// One step of an expanded template.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Segment {
+    Clip(String),
+    Pause(u32),
+}
+
+/// Spacing between clips when rendering.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RenderOptions {
+    /// Silence inserted between consecutive clips.
+    pub gap_ms: u32,
+    /// Overlap between consecutive clips; takes precedence over `gap_ms` when non-zero.
+    pub crossfade_ms: u32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            gap_ms: 60,
+            crossfade_ms: 0,
+        }
+    }
+}
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    "ten", "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen",
+    "seventeen", "eighteen", "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Spells a number as `numbers/...` clip keys, e.g. 215 -> two, hundred, fifteen.
+pub fn number_clips(value: u64) -> Vec<String> {
+    let mut words = Vec::new();
+    push_number_words(value, &mut words);
+    words.into_iter().map(|w| format!("numbers/{}", w)).collect()
+}
+
+fn push_number_words(value: u64, words: &mut Vec<&'static str>) {
+    if value >= 1000 {
+        push_number_words(value / 1000, words);
+        words.push("thousand");
+        match value % 1000 {
+            0 => {},
+            rest => push_number_words(rest, words),
+        }
+    } else if value >= 100 {
+        words.push(ONES[(value / 100) as usize]);
+        words.push("hundred");
+        match value % 100 {
+            0 => {},
+            rest => push_number_words(rest, words),
+        }
+    } else if value >= 20 {
+        words.push(TENS[(value / 10) as usize]);
+        match value % 10 {
+            0 => {},
+            rest => words.push(ONES[rest as usize]),
+        }
+    } else {
+        words.push(ONES[value as usize]);
+    }
+}
+
+/// Spells each digit of `value`, e.g. "350" -> three, five, zero.
+pub fn digit_clips(value: &str) -> Vec<String> {
+    value.chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| format!("numbers/{}", ONES[d as usize]))
+        .collect()
+}
+
+/// Speaks a duration in minutes as hours and minutes.
+pub fn duration_clips(total_minutes: u64) -> Vec<String> {
+    let hours = total_minutes / 60;
+    let minutes = total_minutes % 60;
+    let mut clips = Vec::new();
+    if hours > 0 {
+        clips.extend(number_clips(hours));
+        clips.push(if hours == 1 { "units/hour" } else { "units/hours" }.to_string());
+    }
+    if minutes > 0 || hours == 0 {
+        clips.extend(number_clips(minutes));
+        clips.push(if minutes == 1 { "units/minute" } else { "units/minutes" }.to_string());
+    }
+    clips
+}
+
+/// Expands a template into clips and pauses using `variables`.
+pub fn expand(template: &str, variables: &HashMap<String, String>) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+
+    for token in template.split_whitespace() {
+        if let Some(inner) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            let ms = inner.strip_prefix("pause:")
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| format!("Invalid directive '{}'", token))?;
+            segments.push(Segment::Pause(ms));
+        } else if let Some(inner) = token.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+            let (kind, name) = inner.split_once(':')
+                .ok_or_else(|| format!("Invalid placeholder '{}'", token))?;
+            let value = variables.get(name)
+                .ok_or_else(|| format!("Missing template variable '{}'", name))?;
+            let number = || value.trim().parse::<f64>()
+                .map(|v| v.max(0.0).round() as u64)
+                .map_err(|_| format!("Variable '{}' is not a number: {}", name, value));
+
+            let clips = match kind {
+                "number" => {
+                    let value = value.trim();
+                    let magnitude = value.trim_start_matches('-').parse::<f64>()
+                        .map(|v| v.round() as u64)
+                        .map_err(|_| format!("Variable '{}' is not a number: {}", name, value))?;
+                    let mut clips = Vec::new();
+                    if value.starts_with('-') && magnitude > 0 {
+                        clips.push("numbers/minus".to_string());
+                    }
+                    clips.extend(number_clips(magnitude));
+                    clips
+                },
+                "digits" => digit_clips(value),
+                "flight_level" => {
+                    let mut clips = vec!["phrases/flight_level".to_string()];
+                    clips.extend(digit_clips(&number()?.to_string()));
+                    clips
+                },
+                "duration" => duration_clips(number()?),
+                "clip" => vec![value.clone()],
+                other => return Err(format!("Unknown placeholder kind '{}'", other)),
+            };
+            segments.extend(clips.into_iter().map(Segment::Clip));
+        } else {
+            segments.push(Segment::Clip(token.to_string()));
+        }
+    }
+
+    Ok(segments)
+}
+
+fn ms_to_samples(ms: u32) -> usize {
+    (SAMPLE_RATE as usize * ms as usize / 1000) * CHANNELS as usize
+}
+
+/// Decodes and joins the segments into one interleaved buffer.
+pub fn render_samples(
+    soundpack: &Soundpack,
+    segments: &[Segment],
+    options: RenderOptions
+) -> Result<Vec<f32>, String> {
+    let channels = CHANNELS as usize;
+    let mut output: Vec<f32> = Vec::new();
+    // Whether the previous segment was a clip we may join onto
+    let mut after_clip = false;
+
+    for segment in segments {
+        match segment {
+            Segment::Pause(ms) => {
+                output.resize(output.len() + ms_to_samples(*ms), 0.0);
+                after_clip = false;
+            },
+            Segment::Clip(key) => {
+                let clip = soundpack.decode(key)?;
+
+                if after_clip && options.crossfade_ms > 0 {
+                    // Overlap-add with linear fades, a whole number of frames long
+                    let overlap = ms_to_samples(options.crossfade_ms)
+                        .min(output.len())
+                        .min(clip.len());
+                    let overlap = overlap - overlap % channels;
+                    let frames = (overlap / channels).max(1) as f32;
+                    let start = output.len() - overlap;
+                    for i in 0..overlap {
+                        let t = (i / channels) as f32 / frames;
+                        output[start + i] = output[start + i] * (1.0 - t) + clip[i] * t;
+                    }
+                    output.extend_from_slice(&clip[overlap..]);
+                } else {
+                    if after_clip {
+                        output.resize(output.len() + ms_to_samples(options.gap_ms), 0.0);
+                    }
+                    output.extend_from_slice(&clip);
+                }
+                after_clip = true;
+            },
+        }
+    }
+
+    Ok(output)
+}
+
+/// Renders a template in each announcement language, one after the other, to a buffer
+/// ready for the native player.
+pub fn render(
+    soundpacks: &[(String, Soundpack)],
+    flight_context: &FlightContextState,
+    template: &str,
+    variables: &HashMap<String, String>,
+    options: RenderOptions
+) -> Result<SamplesBuffer<f32>, String> {
+    let segments = expand(template, variables)?;
+    let samples = languages::render_sequence(soundpacks, flight_context, |soundpack| {
+        render_samples(soundpack, &segments, options)
+    })?;
+    Ok(SamplesBuffer::new(CHANNELS, SAMPLE_RATE, samples))
+}
+
+/// Renders a template with the flight context variables (plus any given) in the flight's
+/// announcement languages and plays it natively through the PA chain of the listener's zone.
+/// Returns the announcement duration in milliseconds.
+#[tauri::command]
+pub fn play_announcement_template(
+    soundpack: State<Arc<SoundpackState>>,
+    engine: State<Arc<AudioEngineState>>,
+    flight_context: State<Arc<FlightContextState>>,
+    template: String,
+    variables: Option<HashMap<String, String>>,
+    options: Option<RenderOptions>,
+    volume: Option<f32>
+) -> Result<u64, String> {
+    let soundpack = soundpack.get()?;
+
+    let mut all_variables = flight_context.snapshot().template_variables();
+    all_variables.extend(variables.unwrap_or_default());
+    let options = options.unwrap_or_default();
+
+    let soundpacks = languages::soundpacks(&soundpack, &flight_context);
+    let buffer = render(&soundpacks, &flight_context, &template, &all_variables, options)?;
+    let duration_ms = buffer.total_duration()
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    println!("Playing templated announcement '{}' ({} ms)", template, duration_ms);
+    engine.play_pa(buffer, volume.unwrap_or(1.0))?;
+    Ok(duration_ms)
+}
@@ -0,0 +1,391 @@
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+use std::time::SystemTime;
+use tauri::{ AppHandle, Manager, State };
+
+use crate::dsp::{ self, Biquad };
+use crate::soundpack::{ self, SoundpackState, CHANNELS, CLIP_EXTENSIONS, SAMPLE_RATE };
+
+/// Analysis results keyed by file hash, in the app data dir.
+const CACHE_FILE: &str = "clip_analysis.json";
+
+// BS.1770 gating: 400ms blocks every 100ms, absolute gate at -70 LUFS, relative gate 10 LU down
+const SEGMENT_MS: u32 = 100;
+const SEGMENTS_PER_BLOCK: usize = 4;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = 10.0;
+// Peak reported for digital silence
+const SILENCE_DBFS: f64 = -144.0;
+
+/// Duration and levels of one audio file.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipAnalysis {
+    pub duration_secs: f64,
+    /// EBU R128 integrated loudness; None for silence
+    pub loudness_lufs: Option<f64>,
+    /// Sample peak
+    pub peak_dbfs: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NormalizationSettings {
+    pub enabled: bool,
+    pub target_lufs: f64,
+    /// Gain is limited so peaks stay below this
+    pub max_peak_dbfs: f64,
+}
+
+impl Default for NormalizationSettings {
+    fn default() -> Self {
+        NormalizationSettings {
+            enabled: true,
+            target_lufs: -18.0,
+            max_peak_dbfs: -1.0,
+        }
+    }
+}
+
+impl NormalizationSettings {
+    /// Linear gain that brings a clip to the target loudness.
+    pub fn gain(&self, analysis: &ClipAnalysis) -> f32 {
+        match analysis.loudness_lufs {
+            Some(loudness) if self.enabled => {
+                let gain_db = (self.target_lufs - loudness).min(self.max_peak_dbfs - analysis.peak_dbfs);
+                dsp::db_to_gain(gain_db as f32)
+            },
+            _ => 1.0,
+        }
+    }
+}
+
+/// The BS.1770 K-weighting filter: a head-related high shelf followed by the RLB high-pass.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f32;
+
+    let (f0, gain_db, q) = (1_681.974_5, 3.999_843_9, 0.707_175_2);
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f32.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_77);
+    let shelf = Biquad::from_coefficients(
+        vh + vb * k / q + k * k, 2.0 * (k * k - vh), vh - vb * k / q + k * k,
+        1.0 + k / q + k * k, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k,
+    );
+
+    let (f0, q) = (38.135_47, 0.500_327);
+    let k = (PI * f0 / rate).tan();
+    let high_pass = Biquad::from_coefficients(
+        1.0, -2.0, 1.0,
+        1.0 + k / q + k * k, 2.0 * (k * k - 1.0), 1.0 - k / q + k * k,
+    );
+
+    [shelf, high_pass]
+}
+
+fn power_to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Measures interleaved samples in one pass.
+pub fn analyze<I: Iterator<Item = f32>>(samples: I, channels: u16, sample_rate: u32) -> ClipAnalysis {
+    let channels = channels.max(1) as usize;
+    let mut filters = vec![k_weighting(sample_rate); channels];
+    let segment_frames = (sample_rate * SEGMENT_MS / 1000) as usize;
+
+    // Mean square per 100ms segment, summed over channels
+    let mut segments: Vec<f64> = Vec::new();
+    let mut segment_sum = 0.0f64;
+    let mut frames = 0usize;
+    let mut peak = 0.0f32;
+
+    for (index, sample) in samples.enumerate() {
+        let channel = index % channels;
+        peak = peak.max(sample.abs());
+        let [shelf, high_pass] = &mut filters[channel];
+        let weighted = high_pass.process(shelf.process(sample)) as f64;
+        segment_sum += weighted * weighted;
+
+        if channel == channels - 1 {
+            frames += 1;
+            if frames.is_multiple_of(segment_frames) {
+                segments.push(segment_sum / segment_frames as f64);
+                segment_sum = 0.0;
+            }
+        }
+    }
+    let leftover = frames % segment_frames;
+    let short_tail = (leftover > 0).then(|| segment_sum / leftover as f64);
+
+    let mut blocks: Vec<f64> = segments.windows(SEGMENTS_PER_BLOCK)
+        .map(|window| window.iter().sum::<f64>() / SEGMENTS_PER_BLOCK as f64)
+        .collect();
+    if blocks.is_empty() && frames > 0 {
+        // Shorter than one block (single words, chimes): measure the whole clip
+        let total = segments.iter().map(|s| s * segment_frames as f64).sum::<f64>()
+            + short_tail.unwrap_or(0.0) * leftover as f64;
+        blocks.push(total / frames as f64);
+    }
+
+    let gated_mean = |threshold: f64| {
+        let gated: Vec<f64> = blocks.iter().copied()
+            .filter(|power| *power > 0.0 && power_to_lufs(*power) > threshold)
+            .collect();
+        (!gated.is_empty()).then(|| gated.iter().sum::<f64>() / gated.len() as f64)
+    };
+    // The relative gate only ever raises the threshold; blocks under the absolute gate stay out
+    let loudness = gated_mean(ABSOLUTE_GATE_LUFS)
+        .and_then(|mean| gated_mean(ABSOLUTE_GATE_LUFS.max(power_to_lufs(mean) - RELATIVE_GATE_LU)))
+        .map(power_to_lufs);
+
+    ClipAnalysis {
+        duration_secs: frames as f64 / sample_rate as f64,
+        loudness_lufs: loudness,
+        peak_dbfs: if peak > 0.0 { 20.0 * (peak as f64).log10() } else { SILENCE_DBFS },
+    }
+}
+
+/// FNV-1a over the file contents, so renamed or moved clips keep their analysis.
+fn hash_file(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    Ok(format!("{:016x}-{}", hash, bytes.len()))
+}
+
+/// Size and modification time a file had when it was last hashed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Result<Self, String> {
+        let metadata = fs::metadata(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Ok(FileStamp { len: metadata.len(), modified: metadata.modified().ok() })
+    }
+}
+
+/// Analyses audio files once and remembers the results across sessions.
+#[derive(Debug, Default)]
+pub struct AssetAnalyzer {
+    cache_path: Mutex<Option<PathBuf>>,
+    cache: Mutex<HashMap<String, ClipAnalysis>>,
+    /// Hashes of files seen this session, so playback doesn't reread unchanged clips
+    hashes: Mutex<HashMap<PathBuf, (FileStamp, String)>>,
+    pub settings: Mutex<NormalizationSettings>,
+}
+
+impl AssetAnalyzer {
+    /// The file's content hash, reused while its size and modification time are unchanged.
+    fn file_hash(&self, path: &Path) -> Result<String, String> {
+        let stamp = FileStamp::of(path)?;
+        if let Some((seen, hash)) = self.hashes.lock().unwrap().get(path) {
+            if *seen == stamp {
+                return Ok(hash.clone());
+            }
+        }
+        let hash = hash_file(path)?;
+        self.hashes.lock().unwrap().insert(path.to_path_buf(), (stamp, hash.clone()));
+        Ok(hash)
+    }
+
+    /// Loads the cache from the app data dir the first time it's called.
+    pub fn open_cache(&self, app: &AppHandle) -> Result<(), String> {
+        let mut cache_path = self.cache_path.lock().unwrap();
+        if cache_path.is_some() {
+            return Ok(());
+        }
+        let dir = app.path().app_data_dir()
+            .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create app data dir: {}", e))?;
+        let path = dir.join(CACHE_FILE);
+
+        if let Ok(json) = fs::read_to_string(&path) {
+            match serde_json::from_str::<HashMap<String, ClipAnalysis>>(&json) {
+                Ok(entries) => self.cache.lock().unwrap().extend(entries),
+                Err(e) => println!("Ignoring invalid {}: {}", path.display(), e),
+            }
+        }
+        *cache_path = Some(path);
+        Ok(())
+    }
+
+    fn save_cache(&self) {
+        let Some(path) = self.cache_path.lock().unwrap().clone() else {
+            return;
+        };
+        let result = serde_json::to_string(&*self.cache.lock().unwrap())
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("Failed to save clip analysis to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Looks the file up by hash, analysing it (from `decoded` if given) when unseen.
+    /// Returns whether it was new.
+    fn lookup(&self, path: &Path, decoded: Option<&[f32]>) -> Result<(ClipAnalysis, bool), String> {
+        let hash = self.file_hash(path)?;
+        if let Some(analysis) = self.cache.lock().unwrap().get(&hash) {
+            return Ok((*analysis, false));
+        }
+
+        let analysis = match decoded {
+            Some(samples) => analyze(samples.iter().copied(), CHANNELS, SAMPLE_RATE),
+            None => analyze(soundpack::stream_file(path)?, CHANNELS, SAMPLE_RATE),
+        };
+        println!("Analysed {}: {:.1}s, {:.1} LUFS, peak {:.1} dBFS",
+                path.display(), analysis.duration_secs,
+                analysis.loudness_lufs.unwrap_or(f64::NEG_INFINITY), analysis.peak_dbfs);
+        self.cache.lock().unwrap().insert(hash, analysis);
+        Ok((analysis, true))
+    }
+
+    /// Analysis of a file at `SAMPLE_RATE`/`CHANNELS`; pass its samples if already decoded.
+    pub fn analysis(&self, path: &Path, decoded: Option<&[f32]>) -> Result<ClipAnalysis, String> {
+        let (analysis, new) = self.lookup(path, decoded)?;
+        if new {
+            self.save_cache();
+        }
+        Ok(analysis)
+    }
+
+    /// Scales decoded samples to the target loudness.
+    pub fn normalize(&self, path: &Path, samples: &mut [f32]) -> Result<ClipAnalysis, String> {
+        let analysis = self.analysis(path, Some(samples))?;
+        let gain = self.settings.lock().unwrap().gain(&analysis);
+        if gain != 1.0 {
+            samples.iter_mut().for_each(|sample| *sample *= gain);
+        }
+        Ok(analysis)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipReport {
+    pub key: String,
+    #[serde(flatten)]
+    pub analysis: ClipAnalysis,
+    /// Gain normalisation will apply, in dB
+    pub gain_db: f64,
+}
+
+fn collect_clips(root: &Path, dir: &Path, keys: &mut Vec<(String, PathBuf)>) {
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_clips(root, &path, keys);
+            continue;
+        }
+        let is_clip = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| CLIP_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()));
+        if let (true, Ok(relative)) = (is_clip, path.with_extension("").strip_prefix(root)) {
+            let key = relative.components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            keys.push((key, path));
+        }
+    }
+}
+
+/// Analyses every clip in the soundpack (cached ones are instant) and reports the results.
+#[tauri::command]
+pub fn analyze_soundpack(app: AppHandle, state: State<Arc<SoundpackState>>) -> Result<Vec<ClipReport>, String> {
+    let soundpack = state.get()?;
+    let analyzer = &state.analyzer;
+    analyzer.open_cache(&app)?;
+
+    let mut clips = Vec::new();
+    collect_clips(&soundpack.root, &soundpack.root, &mut clips);
+    clips.sort();
+
+    let settings = *analyzer.settings.lock().unwrap();
+    let mut reports = Vec::new();
+    let mut any_new = false;
+    for (key, path) in clips {
+        match analyzer.lookup(&path, None) {
+            Ok((analysis, new)) => {
+                any_new |= new;
+                let gain_db = 20.0 * (settings.gain(&analysis) as f64).log10();
+                reports.push(ClipReport { key, analysis, gain_db });
+            },
+            Err(e) => println!("Skipping {}: {}", path.display(), e),
+        }
+    }
+    if any_new {
+        analyzer.save_cache();
+    }
+    Ok(reports)
+}
+
+#[tauri::command]
+pub fn set_loudness_normalization(state: State<Arc<SoundpackState>>, settings: NormalizationSettings) {
+    *state.analyzer.settings.lock().unwrap() = settings;
+}
+
+#[tauri::command]
+pub fn get_loudness_normalization(state: State<Arc<SoundpackState>>) -> NormalizationSettings {
+    *state.analyzer.settings.lock().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48_000;
+
+    /// Stereo 997 Hz sine with its peak at `peak_dbfs`; by BS.1770 it reads the same in LUFS.
+    fn sine(peak_dbfs: f64, secs: f64) -> Vec<f32> {
+        let amplitude = 10f64.powf(peak_dbfs / 20.0);
+        (0..(secs * RATE as f64) as usize)
+            .flat_map(|n| {
+                let sample = (amplitude * (2.0 * std::f64::consts::PI * 997.0 * n as f64 / RATE as f64).sin()) as f32;
+                [sample, sample]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sine_reads_its_level_after_k_weighting() {
+        let analysis = analyze(sine(-23.0, 5.0).into_iter(), 2, RATE);
+        let loudness = analysis.loudness_lufs.unwrap();
+        assert!((loudness + 23.0).abs() < 0.2, "{}", loudness);
+        assert!((analysis.peak_dbfs + 23.0).abs() < 0.1);
+        assert!((analysis.duration_secs - 5.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn quiet_passages_are_gated_out() {
+        // The quiet tail is within 10 LU of the tone but under the absolute gate
+        let mut samples = sine(-62.0, 3.0);
+        samples.extend(sine(-71.0, 20.0));
+        let loudness = analyze(samples.into_iter(), 2, RATE).loudness_lufs.unwrap();
+        assert!((loudness + 62.0).abs() < 0.5, "{}", loudness);
+
+        // Far under the tone, the relative gate drops it
+        let mut samples = sine(-20.0, 3.0);
+        samples.extend(sine(-40.0, 20.0));
+        let loudness = analyze(samples.into_iter(), 2, RATE).loudness_lufs.unwrap();
+        assert!((loudness + 20.0).abs() < 0.5, "{}", loudness);
+    }
+
+    #[test]
+    fn silence_has_no_loudness() {
+        let analysis = analyze(std::iter::repeat_n(0.0, RATE as usize * 2), 2, RATE);
+        assert_eq!(analysis.loudness_lufs, None);
+        assert_eq!(analysis.peak_dbfs, SILENCE_DBFS);
+    }
+}
@@ -0,0 +1,220 @@
+use std::sync::mpsc::{ self, Receiver, Sender };
+use std::sync::{ Arc, Mutex };
+use std::thread;
+use std::time::{ Duration, Instant };
+use rodio::{ buffer::SamplesBuffer, OutputStream, Source };
+use serde::Deserialize;
+use tauri::State;
+
+use crate::acoustics::{ self, AcousticProfile, ZoneAcoustics };
+use crate::ambience;
+use crate::flight_phase::FlightPhase;
+use crate::mixer::{ Bus, Mixer, MixerSettings };
+
+/// Where the listener is, as reported by the frontend's camera zone tracking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenerZone {
+    Outside,
+    Jetway,
+    Cabin,
+    Cockpit,
+}
+
+impl ListenerZone {
+    /// Cabin PA loudness in this zone; matches the frontend's zone volumes.
+    pub fn gain(&self) -> f32 {
+        match self {
+            ListenerZone::Outside => 0.45,
+            ListenerZone::Jetway => 0.792,
+            ListenerZone::Cabin => 0.693,
+            ListenerZone::Cockpit => 0.65,
+        }
+    }
+}
+
+pub enum AudioCommand {
+    Play { source: Box<dyn Source<Item = f32> + Send>, bus: Bus, volume: f32 },
+    StopAll,
+    /// Replaces the music, or stops it with None
+    SetMusic(Option<Box<dyn Source<Item = f32> + Send>>),
+    /// Replaces the ambience loops, one per layer (None when the soundpack lacks it)
+    SetAmbience(Vec<Option<Box<dyn Source<Item = f32> + Send>>>),
+    AmbienceLevels([f32; 4]),
+    /// Announcements playing outside the engine (the webview) that the other buses duck under
+    ExternalDuck(bool),
+}
+
+// How often the audio thread updates ducking and fades
+const TICK: Duration = Duration::from_millis(50);
+
+/// Native playback through rodio. The output stream isn't `Send`, so it lives on
+/// its own thread and is driven over a channel.
+pub struct AudioEngineState {
+    sender: Mutex<Option<Sender<AudioCommand>>>,
+    pub zone: Mutex<ListenerZone>,
+    pub cockpit_door_closed: Mutex<bool>,
+    /// PA acoustics per zone, from the soundpack or set at runtime
+    pub acoustics: Mutex<ZoneAcoustics>,
+    /// Bus volumes and ducking, shared with the audio thread
+    pub mixer: Arc<Mutex<MixerSettings>>,
+    // Whether the soundpack has ambience, so level updates don't start the engine for nothing
+    ambience_loaded: Mutex<bool>,
+}
+
+impl AudioEngineState {
+    pub fn new() -> Self {
+        AudioEngineState {
+            sender: Mutex::new(None),
+            zone: Mutex::new(ListenerZone::Cabin),
+            cockpit_door_closed: Mutex::new(true),
+            acoustics: Mutex::new(ZoneAcoustics::default()),
+            mixer: Arc::new(Mutex::new(MixerSettings::default())),
+            ambience_loaded: Mutex::new(false),
+        }
+    }
+
+    /// Sends a command to the audio thread, starting it on first use.
+    pub fn send(&self, command: AudioCommand) -> Result<(), String> {
+        let mut sender = self.sender.lock().unwrap();
+        if sender.is_none() {
+            let (tx, rx) = mpsc::channel();
+            let settings = self.mixer.clone();
+            thread::spawn(move || run_audio_thread(rx, settings));
+            *sender = Some(tx);
+        }
+
+        let result = sender.as_ref().unwrap().send(command);
+        if result.is_err() {
+            // The audio thread died (e.g. no output device); retry on the next call
+            *sender = None;
+            return Err("Native audio output is unavailable".to_string());
+        }
+        Ok(())
+    }
+
+    /// Plays a sound on `bus`; the buses below it duck until it ends.
+    pub fn play(&self, buffer: SamplesBuffer<f32>, bus: Bus, volume: f32) -> Result<(), String> {
+        self.send(AudioCommand::Play { source: Box::new(buffer), bus, volume })
+    }
+
+    /// Plays a cabin PA through the speaker and room chain of the listener's zone.
+    pub fn play_pa(&self, buffer: SamplesBuffer<f32>, volume: f32) -> Result<(), String> {
+        let zone = *self.zone.lock().unwrap();
+        let door_closed = *self.cockpit_door_closed.lock().unwrap();
+        let profile = self.acoustics.lock().unwrap().profile(zone, door_closed);
+        self.send(AudioCommand::Play {
+            source: acoustics::apply(buffer, &profile),
+            bus: Bus::Pa,
+            volume: volume * zone.gain(),
+        })
+    }
+
+    /// Starts a music track on the music bus, replacing the current one.
+    pub fn play_music(&self, source: Box<dyn Source<Item = f32> + Send>) -> Result<(), String> {
+        self.send(AudioCommand::SetMusic(Some(source)))
+    }
+
+    pub fn stop_music(&self) -> Result<(), String> {
+        self.send(AudioCommand::SetMusic(None))
+    }
+
+    /// Starts the ambience loops, replacing any previous ones.
+    pub fn set_ambience(&self, loops: Vec<Option<Box<dyn Source<Item = f32> + Send>>>) -> Result<(), String> {
+        *self.ambience_loaded.lock().unwrap() = loops.iter().any(Option::is_some);
+        self.send(AudioCommand::SetAmbience(loops))
+    }
+
+    /// Recomputes the ambience levels for the flight phase, boarding and listener zone.
+    pub fn update_ambience(&self, phase: FlightPhase, boarding_progress: f32) -> Result<(), String> {
+        if !*self.ambience_loaded.lock().unwrap() {
+            return Ok(());
+        }
+        let zone = *self.zone.lock().unwrap();
+        self.send(AudioCommand::AmbienceLevels(ambience::levels(phase, boarding_progress, zone)))
+    }
+}
+
+fn run_audio_thread(rx: Receiver<AudioCommand>, settings: Arc<Mutex<MixerSettings>>) {
+    let (_stream, handle) = match OutputStream::try_default() {
+        Ok(output) => output,
+        Err(e) => {
+            println!("Failed to open native audio output: {}", e);
+            return;
+        }
+    };
+    println!("Native audio output opened");
+
+    let mut mixer = Mixer::new(handle);
+    let mut last_tick = Instant::now();
+
+    loop {
+        match rx.recv_timeout(TICK) {
+            Ok(AudioCommand::Play { source, bus, volume }) => mixer.play(source, bus, volume),
+            Ok(AudioCommand::StopAll) => mixer.stop_all(),
+            Ok(AudioCommand::SetMusic(source)) => mixer.set_music(source),
+            Ok(AudioCommand::SetAmbience(loops)) => mixer.set_ambience(loops),
+            Ok(AudioCommand::AmbienceLevels(levels)) => mixer.set_ambience_levels(levels),
+            Ok(AudioCommand::ExternalDuck(active)) => mixer.set_external_duck(active),
+            Err(mpsc::RecvTimeoutError::Timeout) => {},
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let elapsed = last_tick.elapsed();
+        last_tick = Instant::now();
+        let settings = *settings.lock().unwrap();
+        mixer.tick(elapsed, &settings);
+    }
+}
+
+/// Stops all announcements and effects the native engine is playing. Music and ambience keep running.
+#[tauri::command]
+pub fn stop_native_audio(engine: State<Arc<AudioEngineState>>) -> Result<(), String> {
+    engine.send(AudioCommand::StopAll)
+}
+
+/// Tells the native engine which zone the listener is in.
+#[tauri::command]
+pub fn set_listener_zone(engine: State<Arc<AudioEngineState>>, zone: ListenerZone) {
+    *engine.zone.lock().unwrap() = zone;
+}
+
+/// Tells the native engine whether the cockpit door is closed (PAs are muffled in the cockpit).
+#[tauri::command]
+pub fn set_cockpit_door(engine: State<Arc<AudioEngineState>>, closed: bool) {
+    *engine.cockpit_door_closed.lock().unwrap() = closed;
+}
+
+/// Replaces the PA acoustics of one zone until the soundpack changes.
+#[tauri::command]
+pub fn set_zone_acoustics(engine: State<Arc<AudioEngineState>>, zone: ListenerZone, profile: AcousticProfile) {
+    *engine.acoustics.lock().unwrap().get_mut(zone) = profile;
+}
+
+#[tauri::command]
+pub fn get_zone_acoustics(engine: State<Arc<AudioEngineState>>) -> ZoneAcoustics {
+    *engine.acoustics.lock().unwrap()
+}
+
+/// Sets the ambience bus volume, 0 to silence it.
+#[tauri::command]
+pub fn set_ambience_volume(engine: State<Arc<AudioEngineState>>, volume: f32) {
+    engine.mixer.lock().unwrap().volumes.ambience = volume.clamp(0.0, 1.0);
+}
+
+/// Replaces the bus volumes and ducking attack/release.
+#[tauri::command]
+pub fn set_mixer_settings(engine: State<Arc<AudioEngineState>>, settings: MixerSettings) {
+    *engine.mixer.lock().unwrap() = settings;
+}
+
+#[tauri::command]
+pub fn get_mixer_settings(engine: State<Arc<AudioEngineState>>) -> MixerSettings {
+    *engine.mixer.lock().unwrap()
+}
+
+/// Ducks music, ambience and chimes while the frontend plays an announcement of its own.
+#[tauri::command]
+pub fn set_announcement_playing(engine: State<Arc<AudioEngineState>>, playing: bool) -> Result<(), String> {
+    engine.send(AudioCommand::ExternalDuck(playing))
+}
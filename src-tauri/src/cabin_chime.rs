@@ -0,0 +1,346 @@
+use serde_json::{ json, Value };
+use std::f32::consts::PI;
+use std::sync::{ Arc, Mutex };
+use std::time::{ Duration, Instant };
+use rodio::buffer::SamplesBuffer;
+use tauri::{ Emitter, Manager, State, Window };
+
+use crate::audio_engine::AudioEngineState;
+use crate::mixer::Bus;
+use crate::soundpack::{ SoundpackState, CHANNELS, SAMPLE_RATE };
+
+/// Aircraft families have distinct cabin chimes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AircraftFamily {
+    Airbus,
+    Boeing,
+}
+
+impl AircraftFamily {
+    /// Guesses the family from the sim's TITLE/ATC MODEL. Defaults to Airbus.
+    pub fn from_title(title: &str) -> Self {
+        let title = title.to_uppercase();
+        let boeing_markers = ["BOEING", "B737", "B747", "B757", "B767", "B777", "B787", "PMDG 7", " 737", " 747", " 777", " 787"];
+        if boeing_markers.iter().any(|marker| title.contains(marker)) {
+            AircraftFamily::Boeing
+        } else {
+            AircraftFamily::Airbus
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AircraftFamily::Airbus => "airbus",
+            AircraftFamily::Boeing => "boeing",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChimeKind {
+    /// Seatbelt or no-smoking sign switched
+    SignChange,
+    /// Cockpit calling the cabin, or cabin calling the cockpit
+    CrewCall,
+}
+
+impl ChimeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChimeKind::SignChange => "sign_change",
+            ChimeKind::CrewCall => "crew_call",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sign_change" => Some(ChimeKind::SignChange),
+            "crew_call" => Some(ChimeKind::CrewCall),
+            _ => None,
+        }
+    }
+}
+
+/// The tones making up a chime, in Hz, played one after the other.
+/// Airbus uses a single high chime for signs and a double for calls;
+/// Boeing uses a single low chime for signs and a high-low for calls.
+pub fn chime_tones(family: AircraftFamily, kind: ChimeKind) -> &'static [f32] {
+    match (family, kind) {
+        (AircraftFamily::Airbus, ChimeKind::SignChange) => &[1046.5],
+        (AircraftFamily::Airbus, ChimeKind::CrewCall) => &[1046.5, 1046.5],
+        (AircraftFamily::Boeing, ChimeKind::SignChange) => &[659.3],
+        (AircraftFamily::Boeing, ChimeKind::CrewCall) => &[880.0, 659.3],
+    }
+}
+
+const TONE_SPACING: Duration = Duration::from_millis(450);
+const TONE_LENGTH: Duration = Duration::from_millis(1400);
+
+/// Synthesises a chime as a few decaying harmonics per tone.
+pub fn synthesize_chime(tones: &[f32]) -> Vec<f32> {
+    let rate = SAMPLE_RATE as f32;
+    let spacing = (TONE_SPACING.as_secs_f32() * rate) as usize;
+    let length = (TONE_LENGTH.as_secs_f32() * rate) as usize;
+    let frames = spacing * tones.len().saturating_sub(1) + length;
+
+    let mut mono = vec![0.0f32; frames];
+    for (index, frequency) in tones.iter().enumerate() {
+        let offset = index * spacing;
+        for i in 0..length {
+            let t = i as f32 / rate;
+            let envelope = (-t * 3.5).exp() * (t * 400.0).min(1.0);
+            let sample = (2.0 * PI * frequency * t).sin() * 0.6
+                + (2.0 * PI * frequency * 2.0 * t).sin() * 0.25
+                + (2.0 * PI * frequency * 3.0 * t).sin() * 0.1;
+            mono[offset + i] += sample * envelope * 0.4;
+        }
+    }
+
+    mono.into_iter()
+        .flat_map(|sample| [sample; CHANNELS as usize])
+        .collect()
+}
+
+/// Minimum time since the last accepted change before another change is accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DebounceConfig {
+    /// Applied to OFF -> ON, which is usually a genuine pilot action.
+    pub on: Duration,
+    /// Applied to every other change, filtering switch bounce and sim glitches.
+    pub off: Duration,
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        DebounceConfig {
+            on: Duration::from_millis(500),
+            off: Duration::from_millis(3000),
+        }
+    }
+}
+
+/// Debounces a cabin sign switch. The first reading only initialises the state.
+pub struct SignDebouncer {
+    pub config: DebounceConfig,
+    state: Option<bool>,
+    last_change: Instant,
+}
+
+impl SignDebouncer {
+    pub fn new(config: DebounceConfig, now: Instant) -> Self {
+        SignDebouncer {
+            config,
+            state: None,
+            last_change: now,
+        }
+    }
+
+    pub fn state(&self) -> Option<bool> {
+        self.state
+    }
+
+    /// Feeds a raw reading. Returns the new state when a change is accepted.
+    pub fn update(&mut self, on: bool, now: Instant) -> Option<bool> {
+        let previous = match self.state {
+            None => {
+                self.state = Some(on);
+                return None;
+            },
+            Some(previous) => previous,
+        };
+        if on == previous {
+            return None;
+        }
+
+        let min_interval = if on { self.config.on } else { self.config.off };
+        if now.duration_since(self.last_change) < min_interval {
+            return None;
+        }
+
+        self.state = Some(on);
+        self.last_change = now;
+        Some(on)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ChimeSettings {
+    pub enabled: bool,
+    pub volume: f32,
+    pub debounce: DebounceConfig,
+}
+
+impl Default for ChimeSettings {
+    fn default() -> Self {
+        ChimeSettings {
+            enabled: true,
+            volume: 0.8,
+            debounce: DebounceConfig::default(),
+        }
+    }
+}
+
+pub struct ChimeState {
+    pub settings: Mutex<ChimeSettings>,
+    pub family: Mutex<AircraftFamily>,
+}
+
+impl ChimeState {
+    pub fn new() -> Self {
+        ChimeState {
+            settings: Mutex::new(ChimeSettings::default()),
+            family: Mutex::new(AircraftFamily::Airbus),
+        }
+    }
+}
+
+/// Plays a cabin chime natively, preferring a `chimes/<family>_<kind>` clip from the
+/// soundpack over the synthesised one. Returns whether it was heard, so callers can
+/// fall back to the frontend's sounds when chimes are off or the output failed.
+pub fn play_chime(window: &Window, kind: ChimeKind) -> bool {
+    let chimes = window.state::<Arc<ChimeState>>();
+    let settings = *chimes.settings.lock().unwrap();
+    if !settings.enabled {
+        return false;
+    }
+    let family = *chimes.family.lock().unwrap();
+
+    let clip_key = format!("chimes/{}_{}", family.as_str(), kind.as_str());
+    let samples = window.state::<Arc<SoundpackState>>().get()
+        .and_then(|soundpack| soundpack.decode(&clip_key))
+        .unwrap_or_else(|_| synthesize_chime(chime_tones(family, kind)));
+
+    let engine = window.state::<Arc<AudioEngineState>>();
+    if let Err(e) = engine.play(SamplesBuffer::new(CHANNELS, SAMPLE_RATE, samples), Bus::Chimes, settings.volume) {
+        println!("Failed to play cabin chime: {}", e);
+        return false;
+    }
+
+    let _ = window.emit("cabin-chime", json!({
+        "kind": kind.as_str(),
+        "family": family.as_str()
+    }));
+    true
+}
+
+/// Configures the native cabin chimes and the sign debounce times.
+#[tauri::command]
+pub fn set_chime_settings(
+    state: State<Arc<ChimeState>>,
+    enabled: bool,
+    volume: f32,
+    on_debounce_ms: u64,
+    off_debounce_ms: u64
+) {
+    *state.settings.lock().unwrap() = ChimeSettings {
+        enabled,
+        volume: volume.clamp(0.0, 1.0),
+        debounce: DebounceConfig {
+            on: Duration::from_millis(on_debounce_ms),
+            off: Duration::from_millis(off_debounce_ms),
+        },
+    };
+}
+
+/// Current chime settings, so the UI can leave the sign chimes to the native player.
+#[tauri::command]
+pub fn get_chime_settings(state: State<Arc<ChimeState>>) -> Value {
+    let settings = *state.settings.lock().unwrap();
+    json!({
+        "enabled": settings.enabled,
+        "volume": settings.volume,
+        "onDebounceMs": settings.debounce.on.as_millis() as u64,
+        "offDebounceMs": settings.debounce.off.as_millis() as u64
+    })
+}
+
+/// Plays a cabin chime on demand, e.g. for a crew call from the UI.
+#[tauri::command]
+pub fn play_cabin_chime(window: Window, kind: String) -> Result<(), String> {
+    let kind = ChimeKind::parse(&kind)
+        .ok_or_else(|| format!("Unknown chime kind: {}", kind))?;
+    play_chime(&window, kind);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn first_reading_initialises_silently() {
+        let start = Instant::now();
+        let mut debouncer = SignDebouncer::new(DebounceConfig::default(), start);
+        assert_eq!(debouncer.update(true, start + ms(5000)), None);
+        assert_eq!(debouncer.state(), Some(true));
+    }
+
+    #[test]
+    fn on_uses_the_short_debounce() {
+        let start = Instant::now();
+        let mut debouncer = SignDebouncer::new(DebounceConfig::default(), start);
+        debouncer.update(false, start);
+        assert_eq!(debouncer.update(true, start + ms(400)), None);
+        assert_eq!(debouncer.update(true, start + ms(600)), Some(true));
+    }
+
+    #[test]
+    fn off_uses_the_long_debounce() {
+        let start = Instant::now();
+        let mut debouncer = SignDebouncer::new(DebounceConfig::default(), start);
+        debouncer.update(false, start);
+        debouncer.update(true, start + ms(1000));
+        assert_eq!(debouncer.update(false, start + ms(2000)), None);
+        assert_eq!(debouncer.update(false, start + ms(3999)), None);
+        assert_eq!(debouncer.update(false, start + ms(4000)), Some(false));
+    }
+
+    #[test]
+    fn repeated_readings_do_not_retrigger() {
+        let start = Instant::now();
+        let mut debouncer = SignDebouncer::new(DebounceConfig::default(), start);
+        debouncer.update(false, start);
+        assert_eq!(debouncer.update(true, start + ms(1000)), Some(true));
+        assert_eq!(debouncer.update(true, start + ms(10000)), None);
+    }
+
+    #[test]
+    fn debounce_times_are_configurable() {
+        let start = Instant::now();
+        let config = DebounceConfig { on: ms(0), off: ms(100) };
+        let mut debouncer = SignDebouncer::new(config, start);
+        debouncer.update(false, start);
+        assert_eq!(debouncer.update(true, start), Some(true));
+        assert_eq!(debouncer.update(false, start + ms(100)), Some(false));
+    }
+
+    #[test]
+    fn family_is_detected_from_title() {
+        assert_eq!(AircraftFamily::from_title("PMDG 737-800 BBJ2"), AircraftFamily::Boeing);
+        assert_eq!(AircraftFamily::from_title("Boeing 787-10 Asobo"), AircraftFamily::Boeing);
+        assert_eq!(AircraftFamily::from_title("FenixA320 British Airways"), AircraftFamily::Airbus);
+        assert_eq!(AircraftFamily::from_title("Unknown"), AircraftFamily::Airbus);
+    }
+
+    #[test]
+    fn chimes_differ_per_family() {
+        assert_eq!(chime_tones(AircraftFamily::Airbus, ChimeKind::SignChange).len(), 1);
+        assert_eq!(chime_tones(AircraftFamily::Airbus, ChimeKind::CrewCall).len(), 2);
+        assert_eq!(chime_tones(AircraftFamily::Boeing, ChimeKind::SignChange).len(), 1);
+        assert_ne!(
+            chime_tones(AircraftFamily::Boeing, ChimeKind::CrewCall),
+            chime_tones(AircraftFamily::Airbus, ChimeKind::CrewCall)
+        );
+    }
+
+    #[test]
+    fn synthesised_chime_is_stereo_and_bounded() {
+        let samples = synthesize_chime(chime_tones(AircraftFamily::Boeing, ChimeKind::CrewCall));
+        assert_eq!(samples.len() % CHANNELS as usize, 0);
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+    }
+}
@@ -0,0 +1,295 @@
+use serde_json::{ json, Value };
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Crew activity during cruise, in the order it normally happens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceStep {
+    SeatbeltOff,
+    DrinksService,
+    MealService,
+    TrolleyStart,
+    TrolleyStop,
+    DutyFree,
+    LightsDim,
+    LightsUp,
+}
+
+impl ServiceStep {
+    /// The `audio-event` payload for this step.
+    pub fn audio_payload(&self) -> Value {
+        match self {
+            ServiceStep::SeatbeltOff => json!({ "type": "seatbelt_off" }),
+            ServiceStep::DrinksService => json!({ "type": "drinks_service" }),
+            ServiceStep::MealService => json!({ "type": "meal_service" }),
+            ServiceStep::TrolleyStart => json!({ "type": "trolley", "action": "start" }),
+            ServiceStep::TrolleyStop => json!({ "type": "trolley", "action": "stop" }),
+            ServiceStep::DutyFree => json!({ "type": "duty_free" }),
+            ServiceStep::LightsDim => json!({ "type": "cabin_lights", "action": "dim" }),
+            ServiceStep::LightsUp => json!({ "type": "cabin_lights", "action": "up" }),
+        }
+    }
+
+    /// Also the clip played for the step.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceStep::SeatbeltOff => "seatbelt_off",
+            ServiceStep::DrinksService => "drinks_service",
+            ServiceStep::MealService => "meal_service",
+            ServiceStep::TrolleyStart => "trolley_start",
+            ServiceStep::TrolleyStop => "trolley_stop",
+            ServiceStep::DutyFree => "duty_free",
+            ServiceStep::LightsDim => "lights_dim",
+            ServiceStep::LightsUp => "lights_up",
+        }
+    }
+}
+
+// Time reserved after cruise for descent, approach and taxi-in
+const DESCENT_AND_TAXI: Duration = Duration::from_secs(40 * 60);
+const MIN_CRUISE: Duration = Duration::from_secs(20 * 60);
+const MAX_GUESSED_CRUISE: Duration = Duration::from_secs(3 * 60 * 60);
+
+/// Estimates how long the cruise will last. Uses the OFP block time when known,
+/// otherwise guesses from how long the climb took.
+pub fn expected_cruise(block_time: Option<Duration>, since_takeoff: Duration) -> Duration {
+    match block_time {
+        Some(block) => block
+            .saturating_sub(since_takeoff)
+            .saturating_sub(DESCENT_AND_TAXI)
+            .max(MIN_CRUISE),
+        // Short hops reach cruise quickly; long-haul climbs take longer
+        None => (since_takeoff * 4).clamp(MIN_CRUISE, MAX_GUESSED_CRUISE),
+    }
+}
+
+/// Builds the service schedule as offsets from the start of cruise.
+pub fn plan(cruise: Duration) -> Vec<(Duration, ServiceStep)> {
+    let minutes = cruise.as_secs() / 60;
+    let at = |fraction: f64| Duration::from_secs_f64(cruise.as_secs_f64() * fraction);
+
+    let mut steps = vec![(Duration::from_secs(30), ServiceStep::SeatbeltOff)];
+
+    // Drinks on everything; the service has to be finished well before descent
+    let drinks_start = at(0.1).max(Duration::from_secs(3 * 60));
+    let drinks_length = at(0.25).min(Duration::from_secs(25 * 60));
+    steps.push((drinks_start, ServiceStep::DrinksService));
+    steps.push((drinks_start + Duration::from_secs(60), ServiceStep::TrolleyStart));
+
+    if minutes >= 60 {
+        // Meal service follows straight on from drinks
+        let meal_start = drinks_start + drinks_length;
+        let meal_length = at(0.25).min(Duration::from_secs(40 * 60));
+        steps.push((meal_start, ServiceStep::MealService));
+        steps.push((meal_start + meal_length, ServiceStep::TrolleyStop));
+    } else {
+        steps.push((drinks_start + drinks_length, ServiceStep::TrolleyStop));
+    }
+
+    if minutes >= 90 {
+        steps.push((at(0.6), ServiceStep::DutyFree));
+    }
+
+    if minutes >= 180 {
+        steps.push((at(0.65), ServiceStep::LightsDim));
+        steps.push((at(0.9), ServiceStep::LightsUp));
+    }
+
+    steps.sort_by_key(|(offset, _)| *offset);
+    steps
+}
+
+/// Runs the cruise service plan, pausing while the seatbelt sign is on. Times are sim
+/// time (`SimClock::now`), so the service follows sim pause and time acceleration.
+pub struct CabinService {
+    pending: VecDeque<(Duration, ServiceStep)>,
+    started_at: Option<Duration>,
+    suspended_at: Option<Duration>,
+    paused_total: Duration,
+    trolley_out: bool,
+    lights_dimmed: bool,
+    // Set once the service has been cancelled so it doesn't start again
+    finished: bool,
+}
+
+impl CabinService {
+    pub fn new() -> Self {
+        CabinService {
+            pending: VecDeque::new(),
+            started_at: None,
+            suspended_at: None,
+            paused_total: Duration::ZERO,
+            trolley_out: false,
+            lights_dimmed: false,
+            finished: false,
+        }
+    }
+
+    /// True once the service has started, including after it was cancelled.
+    pub fn is_started(&self) -> bool {
+        self.started_at.is_some() || self.finished
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended_at.is_some()
+    }
+
+    /// Starts the service. If the seatbelt sign is still on, it waits for it to go off.
+    pub fn start(&mut self, expected_cruise: Duration, seatbelt_on: bool, now: Duration) {
+        self.pending = plan(expected_cruise).into();
+        self.started_at = Some(now);
+        self.paused_total = Duration::ZERO;
+        self.suspended_at = if seatbelt_on { Some(now) } else { None };
+        println!("Cabin service planned for {} min of cruise ({} steps)",
+            expected_cruise.as_secs() / 60, self.pending.len());
+    }
+
+    /// Pauses the service: seatbelt sign on, turbulence or a step descent. Returns steps to play now.
+    pub fn suspend(&mut self, now: Duration) -> Vec<ServiceStep> {
+        if self.started_at.is_none() || self.is_suspended() {
+            return Vec::new();
+        }
+        self.suspended_at = Some(now);
+        println!("Cabin service suspended");
+        if self.trolley_out {
+            // Crew stow the trolleys but will bring them back out afterwards
+            self.pending.push_front((Duration::ZERO, ServiceStep::TrolleyStart));
+            self.trolley_out = false;
+            return vec![ServiceStep::TrolleyStop];
+        }
+        Vec::new()
+    }
+
+    /// Continues the service after the seatbelt sign went off.
+    pub fn resume(&mut self, now: Duration) {
+        if let Some(suspended_at) = self.suspended_at.take() {
+            self.paused_total += now.saturating_sub(suspended_at);
+            println!("Cabin service resumed");
+        }
+    }
+
+    /// Ends the service for good (on approach). Returns clean-up steps.
+    pub fn cancel(&mut self) -> Vec<ServiceStep> {
+        let mut steps = Vec::new();
+        if self.trolley_out {
+            steps.push(ServiceStep::TrolleyStop);
+        }
+        if self.lights_dimmed {
+            steps.push(ServiceStep::LightsUp);
+        }
+        if self.started_at.is_some() && !self.pending.is_empty() {
+            println!("Cabin service cancelled with {} steps remaining", self.pending.len());
+        }
+        let finished = self.is_started();
+        *self = CabinService::new();
+        self.finished = finished;
+        steps
+    }
+
+    /// Returns the steps that are due now.
+    pub fn poll(&mut self, now: Duration) -> Vec<ServiceStep> {
+        let Some(started_at) = self.started_at else {
+            return Vec::new();
+        };
+        if self.is_suspended() {
+            return Vec::new();
+        }
+
+        let elapsed = now.saturating_sub(started_at).saturating_sub(self.paused_total);
+        let mut due = Vec::new();
+        while let Some((offset, step)) = self.pending.front().copied() {
+            if offset > elapsed {
+                break;
+            }
+            self.pending.pop_front();
+            match step {
+                ServiceStep::TrolleyStart => self.trolley_out = true,
+                ServiceStep::TrolleyStop => self.trolley_out = false,
+                ServiceStep::LightsDim => self.lights_dimmed = true,
+                ServiceStep::LightsUp => self.lights_dimmed = false,
+                _ => {}
+            }
+            due.push(step);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(value: u64) -> Duration {
+        Duration::from_secs(value * 60)
+    }
+
+    fn steps(plan: &[(Duration, ServiceStep)]) -> Vec<ServiceStep> {
+        plan.iter().map(|(_, step)| *step).collect()
+    }
+
+    #[test]
+    fn plan_scales_with_the_cruise() {
+        use ServiceStep::*;
+        assert_eq!(steps(&plan(minutes(40))), [SeatbeltOff, DrinksService, TrolleyStart, TrolleyStop]);
+        assert_eq!(steps(&plan(minutes(100))),
+            [SeatbeltOff, DrinksService, TrolleyStart, MealService, TrolleyStop, DutyFree]);
+        let long_haul = plan(minutes(420));
+        assert!(steps(&long_haul).contains(&LightsDim));
+        assert_eq!(long_haul.last().map(|(_, step)| *step), Some(LightsUp));
+        assert!(long_haul.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    }
+
+    #[test]
+    fn expected_cruise_prefers_the_block_time() {
+        assert_eq!(expected_cruise(Some(minutes(180)), minutes(20)), minutes(120));
+        assert_eq!(expected_cruise(Some(minutes(60)), minutes(20)), MIN_CRUISE);
+        assert_eq!(expected_cruise(None, minutes(15)), minutes(60));
+        assert_eq!(expected_cruise(None, minutes(90)), MAX_GUESSED_CRUISE);
+    }
+
+    #[test]
+    fn poll_plays_steps_as_they_fall_due() {
+        let mut service = CabinService::new();
+        assert!(service.poll(minutes(10)).is_empty());
+        service.start(minutes(40), false, minutes(10));
+        assert!(service.is_started());
+        assert!(service.poll(minutes(10)).is_empty());
+        assert_eq!(service.poll(minutes(11)), [ServiceStep::SeatbeltOff]);
+        assert_eq!(service.poll(minutes(15)), [ServiceStep::DrinksService, ServiceStep::TrolleyStart]);
+        assert!(service.poll(minutes(15)).is_empty());
+    }
+
+    #[test]
+    fn waits_for_the_seatbelt_sign_and_pauses_while_suspended() {
+        let mut service = CabinService::new();
+        service.start(minutes(40), true, Duration::ZERO);
+        assert!(service.is_suspended());
+        assert!(service.poll(minutes(30)).is_empty());
+        service.resume(minutes(30));
+        assert_eq!(service.poll(minutes(31)), [ServiceStep::SeatbeltOff]);
+        assert_eq!(service.poll(minutes(35)), [ServiceStep::DrinksService, ServiceStep::TrolleyStart]);
+
+        // The trolleys go back in while suspended and come out again on resume
+        assert_eq!(service.suspend(minutes(36)), [ServiceStep::TrolleyStop]);
+        assert!(service.suspend(minutes(37)).is_empty());
+        assert!(service.poll(minutes(50)).is_empty());
+        service.resume(minutes(51));
+        assert_eq!(service.poll(minutes(51)), [ServiceStep::TrolleyStart]);
+        // The 15 minutes suspended don't count towards the trolley stop at 14 min of service
+        assert!(service.poll(minutes(58)).is_empty());
+        assert_eq!(service.poll(minutes(59)), [ServiceStep::TrolleyStop]);
+    }
+
+    #[test]
+    fn cancel_cleans_up_and_never_restarts() {
+        let mut service = CabinService::new();
+        assert!(!service.is_started());
+        assert!(service.suspend(minutes(1)).is_empty());
+        service.start(minutes(420), false, Duration::ZERO);
+        let dimmed = service.poll(minutes(280));
+        assert!(dimmed.contains(&ServiceStep::LightsDim));
+        assert_eq!(service.cancel(), [ServiceStep::LightsUp]);
+        assert!(service.is_started());
+        assert!(service.poll(minutes(500)).is_empty());
+    }
+}
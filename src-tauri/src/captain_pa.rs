@@ -0,0 +1,212 @@
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::mpsc::{ self, Receiver, Sender };
+use std::sync::{ Arc, Mutex };
+use std::thread::{ self, JoinHandle };
+use rodio::buffer::SamplesBuffer;
+use rodio::cpal::{ self, FromSample, Sample, SampleFormat, SizedSample };
+use rodio::cpal::traits::{ DeviceTrait, HostTrait, StreamTrait };
+use rodio::source::UniformSourceIterator;
+use tauri::{ Emitter, Manager, State, Window };
+
+use crate::audio_engine::AudioEngineState;
+use crate::dsp;
+use crate::flight_phase::FlightPhase;
+use crate::logbook;
+use crate::soundpack::{ SoundpackState, CHANNELS, SAMPLE_RATE };
+use crate::track;
+
+// Longest PA we keep; anything after this is dropped
+const MAX_RECORDING_SECS: usize = 180;
+
+/// A microphone capture in progress. The cpal stream isn't `Send`, so it lives on
+/// its own thread until told to stop.
+struct Recording {
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+    captured: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+}
+
+pub struct CaptainPaState {
+    recording: Mutex<Option<Recording>>,
+    /// Recorded clip to play when the flight enters each phase
+    pub schedule: Mutex<HashMap<FlightPhase, String>>,
+}
+
+impl CaptainPaState {
+    pub fn new() -> Self {
+        CaptainPaState {
+            recording: Mutex::new(None),
+            schedule: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    captured: Arc<Mutex<Vec<f32>>>
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let max_samples = config.sample_rate.0 as usize * MAX_RECORDING_SECS;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let mut captured = captured.lock().unwrap();
+            if captured.len() >= max_samples {
+                return;
+            }
+            // Downmix to mono; a PA is a single voice
+            captured.extend(data.chunks(channels).map(|frame| {
+                frame.iter().map(|s| f32::from_sample(*s)).sum::<f32>() / channels as f32
+            }));
+        },
+        |e| println!("Microphone stream error: {}", e),
+        None
+    )
+}
+
+/// Opens the default microphone and starts capturing into `captured`. Returns the sample rate.
+fn open_microphone(captured: Arc<Mutex<Vec<f32>>>) -> Result<(cpal::Stream, u32), String> {
+    let device = cpal::default_host().default_input_device()
+        .ok_or_else(|| "No microphone found".to_string())?;
+    let supported = device.default_input_config()
+        .map_err(|e| format!("Failed to query microphone: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_input_stream::<f32>(&device, &config, captured),
+        SampleFormat::I16 => build_input_stream::<i16>(&device, &config, captured),
+        SampleFormat::U16 => build_input_stream::<u16>(&device, &config, captured),
+        other => return Err(format!("Unsupported microphone sample format {:?}", other)),
+    }.map_err(|e| format!("Failed to open microphone: {}", e))?;
+
+    stream.play().map_err(|e| format!("Failed to start microphone: {}", e))?;
+    Ok((stream, config.sample_rate.0))
+}
+
+fn run_capture(captured: Arc<Mutex<Vec<f32>>>, ready: Sender<Result<u32, String>>, stop: Receiver<()>) {
+    match open_microphone(captured) {
+        Ok((stream, sample_rate)) => {
+            let _ = ready.send(Ok(sample_rate));
+            // Keep the stream alive until stopped (or the state is dropped)
+            let _ = stop.recv();
+            drop(stream);
+        },
+        Err(e) => {
+            let _ = ready.send(Err(e));
+        },
+    }
+}
+
+/// Plays a recorded PA through the native engine, attenuated for the listener's zone.
+fn play_clip(window: &Window, clip: &str) -> Result<(), String> {
+    let samples = window.state::<Arc<SoundpackState>>().get()?.decode(clip)?;
+    window.state::<Arc<AudioEngineState>>()
+        .play_pa(SamplesBuffer::new(CHANNELS, SAMPLE_RATE, samples), 1.0)?;
+
+    logbook::record_announcement(window, "captain_pa");
+    track::record_announcement(window, "captain_pa");
+    let _ = window.emit("captain-pa", json!({
+        "clip": clip
+    }));
+    Ok(())
+}
+
+/// Plays the captain's PA scheduled for `phase`, if any.
+pub fn play_for_phase(window: &Window, phase: FlightPhase) {
+    let clip = window.state::<Arc<CaptainPaState>>().schedule.lock().unwrap().get(&phase).cloned();
+    if let Some(clip) = clip {
+        println!("Playing captain PA '{}' for phase {}", clip, phase.as_str());
+        if let Err(e) = play_clip(window, &clip) {
+            println!("Failed to play captain PA: {}", e);
+        }
+    }
+}
+
+/// Starts recording the captain's PA from the default microphone.
+#[tauri::command]
+pub fn start_pa_recording(state: State<Arc<CaptainPaState>>) -> Result<(), String> {
+    let mut recording = state.recording.lock().unwrap();
+    if recording.is_some() {
+        return Err("A PA is already being recorded".to_string());
+    }
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let thread_captured = captured.clone();
+    let thread = thread::spawn(move || run_capture(thread_captured, ready_tx, stop_rx));
+
+    let sample_rate = ready_rx.recv()
+        .map_err(|_| "Microphone capture stopped unexpectedly".to_string())??;
+    println!("Recording captain PA at {} Hz", sample_rate);
+
+    *recording = Some(Recording {
+        stop: stop_tx,
+        thread,
+        captured,
+        sample_rate,
+    });
+    Ok(())
+}
+
+/// Stops recording and saves the PA to the soundpack as `captain/<name>`. It is saved
+/// dry; playback puts it through the listener zone's speaker and room chain. Returns the clip key.
+#[tauri::command]
+pub fn stop_pa_recording(
+    state: State<Arc<CaptainPaState>>,
+    soundpack: State<Arc<SoundpackState>>,
+    name: String
+) -> Result<String, String> {
+    // Checked first so a bad name doesn't throw away the recording
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(format!("Invalid PA name: {}", name));
+    }
+
+    let recording = state.recording.lock().unwrap().take()
+        .ok_or_else(|| "No PA is being recorded".to_string())?;
+    let _ = recording.stop.send(());
+    let _ = recording.thread.join();
+
+    let mono = std::mem::take(&mut *recording.captured.lock().unwrap());
+    if mono.is_empty() {
+        return Err("Nothing was recorded".to_string());
+    }
+
+    let resampled: Vec<f32> = UniformSourceIterator::<_, f32>::new(
+        SamplesBuffer::new(1, recording.sample_rate, mono),
+        1,
+        SAMPLE_RATE
+    ).collect();
+    let stereo: Vec<f32> = dsp::normalize(&resampled, 0.8).into_iter()
+        .flat_map(|sample| [sample; CHANNELS as usize])
+        .collect();
+
+    let key = format!("captain/{}", name);
+    let path = soundpack.get()?.write(&key, &stereo)?;
+    println!("Captain PA saved to {} ({:.1}s)", path.display(), resampled.len() as f32 / SAMPLE_RATE as f32);
+    Ok(key)
+}
+
+/// Plays a recorded PA when the flight enters `phase`, or clears it when `clip` is None.
+#[tauri::command]
+pub fn set_captain_pa(state: State<Arc<CaptainPaState>>, phase: FlightPhase, clip: Option<String>) {
+    let mut schedule = state.schedule.lock().unwrap();
+    match clip {
+        Some(clip) => schedule.insert(phase, clip),
+        None => schedule.remove(&phase),
+    };
+}
+
+/// Plays a recorded PA now, e.g. to preview it.
+#[tauri::command]
+pub fn play_captain_pa(window: Window, clip: String) -> Result<(), String> {
+    play_clip(&window, &clip)
+}
@@ -0,0 +1,212 @@
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::flight_phase::FlightPhase;
+
+/// Below this ground speed the estimate is meaningless (taxiing, sim paused)
+const MIN_GROUND_SPEED_KTS: f64 = 60.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DescentSettings {
+    pub enabled: bool,
+    /// How long before landing the "arriving soon" PA plays
+    pub lead_minutes: f64,
+    /// Track miles needed per 1000 ft of descent; 3 is the usual rule of thumb
+    pub descent_nm_per_1000ft: f64,
+    /// Average rate of descent from top of descent to touchdown, approach included
+    pub descent_rate_fpm: f64,
+    /// Level flight needed before the PA can play in cruise, so short flights don't get
+    /// it straight after the top of climb
+    pub min_cruise_minutes: f64,
+}
+
+impl Default for DescentSettings {
+    fn default() -> Self {
+        DescentSettings {
+            enabled: true,
+            lead_minutes: 30.0,
+            descent_nm_per_1000ft: 3.0,
+            descent_rate_fpm: 1500.0,
+            min_cruise_minutes: 3.0,
+        }
+    }
+}
+
+/// Where the aircraft is relative to its destination.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RouteProgress {
+    pub remaining_nm: f64,
+    pub ground_speed_kts: f64,
+    pub altitude_ft: f64,
+    pub destination_elevation_ft: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrivalEstimate {
+    pub remaining_nm: f64,
+    /// Track miles to the top of descent, zero once descending
+    pub top_of_descent_nm: f64,
+    pub minutes_to_top_of_descent: f64,
+    pub minutes_to_landing: f64,
+}
+
+/// Estimates time to landing: the cruise leg to the top of descent at the current
+/// ground speed, then the descent at the configured average rate.
+pub fn estimate(progress: &RouteProgress, settings: &DescentSettings) -> Option<ArrivalEstimate> {
+    if progress.ground_speed_kts < MIN_GROUND_SPEED_KTS || progress.remaining_nm < 0.0 {
+        return None;
+    }
+    let height = (progress.altitude_ft - progress.destination_elevation_ft.unwrap_or(0.0)).max(0.0);
+    let descent_nm = height / 1000.0 * settings.descent_nm_per_1000ft;
+    let descent_minutes = height / settings.descent_rate_fpm.max(1.0);
+
+    let (top_of_descent_nm, minutes_to_top_of_descent, minutes_to_landing) = if progress.remaining_nm > descent_nm {
+        let cruise_nm = progress.remaining_nm - descent_nm;
+        let cruise_minutes = cruise_nm / progress.ground_speed_kts * 60.0;
+        (cruise_nm, cruise_minutes, cruise_minutes + descent_minutes)
+    } else {
+        // Already inside the descent; whichever of distance or height takes longer
+        let distance_minutes = progress.remaining_nm / progress.ground_speed_kts * 60.0;
+        (0.0, 0.0, distance_minutes.max(descent_minutes))
+    };
+
+    Some(ArrivalEstimate {
+        remaining_nm: progress.remaining_nm,
+        top_of_descent_nm,
+        minutes_to_top_of_descent,
+        minutes_to_landing,
+    })
+}
+
+/// Local time at the destination as `HH:MM`, from sim zulu time in seconds since midnight.
+pub fn local_time(zulu_secs: f64, utc_offset_hours: f64) -> String {
+    let minutes = ((zulu_secs / 60.0) + utc_offset_hours * 60.0).round() as i64;
+    let minutes = minutes.rem_euclid(24 * 60);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Announcement template for the arrival PA. Only says what `variables` has: the
+/// destination and time to landing, then the local time and weather there.
+pub fn arrival_template(variables: &HashMap<String, String>) -> String {
+    let has = |name: &str| variables.contains_key(name);
+    let mut tokens = vec!["phrases/we_have_started_our_preparations"];
+    if has("destination") {
+        tokens.extend(["phrases/for_our_arrival_at", "{clip:destination}"]);
+    }
+    if has("minutes_to_landing") {
+        tokens.extend(["phrases/we_expect_to_land_in", "{duration:minutes_to_landing}"]);
+    }
+    if has("destination_local_time") {
+        tokens.extend(["[pause:300]", "phrases/the_local_time_is", "{digits:destination_local_time}"]);
+    }
+    if has("destination_weather") && has("destination_temperature") {
+        tokens.extend([
+            "[pause:300]", "phrases/the_weather_there_is", "{clip:destination_weather}",
+            "phrases/with_a_temperature_of", "{number:destination_temperature}", "units/degrees",
+        ]);
+    }
+    tokens.join(" ")
+}
+
+/// Makes sure "arriving soon" plays once per flight, from the estimate or, when none
+/// could be made, from the descent altitude trigger.
+pub struct DescentPlanner {
+    announced: bool,
+    /// Sim time the current cruise began
+    cruise_since: Option<Duration>,
+}
+
+impl DescentPlanner {
+    pub fn new() -> Self {
+        DescentPlanner { announced: false, cruise_since: None }
+    }
+
+    /// Whether the estimate says it is time for the arrival PA. In cruise it waits until
+    /// the aircraft has been level for `min_cruise_minutes` of sim time (`now`).
+    pub fn is_due(&mut self, phase: FlightPhase, estimate: &ArrivalEstimate, settings: &DescentSettings, now: Duration) -> bool {
+        if phase == FlightPhase::Cruise {
+            self.cruise_since.get_or_insert(now);
+        } else {
+            self.cruise_since = None;
+        }
+        let settled = match phase {
+            FlightPhase::Cruise => self.cruise_since
+                .is_some_and(|since| now.saturating_sub(since).as_secs_f64() >= settings.min_cruise_minutes * 60.0),
+            FlightPhase::Descent => true,
+            _ => false,
+        };
+        settings.enabled && !self.announced && settled
+            && estimate.minutes_to_landing <= settings.lead_minutes
+    }
+
+    /// Marks the arrival PA as played. Returns false if it already was this flight.
+    pub fn claim(&mut self) -> bool {
+        !std::mem::replace(&mut self.announced, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate(minutes_to_landing: f64) -> ArrivalEstimate {
+        ArrivalEstimate {
+            remaining_nm: 100.0,
+            top_of_descent_nm: 0.0,
+            minutes_to_top_of_descent: 0.0,
+            minutes_to_landing,
+        }
+    }
+
+    fn minutes(value: u64) -> Duration {
+        Duration::from_secs(value * 60)
+    }
+
+    #[test]
+    fn short_flight_waits_for_the_minimum_cruise() {
+        let settings = DescentSettings::default();
+        let mut planner = DescentPlanner::new();
+        // Top of climb on a short hop: already within the lead time
+        assert!(!planner.is_due(FlightPhase::Cruise, &estimate(20.0), &settings, minutes(10)));
+        assert!(!planner.is_due(FlightPhase::Cruise, &estimate(19.0), &settings, minutes(12)));
+        assert!(planner.is_due(FlightPhase::Cruise, &estimate(17.0), &settings, minutes(13)));
+    }
+
+    #[test]
+    fn descent_is_due_straight_away() {
+        let settings = DescentSettings::default();
+        let mut planner = DescentPlanner::new();
+        assert!(!planner.is_due(FlightPhase::Climb, &estimate(20.0), &settings, minutes(5)));
+        assert!(planner.is_due(FlightPhase::Descent, &estimate(20.0), &settings, minutes(6)));
+        assert!(planner.claim());
+        assert!(!planner.is_due(FlightPhase::Descent, &estimate(10.0), &settings, minutes(7)));
+    }
+
+    #[test]
+    fn arrival_template_says_only_what_is_known() {
+        let mut variables = HashMap::new();
+        assert_eq!(arrival_template(&variables), "phrases/we_have_started_our_preparations");
+
+        variables.insert("destination".to_string(), "cities/lemd".to_string());
+        variables.insert("minutes_to_landing".to_string(), "25".to_string());
+        variables.insert("destination_local_time".to_string(), "14:35".to_string());
+        variables.insert("destination_weather".to_string(), "weather/light_rain".to_string());
+        // Weather needs the temperature to go with it
+        assert!(!arrival_template(&variables).contains("destination_weather"));
+
+        variables.insert("destination_temperature".to_string(), "12".to_string());
+        let template = arrival_template(&variables);
+        assert_eq!(
+            template,
+            "phrases/we_have_started_our_preparations phrases/for_our_arrival_at {clip:destination} \
+             phrases/we_expect_to_land_in {duration:minutes_to_landing} \
+             [pause:300] phrases/the_local_time_is {digits:destination_local_time} \
+             [pause:300] phrases/the_weather_there_is {clip:destination_weather} \
+             phrases/with_a_temperature_of {number:destination_temperature} units/degrees"
+        );
+        assert!(crate::announcement_template::expand(&template, &variables).is_ok());
+    }
+}
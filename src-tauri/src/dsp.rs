@@ -0,0 +1,389 @@
+use std::f32::consts::PI;
+use std::time::Duration;
+use rodio::Source;
+
+/// Second-order IIR filter (RBJ audio EQ cookbook), one channel.
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    pub fn from_coefficients(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Biquad {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    fn omega(sample_rate: u32, frequency: f32, q: f32) -> (f32, f32) {
+        // Keep the corner below Nyquist so low sample rates stay stable
+        let frequency = frequency.min(sample_rate as f32 * 0.45);
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        (w0.cos(), w0.sin() / (2.0 * q))
+    }
+
+    pub fn high_pass(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let (cos, alpha) = Self::omega(sample_rate, frequency, q);
+        Self::from_coefficients(
+            (1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0,
+            1.0 + alpha, -2.0 * cos, 1.0 - alpha,
+        )
+    }
+
+    pub fn low_pass(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let (cos, alpha) = Self::omega(sample_rate, frequency, q);
+        Self::from_coefficients(
+            (1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0,
+            1.0 + alpha, -2.0 * cos, 1.0 - alpha,
+        )
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1 - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    feedback: f32,
+}
+
+impl Comb {
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.buffer[self.index];
+        self.buffer[self.index] = x + y * self.feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        y
+    }
+}
+
+struct AllPass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl AllPass {
+    fn process(&mut self, x: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        let y = delayed - x;
+        self.buffer[self.index] = x + delayed * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        y
+    }
+}
+
+/// Small Schroeder reverb, one channel. `size` scales the delay lines (1.0 is a cabin).
+pub struct Reverb {
+    combs: Vec<Comb>,
+    all_passes: Vec<AllPass>,
+    mix: f32,
+}
+
+impl Reverb {
+    pub fn new(sample_rate: u32, size: f32, decay: f32, mix: f32) -> Self {
+        let samples = |ms: f32| ((ms * size * sample_rate as f32 / 1000.0) as usize).max(1);
+        Reverb {
+            combs: [29.7, 37.1, 41.1, 43.7].iter()
+                .map(|ms| Comb { buffer: vec![0.0; samples(*ms)], index: 0, feedback: decay.clamp(0.0, 0.95) })
+                .collect(),
+            all_passes: [5.0, 1.7].iter()
+                .map(|ms| AllPass { buffer: vec![0.0; samples(*ms)], index: 0 })
+                .collect(),
+            mix,
+        }
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let mut wet = self.combs.iter_mut().map(|comb| comb.process(x)).sum::<f32>() / 4.0;
+        for all_pass in &mut self.all_passes {
+            wet = all_pass.process(wet);
+        }
+        x * (1.0 - self.mix) + wet * self.mix
+    }
+}
+
+/// Brings a recording up to a consistent peak level, leaving its tone alone.
+pub fn normalize(mono: &[f32], peak: f32) -> Vec<f32> {
+    let loudest = mono.iter().fold(0.0f32, |loudest, s| loudest.max(s.abs()));
+    let gain = if loudest > 0.0 { peak / loudest } else { 1.0 };
+    mono.iter().map(|s| s * gain).collect()
+}
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Per-sample processing stage for an interleaved stream.
+pub trait Processor {
+    /// Builds per-channel state once the stream format is known.
+    fn prepare(&mut self, channels: usize, sample_rate: u32);
+    fn process(&mut self, channel: usize, sample: f32) -> f32;
+
+    /// How long the stage keeps ringing after the input ends.
+    fn tail(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Runs a `Processor` over a rodio `Source`.
+pub struct Processed<S, P> {
+    source: S,
+    processor: P,
+    channels: usize,
+    channel: usize,
+    // Samples of silence still to feed through once the source has ended
+    tail_samples: usize,
+    source_ended: bool,
+}
+
+impl<S, P> Processed<S, P>
+where
+    S: Source<Item = f32>,
+    P: Processor,
+{
+    pub fn new(source: S, mut processor: P) -> Self {
+        let channels = source.channels().max(1) as usize;
+        processor.prepare(channels, source.sample_rate());
+        let tail_frames = (processor.tail().as_secs_f32() * source.sample_rate() as f32) as usize;
+        Processed {
+            source,
+            processor,
+            channels,
+            channel: 0,
+            tail_samples: tail_frames * channels,
+            source_ended: false,
+        }
+    }
+}
+
+impl<S, P> Iterator for Processed<S, P>
+where
+    S: Source<Item = f32>,
+    P: Processor,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let next = if self.source_ended { None } else { self.source.next() };
+        let sample = match next {
+            Some(sample) => sample,
+            None => {
+                // Let the stage ring out on silence
+                self.source_ended = true;
+                if self.tail_samples == 0 {
+                    return None;
+                }
+                self.tail_samples -= 1;
+                0.0
+            },
+        };
+        let out = self.processor.process(self.channel, sample);
+        self.channel = (self.channel + 1) % self.channels;
+        Some(out)
+    }
+}
+
+impl<S, P> Source for Processed<S, P>
+where
+    S: Source<Item = f32>,
+    P: Processor,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.source_ended {
+            return Some(self.tail_samples);
+        }
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration().map(|d| d + self.processor.tail())
+    }
+}
+
+/// Band-limited PA speaker: cuts lows and highs the ceiling speakers can't reproduce.
+pub struct SpeakerEq {
+    low_cut_hz: f32,
+    high_cut_hz: f32,
+    filters: Vec<(Biquad, Biquad)>,
+}
+
+impl SpeakerEq {
+    pub fn new(low_cut_hz: f32, high_cut_hz: f32) -> Self {
+        SpeakerEq { low_cut_hz, high_cut_hz, filters: Vec::new() }
+    }
+}
+
+impl Processor for SpeakerEq {
+    fn prepare(&mut self, channels: usize, sample_rate: u32) {
+        let pair = (
+            Biquad::high_pass(sample_rate, self.low_cut_hz, 0.707),
+            Biquad::low_pass(sample_rate, self.high_cut_hz, 0.707),
+        );
+        self.filters = vec![pair; channels];
+    }
+
+    fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        let (high_pass, low_pass) = &mut self.filters[channel];
+        low_pass.process(high_pass.process(sample))
+    }
+}
+
+/// Room reverb, one independent tank per channel.
+pub struct CabinReverb {
+    size: f32,
+    decay: f32,
+    mix: f32,
+    tanks: Vec<Reverb>,
+}
+
+impl CabinReverb {
+    pub fn new(size: f32, decay: f32, mix: f32) -> Self {
+        CabinReverb { size, decay, mix, tanks: Vec::new() }
+    }
+}
+
+impl Processor for CabinReverb {
+    fn prepare(&mut self, channels: usize, sample_rate: u32) {
+        // Slightly different sizes decorrelate the channels
+        self.tanks = (0..channels)
+            .map(|channel| Reverb::new(sample_rate, self.size * (1.0 + channel as f32 * 0.07), self.decay, self.mix))
+            .collect();
+    }
+
+    fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        self.tanks[channel].process(sample)
+    }
+
+    fn tail(&self) -> Duration {
+        // The longest comb decays by 60 dB after this many round trips; the
+        // channels' tanks are up to a few percent larger, hence the margin
+        let round_trips = -3.0 / self.decay.clamp(0.05, 0.95).log10();
+        Duration::from_secs_f32(0.0437 * self.size * round_trips * 1.2)
+    }
+}
+
+/// Feed-forward compressor. All channels share one envelope so the image doesn't shift.
+pub struct Compressor {
+    threshold_db: f32,
+    ratio: f32,
+    attack: Duration,
+    release: Duration,
+    makeup: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    envelope: f32,
+}
+
+impl Compressor {
+    pub fn new(threshold_db: f32, ratio: f32, attack: Duration, release: Duration, makeup_db: f32) -> Self {
+        Compressor {
+            threshold_db,
+            ratio: ratio.max(1.0),
+            attack,
+            release,
+            makeup: db_to_gain(makeup_db),
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            envelope: 0.0,
+        }
+    }
+}
+
+impl Processor for Compressor {
+    fn prepare(&mut self, channels: usize, sample_rate: u32) {
+        // The envelope sees every interleaved sample, so time constants count all channels
+        let rate = (sample_rate as usize * channels) as f32;
+        let coeff = |time: Duration| (-1.0 / (time.as_secs_f32() * rate).max(1.0)).exp();
+        self.attack_coeff = coeff(self.attack);
+        self.release_coeff = coeff(self.release);
+    }
+
+    fn process(&mut self, _channel: usize, sample: f32) -> f32 {
+        let level = sample.abs();
+        let coeff = if level > self.envelope { self.attack_coeff } else { self.release_coeff };
+        self.envelope = level + coeff * (self.envelope - level);
+
+        let level_db = 20.0 * self.envelope.max(1e-6).log10();
+        let over = level_db - self.threshold_db;
+        let reduction_db = if over > 0.0 { over - over / self.ratio } else { 0.0 };
+        sample * db_to_gain(-reduction_db) * self.makeup
+    }
+}
+
+/// Sound heard through a wall or door: mostly lows, and quieter.
+pub struct Muffle {
+    cutoff_hz: f32,
+    gain: f32,
+    filters: Vec<(Biquad, Biquad)>,
+}
+
+impl Muffle {
+    pub fn new(cutoff_hz: f32, gain_db: f32) -> Self {
+        Muffle { cutoff_hz, gain: db_to_gain(gain_db), filters: Vec::new() }
+    }
+}
+
+impl Processor for Muffle {
+    fn prepare(&mut self, channels: usize, sample_rate: u32) {
+        // Two cascaded low passes for a steeper, more wall-like roll-off
+        let low_pass = Biquad::low_pass(sample_rate, self.cutoff_hz, 0.707);
+        self.filters = vec![(low_pass, low_pass); channels];
+    }
+
+    fn process(&mut self, channel: usize, sample: f32) -> f32 {
+        let (first, second) = &mut self.filters[channel];
+        second.process(first.process(sample)) * self.gain
+    }
+}
+
+/// Adds the DSP stages as adapters on any `f32` source.
+pub trait DspSourceExt: Source<Item = f32> + Sized {
+    fn speaker_eq(self, low_cut_hz: f32, high_cut_hz: f32) -> Processed<Self, SpeakerEq> {
+        Processed::new(self, SpeakerEq::new(low_cut_hz, high_cut_hz))
+    }
+
+    fn cabin_reverb(self, size: f32, decay: f32, mix: f32) -> Processed<Self, CabinReverb> {
+        Processed::new(self, CabinReverb::new(size, decay, mix))
+    }
+
+    fn compress(self, compressor: Compressor) -> Processed<Self, Compressor> {
+        Processed::new(self, compressor)
+    }
+
+    fn muffle(self, cutoff_hz: f32, gain_db: f32) -> Processed<Self, Muffle> {
+        Processed::new(self, Muffle::new(cutoff_hz, gain_db))
+    }
+}
+
+impl<S: Source<Item = f32>> DspSourceExt for S {}
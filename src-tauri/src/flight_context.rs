@@ -0,0 +1,167 @@
+use serde::Serialize;
+use serde_json::{ json, Value };
+use std::collections::HashMap;
+use std::sync::{ Arc, Mutex };
+use tauri::State;
+
+use crate::airports::{ Airport, NearbyAirport };
+use crate::languages::LanguageSettings;
+use crate::ofp::OfpData;
+use crate::sim_flight_plan::SimFlightPlan;
+use crate::weather::WeatherSample;
+
+/// What simpa knows about the current flight beyond raw sim variables.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightContext {
+    pub ofp: Option<OfpData>,
+    /// The flight plan active in the sim, used when there is no OFP
+    pub sim_flight_plan: Option<SimFlightPlan>,
+    /// Closest airport to the aircraft, from the airport database
+    pub nearest_airport: Option<NearbyAirport>,
+    /// The OFP airports from the database, or without an OFP the airports
+    /// we took off from and landed at
+    pub departure_airport: Option<Airport>,
+    pub arrival_airport: Option<Airport>,
+    /// Ambient weather around the aircraft
+    pub weather: Option<WeatherSample>,
+    /// Ambient weather near landing, brought down to the ground; cleared at takeoff
+    pub arrival_weather: Option<WeatherSample>,
+}
+
+impl FlightContext {
+    pub fn origin_icao(&self) -> Option<String> {
+        self.ofp.as_ref().map(|ofp| ofp.origin.icao.clone())
+            .or_else(|| self.sim_flight_plan.as_ref().and_then(|plan| plan.origin.clone()))
+            .or_else(|| self.departure_airport.as_ref().map(|airport| airport.ident.clone()))
+    }
+
+    pub fn destination_icao(&self) -> Option<String> {
+        self.ofp.as_ref().map(|ofp| ofp.destination.icao.clone())
+            .or_else(|| self.sim_flight_plan.as_ref().and_then(|plan| plan.destination.clone()))
+            .or_else(|| self.arrival_airport.as_ref().map(|airport| airport.ident.clone()))
+    }
+
+    pub fn origin_elevation_ft(&self) -> Option<f64> {
+        self.ofp.as_ref().and_then(|ofp| ofp.origin.elevation_ft)
+            .or_else(|| self.departure_airport.as_ref().and_then(|airport| airport.elevation_ft))
+    }
+
+    pub fn destination_position(&self) -> Option<(f64, f64)> {
+        self.ofp.as_ref()
+            .and_then(|ofp| ofp.destination.latitude.zip(ofp.destination.longitude))
+            .or_else(|| self.arrival_airport.as_ref().map(|airport| (airport.latitude, airport.longitude)))
+    }
+
+    pub fn destination_elevation_ft(&self) -> Option<f64> {
+        self.ofp.as_ref().and_then(|ofp| ofp.destination.elevation_ft)
+            .or_else(|| self.arrival_airport.as_ref().and_then(|airport| airport.elevation_ft))
+    }
+
+    /// Weather at the destination: what we saw near landing, else the OFP's METAR.
+    pub fn destination_weather(&self) -> Option<WeatherSample> {
+        self.arrival_weather.or_else(|| self.ofp.as_ref()
+            .and_then(|ofp| ofp.destination.metar.as_deref())
+            .and_then(WeatherSample::from_metar))
+    }
+
+    /// Route details attached to every announcement so the frontend and
+    /// the template engine can say where we're going.
+    pub fn announcement_fields(&self) -> Value {
+        if self.origin_icao().is_none() && self.destination_icao().is_none() {
+            return Value::Null;
+        }
+        let ofp = self.ofp.as_ref();
+        let name = |ofp_name: Option<&Option<String>>, airport: &Option<Airport>| {
+            ofp_name.cloned().flatten().or_else(|| airport.as_ref().map(|a| a.name.clone()))
+        };
+        let city = |airport: &Option<Airport>| airport.as_ref().and_then(|a| a.municipality.clone());
+        json!({
+            "flightNumber": ofp.and_then(|ofp| ofp.flight_designator()),
+            "airline": ofp.and_then(|ofp| ofp.airline_icao.clone()),
+            "origin": self.origin_icao(),
+            "originName": name(ofp.map(|ofp| &ofp.origin.name), &self.departure_airport),
+            "originCity": city(&self.departure_airport),
+            "destination": self.destination_icao(),
+            "destinationName": name(ofp.map(|ofp| &ofp.destination.name), &self.arrival_airport),
+            "destinationCity": city(&self.arrival_airport),
+            "cruiseFlightLevel": ofp.and_then(|ofp| ofp.cruise_flight_level()),
+            "blockTimeMinutes": ofp.and_then(|ofp| ofp.block_time_secs).map(|secs| secs / 60),
+            "destinationWeather": self.destination_weather().map(|weather| weather.report()),
+        })
+    }
+
+    /// Variables available to announcement templates. Airports map to
+    /// `cities/<icao>` clips in the soundpack.
+    pub fn template_variables(&self) -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+        if let Some(origin) = self.origin_icao() {
+            variables.insert("origin".to_string(), format!("cities/{}", origin.to_lowercase()));
+        }
+        if let Some(destination) = self.destination_icao() {
+            variables.insert("destination".to_string(), format!("cities/{}", destination.to_lowercase()));
+        }
+        if let Some(ofp) = &self.ofp {
+            if let Some(number) = &ofp.flight_number {
+                variables.insert("flight_number".to_string(), number.clone());
+            }
+            if let Some(level) = ofp.cruise_flight_level() {
+                variables.insert("cruise_level".to_string(), level.to_string());
+            }
+            if let Some(block) = ofp.block_time_secs {
+                variables.insert("block_time".to_string(), (block / 60).to_string());
+            }
+        }
+        if let Some(weather) = &self.weather {
+            variables.extend(weather.template_variables(""));
+        }
+        if let Some(weather) = self.destination_weather() {
+            variables.extend(weather.template_variables("destination_"));
+        }
+        variables
+    }
+}
+
+/// Shared flight context, written by importers and read by the collection loop.
+pub struct FlightContextState {
+    pub context: Mutex<FlightContext>,
+    pub languages: Mutex<LanguageSettings>,
+}
+
+impl FlightContextState {
+    pub fn new() -> Self {
+        FlightContextState {
+            context: Mutex::new(FlightContext::default()),
+            languages: Mutex::new(LanguageSettings::default()),
+        }
+    }
+
+    /// Clears what was learned during the flight. The imported OFP is kept.
+    pub fn reset_flight(&self) {
+        let mut context = self.context.lock().unwrap();
+        *context = FlightContext {
+            ofp: context.ofp.take(),
+            ..FlightContext::default()
+        };
+    }
+
+    pub fn snapshot(&self) -> FlightContext {
+        self.context.lock().unwrap().clone()
+    }
+
+    /// Languages announcements play in for this flight, in order.
+    pub fn announcement_languages(&self) -> Vec<String> {
+        self.languages.lock().unwrap().sequence(&self.snapshot())
+    }
+}
+
+/// The current flight context: OFP, sim flight plan and airports, with the origin and
+/// destination simpa settled on.
+#[tauri::command]
+pub fn get_flight_context(state: State<Arc<FlightContextState>>) -> Value {
+    let context = state.snapshot();
+    let mut value = json!(context);
+    value["origin"] = json!(context.origin_icao());
+    value["destination"] = json!(context.destination_icao());
+    value
+}
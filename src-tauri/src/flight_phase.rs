@@ -0,0 +1,249 @@
+use serde::{ Deserialize, Serialize };
+use std::time::{ Duration, Instant };
+
+/// Coarse flight phases used for logbook timings and phase-driven announcements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlightPhase {
+    Preflight,
+    TaxiOut,
+    Takeoff,
+    Climb,
+    Cruise,
+    Descent,
+    Approach,
+    Landing,
+    TaxiIn,
+    Arrived,
+}
+
+impl FlightPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlightPhase::Preflight => "preflight",
+            FlightPhase::TaxiOut => "taxi_out",
+            FlightPhase::Takeoff => "takeoff",
+            FlightPhase::Climb => "climb",
+            FlightPhase::Cruise => "cruise",
+            FlightPhase::Descent => "descent",
+            FlightPhase::Approach => "approach",
+            FlightPhase::Landing => "landing",
+            FlightPhase::TaxiIn => "taxi_in",
+            FlightPhase::Arrived => "arrived",
+        }
+    }
+}
+
+// A candidate phase must hold for this long before we switch to it
+const PHASE_CONFIRM: Duration = Duration::from_secs(5);
+// Level flight must hold for this long before it counts as cruise
+const CRUISE_CONFIRM: Duration = Duration::from_secs(60);
+// Stationary time after landing before the flight counts as arrived
+const ARRIVED_CONFIRM: Duration = Duration::from_secs(30);
+// Levelling off above this in a descent is a step down to a lower cruise level, not the arrival
+const STEP_DESCENT_MIN_AGL: f64 = 10000.0;
+
+/// Derives the flight phase from on-ground state, vertical speed, ground speed and height.
+pub struct FlightPhaseTracker {
+    phase: FlightPhase,
+    candidate: Option<(FlightPhase, Instant)>,
+    has_been_airborne: bool,
+}
+
+impl FlightPhaseTracker {
+    pub fn new() -> Self {
+        FlightPhaseTracker {
+            phase: FlightPhase::Preflight,
+            candidate: None,
+            has_been_airborne: false,
+        }
+    }
+
+    /// Feeds one sample. Returns `(previous, new)` when the phase changes.
+    pub fn update(
+        &mut self,
+        on_ground: bool,
+        vertical_speed_fpm: f64,
+        ground_speed_kts: f64,
+        height_agl_ft: f64
+    ) -> Option<(FlightPhase, FlightPhase)> {
+        self.update_at(on_ground, vertical_speed_fpm, ground_speed_kts, height_agl_ft, Instant::now())
+    }
+
+    fn update_at(
+        &mut self,
+        on_ground: bool,
+        vertical_speed_fpm: f64,
+        ground_speed_kts: f64,
+        height_agl_ft: f64,
+        now: Instant
+    ) -> Option<(FlightPhase, FlightPhase)> {
+        if !on_ground {
+            self.has_been_airborne = true;
+        }
+
+        let (target, confirm) = self.target_phase(on_ground, vertical_speed_fpm, ground_speed_kts, height_agl_ft);
+        if target == self.phase {
+            self.candidate = None;
+            return None;
+        }
+
+        match self.candidate {
+            Some((phase, since)) if phase == target => {
+                if now.duration_since(since) < confirm {
+                    return None;
+                }
+            },
+            _ => {
+                self.candidate = Some((target, now));
+                if !confirm.is_zero() {
+                    return None;
+                }
+            }
+        }
+
+        let previous = self.phase;
+        self.phase = target;
+        self.candidate = None;
+        Some((previous, target))
+    }
+
+    /// Resets to preflight, e.g. after a flight reload.
+    pub fn reset(&mut self) {
+        *self = FlightPhaseTracker::new();
+    }
+
+    fn target_phase(
+        &self,
+        on_ground: bool,
+        vs: f64,
+        gs: f64,
+        agl: f64
+    ) -> (FlightPhase, Duration) {
+        use FlightPhase::*;
+
+        if on_ground {
+            if !self.has_been_airborne {
+                return match gs {
+                    s if s > 40.0 => (Takeoff, Duration::ZERO),
+                    s if s > 3.0 => (TaxiOut, PHASE_CONFIRM),
+                    _ if self.phase == TaxiOut => (TaxiOut, PHASE_CONFIRM),
+                    _ => (Preflight, PHASE_CONFIRM),
+                };
+            }
+            return match gs {
+                s if s > 40.0 => (Landing, Duration::ZERO),
+                s if s > 3.0 => (TaxiIn, PHASE_CONFIRM),
+                _ if self.phase == Arrived => (Arrived, ARRIVED_CONFIRM),
+                _ if self.phase == Landing || self.phase == TaxiIn => (Arrived, ARRIVED_CONFIRM),
+                _ => (self.phase, PHASE_CONFIRM),
+            };
+        }
+
+        // Airborne
+        if agl < 3000.0 && vs < -200.0 && matches!(self.phase, Descent | Approach | Cruise) {
+            return (Approach, PHASE_CONFIRM);
+        }
+        if vs > 500.0 {
+            // Climbing again after a go-around or step climb
+            return (Climb, PHASE_CONFIRM);
+        }
+        if vs < -500.0 {
+            return match self.phase {
+                Approach => (Approach, PHASE_CONFIRM),
+                _ => (Descent, PHASE_CONFIRM),
+            };
+        }
+        let step_descent = self.phase == Descent && agl > STEP_DESCENT_MIN_AGL;
+        if vs.abs() < 300.0 && (matches!(self.phase, Climb | Cruise) || step_descent) {
+            return (Cruise, CRUISE_CONFIRM);
+        }
+        match self.phase {
+            Preflight | TaxiOut | Takeoff => (Climb, PHASE_CONFIRM),
+            phase => (phase, PHASE_CONFIRM),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `(on_ground, vs, gs, agl)` once a second from `start`; returns the changes.
+    fn fly(
+        tracker: &mut FlightPhaseTracker,
+        start: &mut Instant,
+        seconds: u64,
+        sample: (bool, f64, f64, f64)
+    ) -> Vec<FlightPhase> {
+        let (on_ground, vs, gs, agl) = sample;
+        let mut changes = Vec::new();
+        for _ in 0..seconds {
+            *start += Duration::from_secs(1);
+            if let Some((_, phase)) = tracker.update_at(on_ground, vs, gs, agl, *start) {
+                changes.push(phase);
+            }
+        }
+        changes
+    }
+
+    #[test]
+    fn a_whole_flight() {
+        use FlightPhase::*;
+        let mut tracker = FlightPhaseTracker::new();
+        let mut now = Instant::now();
+        let mut phases = Vec::new();
+        phases.extend(fly(&mut tracker, &mut now, 10, (true, 0.0, 0.0, 0.0)));
+        phases.extend(fly(&mut tracker, &mut now, 10, (true, 0.0, 15.0, 0.0)));
+        phases.extend(fly(&mut tracker, &mut now, 2, (true, 0.0, 120.0, 0.0)));
+        phases.extend(fly(&mut tracker, &mut now, 10, (false, 2500.0, 180.0, 500.0)));
+        phases.extend(fly(&mut tracker, &mut now, 70, (false, 0.0, 450.0, 35000.0)));
+        phases.extend(fly(&mut tracker, &mut now, 10, (false, -2000.0, 400.0, 20000.0)));
+        phases.extend(fly(&mut tracker, &mut now, 10, (false, -800.0, 160.0, 2000.0)));
+        phases.extend(fly(&mut tracker, &mut now, 2, (true, 0.0, 130.0, 0.0)));
+        phases.extend(fly(&mut tracker, &mut now, 10, (true, 0.0, 15.0, 0.0)));
+        phases.extend(fly(&mut tracker, &mut now, 40, (true, 0.0, 0.0, 0.0)));
+        assert_eq!(phases, [TaxiOut, Takeoff, Climb, Cruise, Descent, Approach, Landing, TaxiIn, Arrived]);
+    }
+
+    #[test]
+    fn short_blips_are_not_confirmed() {
+        let mut tracker = FlightPhaseTracker::new();
+        let mut now = Instant::now();
+        fly(&mut tracker, &mut now, 2, (true, 0.0, 120.0, 0.0));
+        fly(&mut tracker, &mut now, 10, (false, 2500.0, 180.0, 500.0));
+        // Leveling off briefly during the climb isn't cruise
+        assert!(fly(&mut tracker, &mut now, 30, (false, 0.0, 250.0, 10000.0)).is_empty());
+        assert!(fly(&mut tracker, &mut now, 10, (false, 2000.0, 250.0, 11000.0)).is_empty());
+        assert_eq!(tracker.phase, FlightPhase::Climb);
+    }
+
+    #[test]
+    fn step_descents_return_to_cruise() {
+        use FlightPhase::*;
+        let mut tracker = FlightPhaseTracker::new();
+        let mut now = Instant::now();
+        fly(&mut tracker, &mut now, 2, (true, 0.0, 120.0, 0.0));
+        fly(&mut tracker, &mut now, 10, (false, 2500.0, 180.0, 500.0));
+        fly(&mut tracker, &mut now, 70, (false, 0.0, 450.0, 37000.0));
+        assert_eq!(fly(&mut tracker, &mut now, 10, (false, -1500.0, 450.0, 35000.0)), [Descent]);
+        assert_eq!(fly(&mut tracker, &mut now, 70, (false, 0.0, 450.0, 33000.0)), [Cruise]);
+
+        // Levelling off during the arrival stays in the descent
+        fly(&mut tracker, &mut now, 10, (false, -2000.0, 300.0, 20000.0));
+        assert!(fly(&mut tracker, &mut now, 120, (false, 0.0, 250.0, 6000.0)).is_empty());
+        assert_eq!(tracker.phase, Descent);
+    }
+
+    #[test]
+    fn reset_starts_over() {
+        let mut tracker = FlightPhaseTracker::new();
+        let mut now = Instant::now();
+        fly(&mut tracker, &mut now, 2, (true, 0.0, 120.0, 0.0));
+        fly(&mut tracker, &mut now, 10, (false, 2500.0, 180.0, 500.0));
+        tracker.reset();
+        assert_eq!(tracker.phase, FlightPhase::Preflight);
+        // Not yet airborne again, so fast on the ground is a takeoff, not a landing
+        assert_eq!(fly(&mut tracker, &mut now, 1, (true, 0.0, 120.0, 0.0)), [FlightPhase::Takeoff]);
+    }
+}
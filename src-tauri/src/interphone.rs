@@ -0,0 +1,244 @@
+use serde_json::json;
+use std::sync::{ Arc, Mutex };
+use std::thread;
+use std::time::Duration;
+use tauri::{ Emitter, Manager, State, Window };
+
+use crate::cabin_chime::{ self, ChimeKind };
+use crate::flight_phase::FlightPhase;
+use crate::sim_clock::SimClock;
+use crate::simconnect_data::{ play_clip, Playback };
+
+/// Cabin call buttons of an aircraft add-on, exposed as L-vars.
+#[derive(Debug)]
+pub struct CallProfile {
+    pub name: &'static str,
+    /// Matched case-insensitively against the sim TITLE
+    pub title_markers: &'static [&'static str],
+    pub call_lvars: &'static [&'static str],
+}
+
+pub const PROFILES: &[CallProfile] = &[
+    CallProfile {
+        name: "Fenix A320",
+        title_markers: &["FENIX"],
+        call_lvars: &["L:S_OH_CALLS_FWD", "L:S_OH_CALLS_ALL"],
+    },
+    CallProfile {
+        name: "FlyByWire A32NX",
+        title_markers: &["A32NX", "FLYBYWIRE"],
+        call_lvars: &["L:PUSH_OVHD_CALLS_FWD", "L:PUSH_OVHD_CALLS_ALL"],
+    },
+];
+
+// Typical length of the safety demo, which the crew finish before reporting the cabin secure
+const SAFETY_DEMO_LENGTH: Duration = Duration::from_secs(4 * 60);
+
+// Cabin call L-vars without a profile, i.e. just the custom one
+const CUSTOM_CALL_DEFINE_ID: u32 = 19;
+// Each profile's call L-vars get their own DefineID from here on
+const PROFILE_CALL_DEFINE_BASE: u32 = 30;
+
+/// Cabin call L-vars registered together as one SimConnect data definition.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallDefinition {
+    pub define_id: u32,
+    /// The profile's L-vars, then the custom one
+    pub lvars: Vec<String>,
+}
+
+/// The call L-vars to watch for `profile`, or only the custom one without a profile.
+pub fn call_definition(profile: Option<&CallProfile>, custom: Option<&str>) -> CallDefinition {
+    let index = profile.and_then(|profile| PROFILES.iter().position(|known| std::ptr::eq(known, profile)));
+    CallDefinition {
+        define_id: index.map_or(CUSTOM_CALL_DEFINE_ID, |index| PROFILE_CALL_DEFINE_BASE + index as u32),
+        lvars: profile.map_or(&[][..], |profile| profile.call_lvars).iter()
+            .map(|lvar| lvar.to_string())
+            .chain(custom.map(str::to_string))
+            .collect(),
+    }
+}
+
+/// Every call definition, registered up front so switching aircraft only changes
+/// which one is requested.
+pub fn call_definitions(custom: Option<&str>) -> Vec<CallDefinition> {
+    PROFILES.iter().map(Some).chain([None])
+        .map(|profile| call_definition(profile, custom))
+        .filter(|definition| !definition.lvars.is_empty())
+        .collect()
+}
+
+/// Picks the profile for an aircraft TITLE.
+pub fn profile_for(title: &str) -> Option<&'static CallProfile> {
+    let title = title.to_uppercase();
+    PROFILES.iter().find(|profile| profile.title_markers.iter().any(|marker| title.contains(marker)))
+}
+
+/// Detects presses of the cabin call buttons for the loaded aircraft.
+pub struct CallWatcher {
+    pub definition: CallDefinition,
+    previous: Vec<bool>,
+}
+
+impl CallWatcher {
+    pub fn new(definition: CallDefinition) -> Self {
+        let previous = vec![false; definition.lvars.len()];
+        CallWatcher { definition, previous }
+    }
+
+    /// Feeds the current values of the definition's L-vars. Returns true when a call
+    /// button was just pressed.
+    pub fn update(&mut self, values: &[f64]) -> bool {
+        let mut pressed = false;
+        for (index, value) in values.iter().enumerate().take(self.previous.len()) {
+            let down = *value != 0.0;
+            if down && !self.previous[index] {
+                println!("Cabin call pressed ({})", self.definition.lvars[index]);
+                pressed = true;
+            }
+            self.previous[index] = down;
+        }
+        pressed
+    }
+}
+
+/// Interphone state shared between the SimConnect loop and the commands.
+pub struct InterphoneState {
+    /// Extra cabin call L-var, e.g. for add-ons without a built-in profile.
+    /// Takes effect when data collection next starts.
+    pub custom_lvar: Mutex<Option<String>>,
+    pub phase: Mutex<FlightPhase>,
+    /// Sim time the safety demo began
+    pub safety_demo_started: Mutex<Option<Duration>>,
+    // Bumped on every call so a newer call replaces a pending reply
+    reply_generation: Mutex<u64>,
+}
+
+impl InterphoneState {
+    pub fn new() -> Self {
+        InterphoneState {
+            custom_lvar: Mutex::new(None),
+            phase: Mutex::new(FlightPhase::Preflight),
+            safety_demo_started: Mutex::new(None),
+            reply_generation: Mutex::new(0),
+        }
+    }
+
+    /// Forgets the flight: back to preflight, with any pending crew reply dropped.
+    pub fn reset(&self) {
+        *self.phase.lock().unwrap() = FlightPhase::Preflight;
+        *self.safety_demo_started.lock().unwrap() = None;
+        *self.reply_generation.lock().unwrap() += 1;
+    }
+}
+
+fn random_secs(min: u64, max: u64) -> Duration {
+    Duration::from_secs(rand::random::<u64>() % (max - min + 1) + min)
+}
+
+/// Runs `reply` after `delay` of sim time unless another call was made in the meantime.
+fn schedule_reply(window: &Window, delay: Duration, reply: impl FnOnce(&Window) + Send + 'static) {
+    let state = window.state::<Arc<InterphoneState>>().inner().clone();
+    let clock = window.state::<Arc<SimClock>>().inner().clone();
+    let generation = {
+        let mut current = state.reply_generation.lock().unwrap();
+        *current += 1;
+        *current
+    };
+
+    let window = window.clone();
+    thread::spawn(move || {
+        if clock.sleep_while(delay, || *state.reply_generation.lock().unwrap() == generation) {
+            reply(&window);
+        }
+    });
+}
+
+fn cabin_ready(window: &Window, stage: &'static str) {
+    println!("Cabin ready for {}", stage);
+    cabin_chime::play_chime(window, ChimeKind::CrewCall);
+    play_clip(window, Playback::Interphone, &format!("cabin_ready_{}", stage), json!({
+        "type": "cabin_ready",
+        "stage": stage
+    }));
+    let _ = window.emit("cabin-ready", json!({
+        "stage": stage
+    }));
+}
+
+/// Asks the crew to report when the cabin is secure. Before departure they finish the
+/// safety demo first; in descent this is the "cabin crew, prepare for landing" call.
+pub fn request_ready(window: &Window) {
+    let state = window.state::<Arc<InterphoneState>>();
+    let phase = *state.phase.lock().unwrap();
+
+    match phase {
+        FlightPhase::Descent | FlightPhase::Approach => {
+            println!("Cabin crew asked to prepare for landing");
+            // The purser acknowledges, then walks the cabin
+            schedule_reply(window, random_secs(3, 6), |window| {
+                play_clip(window, Playback::Interphone, "crew_landing_ack", json!({
+                    "type": "crew_landing_ack"
+                }));
+                schedule_reply(window, random_secs(120, 240), |window| cabin_ready(window, "landing"));
+            });
+        },
+        _ => {
+            let clock = window.state::<Arc<SimClock>>();
+            let demo_remaining = state.safety_demo_started.lock().unwrap()
+                .map(|started| SAFETY_DEMO_LENGTH.saturating_sub(clock.since(started)));
+            let delay = match demo_remaining {
+                Some(remaining) => remaining + random_secs(20, 60),
+                // No demo yet: the crew still have to do it
+                None => SAFETY_DEMO_LENGTH + random_secs(30, 90),
+            };
+            println!("Cabin secure report expected in {}s", delay.as_secs());
+            schedule_reply(window, delay, |window| cabin_ready(window, "departure"));
+        },
+    }
+}
+
+/// Calls the cabin on the interphone, as if the pilot pressed the call button.
+pub fn call(window: &Window) {
+    let phase = *window.state::<Arc<InterphoneState>>().phase.lock().unwrap();
+    cabin_chime::play_chime(window, ChimeKind::CrewCall);
+    let _ = window.emit("cabin-call", json!({
+        "phase": phase.as_str()
+    }));
+
+    match phase {
+        FlightPhase::Preflight | FlightPhase::TaxiOut | FlightPhase::Descent | FlightPhase::Approach => {
+            request_ready(window);
+        },
+        _ => {
+            schedule_reply(window, random_secs(4, 10), |window| {
+                play_clip(window, Playback::Interphone, "crew_interphone_reply", json!({
+                    "type": "crew_interphone_reply"
+                }));
+            });
+        },
+    }
+}
+
+#[tauri::command]
+pub fn call_cabin(window: Window) {
+    call(&window);
+}
+
+#[tauri::command]
+pub fn request_cabin_ready(window: Window) {
+    request_ready(&window);
+}
+
+/// Watches an extra L-var as the cabin call button, or none.
+#[tauri::command]
+pub fn set_cabin_call_lvar(state: State<Arc<InterphoneState>>, lvar: Option<String>) -> Result<(), String> {
+    let lvar = lvar.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    if let Some(lvar) = &lvar {
+        if !lvar.starts_with("L:") {
+            return Err(format!("Not an L-var: {}", lvar));
+        }
+    }
+    *state.custom_lvar.lock().unwrap() = lvar;
+    Ok(())
+}
@@ -0,0 +1,293 @@
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+
+use crate::airports::Airport;
+use crate::flight_context::{ FlightContext, FlightContextState };
+use crate::soundpack::{ Soundpack, CHANNELS, SAMPLE_RATE };
+
+/// Language of each ICAO location prefix, as ISO 639-1 codes. Two-letter prefixes are
+/// tried before one-letter ones. Only a fallback for airports missing from the airport
+/// database, so prefixes spanning several languages (Canada, Switzerland) are left out.
+const PREFIX_LANGUAGES: &[(&str, &str)] = &[
+    // Europe
+    ("ED", "de"), ("ET", "de"), ("LO", "de"),
+    ("LF", "fr"), ("EB", "nl"), ("EH", "nl"), ("EL", "fr"),
+    ("LE", "es"), ("GC", "es"), ("LP", "pt"), ("LI", "it"),
+    ("EP", "pl"), ("LK", "cs"), ("LZ", "sk"), ("LH", "hu"), ("LR", "ro"), ("LB", "bg"),
+    ("LG", "el"), ("LC", "el"), ("LT", "tr"), ("LD", "hr"), ("LJ", "sl"), ("LY", "sr"),
+    ("EK", "da"), ("EN", "no"), ("ES", "sv"), ("EF", "fi"), ("BI", "is"),
+    ("EY", "lt"), ("EV", "lv"), ("EE", "et"),
+    ("UU", "ru"), ("UL", "ru"), ("UW", "ru"), ("UN", "ru"), ("UR", "ru"), ("UK", "uk"),
+    ("EG", "en"), ("EI", "en"),
+    // Middle East and Africa
+    ("LL", "he"), ("OI", "fa"),
+    ("OE", "ar"), ("OM", "ar"), ("OT", "ar"), ("OB", "ar"), ("OK", "ar"), ("OJ", "ar"),
+    ("OL", "ar"), ("OS", "ar"), ("HE", "ar"), ("GM", "ar"), ("DT", "ar"), ("DA", "ar"),
+    ("FA", "en"),
+    // Asia and Oceania
+    ("RJ", "ja"), ("RO", "ja"), ("RK", "ko"), ("ZK", "ko"), ("ZM", "mn"),
+    ("RC", "zh"), ("VH", "zh"), ("Z", "zh"),
+    ("VT", "th"), ("VV", "vi"), ("WI", "id"), ("WA", "id"),
+    ("Y", "en"), ("NZ", "en"),
+    // Americas
+    ("K", "en"), ("SB", "pt"), ("SD", "pt"), ("SN", "pt"), ("SS", "pt"), ("SW", "pt"),
+    ("MM", "es"), ("SK", "es"), ("SC", "es"), ("SA", "es"), ("SP", "es"), ("SE", "es"),
+    ("SU", "es"), ("SL", "es"), ("SG", "es"), ("SV", "es"), ("MG", "es"), ("MH", "es"),
+    ("MN", "es"), ("MR", "es"), ("MP", "es"), ("MD", "es"),
+];
+
+/// Language of each ISO 3166-1 country, as ISO 639-1 codes. Regions (ISO 3166-2) that
+/// speak another language than the rest of their country come first.
+const COUNTRY_LANGUAGES: &[(&str, &str)] = &[
+    ("CA-QC", "fr"), ("CH-GE", "fr"), ("CH-VD", "fr"), ("CH-NE", "fr"), ("CH-JU", "fr"),
+    ("CH-TI", "it"),
+    // Europe
+    ("DE", "de"), ("AT", "de"), ("CH", "de"), ("FR", "fr"), ("BE", "nl"), ("NL", "nl"),
+    ("LU", "fr"), ("MC", "fr"), ("ES", "es"), ("PT", "pt"), ("IT", "it"), ("MT", "en"),
+    ("PL", "pl"), ("CZ", "cs"), ("SK", "sk"), ("HU", "hu"), ("RO", "ro"), ("BG", "bg"),
+    ("GR", "el"), ("CY", "el"), ("TR", "tr"), ("HR", "hr"), ("SI", "sl"), ("RS", "sr"),
+    ("DK", "da"), ("NO", "no"), ("SE", "sv"), ("FI", "fi"), ("IS", "is"),
+    ("LT", "lt"), ("LV", "lv"), ("EE", "et"), ("RU", "ru"), ("BY", "ru"), ("UA", "uk"),
+    ("GB", "en"), ("IE", "en"),
+    // Middle East and Africa
+    ("IL", "he"), ("IR", "fa"),
+    ("SA", "ar"), ("AE", "ar"), ("QA", "ar"), ("BH", "ar"), ("KW", "ar"), ("OM", "ar"),
+    ("JO", "ar"), ("LB", "ar"), ("SY", "ar"), ("IQ", "ar"), ("EG", "ar"), ("MA", "ar"),
+    ("TN", "ar"), ("DZ", "ar"), ("ZA", "en"),
+    // Asia and Oceania
+    ("JP", "ja"), ("KR", "ko"), ("KP", "ko"), ("MN", "mn"),
+    ("CN", "zh"), ("TW", "zh"), ("HK", "zh"), ("MO", "zh"),
+    ("TH", "th"), ("VN", "vi"), ("ID", "id"), ("AU", "en"), ("NZ", "en"),
+    // Americas
+    ("US", "en"), ("CA", "en"), ("BR", "pt"),
+    ("MX", "es"), ("CO", "es"), ("CL", "es"), ("AR", "es"), ("PE", "es"), ("EC", "es"),
+    ("UY", "es"), ("BO", "es"), ("PY", "es"), ("VE", "es"), ("GT", "es"), ("HN", "es"),
+    ("NI", "es"), ("CR", "es"), ("PA", "es"), ("DO", "es"), ("SV", "es"), ("CU", "es"),
+];
+
+/// Language spoken at an airport from the airport database, by its region or country.
+fn country_language(airport: &Airport) -> Option<&'static str> {
+    airport.region.iter()
+        .chain(std::iter::once(&airport.country))
+        .find_map(|code| COUNTRY_LANGUAGES.iter().find(|(country, _)| country.eq_ignore_ascii_case(code)))
+        .map(|(_, language)| *language)
+}
+
+/// Language spoken at an airport, guessed from its ICAO code.
+pub fn airport_language(icao: &str) -> Option<&'static str> {
+    let icao = icao.to_ascii_uppercase();
+    PREFIX_LANGUAGES.iter()
+        .filter(|(prefix, _)| prefix.len() == 2)
+        .chain(PREFIX_LANGUAGES.iter().filter(|(prefix, _)| prefix.len() == 1))
+        .find(|(prefix, _)| icao.starts_with(prefix))
+        .map(|(_, language)| *language)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LanguageSettings {
+    /// Off plays every announcement in the base language only
+    pub enabled: bool,
+    /// The language of the soundpack's own clips, played last
+    pub base_language: String,
+    /// Per-language switches by ISO 639-1 code; languages not listed are on
+    pub languages: HashMap<String, bool>,
+    /// Pause between languages
+    pub gap_ms: u32,
+}
+
+impl Default for LanguageSettings {
+    fn default() -> Self {
+        LanguageSettings {
+            enabled: true,
+            base_language: "en".to_string(),
+            languages: HashMap::new(),
+            gap_ms: 800,
+        }
+    }
+}
+
+impl LanguageSettings {
+    fn is_enabled(&self, language: &str) -> bool {
+        self.languages.get(language).copied().unwrap_or(true)
+    }
+
+    /// Play order for an announcement: departure country's language, then the arrival
+    /// country's, then the base language.
+    pub fn sequence(&self, context: &FlightContext) -> Vec<String> {
+        let mut sequence: Vec<String> = Vec::new();
+        if self.enabled {
+            let airports = [
+                (context.origin_icao(), context.departure_airport.as_ref()),
+                (context.destination_icao(), context.arrival_airport.as_ref()),
+            ];
+            let languages = airports.iter().filter_map(|(icao, airport)| {
+                let icao = icao.as_deref()?;
+                // The database's airport may still be the last flight's until it's looked up again
+                airport.filter(|airport| airport.ident.eq_ignore_ascii_case(icao))
+                    .and_then(country_language)
+                    .or_else(|| airport_language(icao))
+            });
+            for language in languages {
+                if language != self.base_language && self.is_enabled(language)
+                    && !sequence.iter().any(|l| l == language)
+                {
+                    sequence.push(language.to_string());
+                }
+            }
+        }
+        // The base language can only be switched off when another one plays instead
+        if sequence.is_empty() || self.is_enabled(&self.base_language) {
+            sequence.push(self.base_language.clone());
+        }
+        sequence
+    }
+}
+
+/// The soundpacks to play an announcement from, in order. Other languages live in
+/// `<soundpack>/languages/<code>/`; those the soundpack lacks are skipped.
+pub fn soundpacks(soundpack: &Soundpack, context: &FlightContextState) -> Vec<(String, Soundpack)> {
+    let settings = context.languages.lock().unwrap().clone();
+    settings.sequence(&context.snapshot()).into_iter()
+        .filter_map(|language| {
+            if language == settings.base_language {
+                return Some((language, soundpack.clone()));
+            }
+            let root = soundpack.root.join("languages").join(&language);
+            root.is_dir().then(|| (language, Soundpack::new(root, soundpack.analyzer.clone())))
+        })
+        .collect()
+}
+
+/// Renders an announcement in each language and joins them with the configured gap.
+/// Languages it fails in (e.g. missing clips) are left out; fails only if all do.
+pub fn render_sequence<F>(packs: &[(String, Soundpack)], context: &FlightContextState, render: F) -> Result<Vec<f32>, String>
+where
+    F: Fn(&Soundpack) -> Result<Vec<f32>, String>,
+{
+    let gap_ms = context.languages.lock().unwrap().gap_ms;
+    let gap = (SAMPLE_RATE as usize * gap_ms as usize / 1000) * CHANNELS as usize;
+
+    let mut output: Vec<f32> = Vec::new();
+    let mut last_error = None;
+    for (language, pack) in packs {
+        match render(pack) {
+            Ok(samples) => {
+                if !output.is_empty() {
+                    output.resize(output.len() + gap, 0.0);
+                }
+                output.extend(samples);
+            },
+            Err(e) => {
+                println!("Skipping language {}: {}", language, e);
+                last_error = Some(e);
+            },
+        }
+    }
+
+    match (output.is_empty(), last_error) {
+        (true, Some(e)) => Err(e),
+        _ => Ok(output),
+    }
+}
+
+#[tauri::command]
+pub fn set_language_settings(state: State<Arc<FlightContextState>>, settings: LanguageSettings) {
+    *state.languages.lock().unwrap() = settings;
+}
+
+#[tauri::command]
+pub fn get_language_settings(state: State<Arc<FlightContextState>>) -> LanguageSettings {
+    state.languages.lock().unwrap().clone()
+}
+
+/// The languages announcements play in for the current flight, in order.
+#[tauri::command]
+pub fn get_announcement_languages(state: State<Arc<FlightContextState>>) -> Vec<String> {
+    state.announcement_languages()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim_flight_plan::SimFlightPlan;
+
+    fn airport(ident: &str, country: &str, region: &str) -> Airport {
+        Airport {
+            ident: ident.to_string(),
+            iata: None,
+            name: ident.to_string(),
+            kind: "large_airport".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            elevation_ft: None,
+            country: country.to_string(),
+            region: Some(region.to_string()),
+            municipality: None,
+        }
+    }
+
+    fn flight(origin: &str, destination: &str) -> FlightContext {
+        FlightContext {
+            sim_flight_plan: Some(SimFlightPlan {
+                origin: Some(origin.to_string()),
+                destination: Some(destination.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prefixes_prefer_the_longer_match() {
+        assert_eq!(airport_language("eddf"), Some("de"));
+        assert_eq!(airport_language("ZBAA"), Some("zh"));
+        assert_eq!(airport_language("ZKPY"), Some("ko"));
+        assert_eq!(airport_language("ZMCK"), Some("mn"));
+        assert_eq!(airport_language("KJFK"), Some("en"));
+        // Canada speaks both; only the database can tell
+        assert_eq!(airport_language("CYUL"), None);
+        assert_eq!(airport_language("XXXX"), None);
+    }
+
+    #[test]
+    fn database_country_and_region_win_over_the_prefix() {
+        let mut context = flight("CYYZ", "CYUL");
+        context.departure_airport = Some(airport("CYYZ", "CA", "CA-ON"));
+        context.arrival_airport = Some(airport("CYUL", "CA", "CA-QC"));
+        assert_eq!(LanguageSettings::default().sequence(&context), ["fr", "en"]);
+
+        // A stale arrival airport from the last flight isn't used
+        let mut context = flight("LSZH", "LSGG");
+        context.departure_airport = Some(airport("LSZH", "CH", "CH-ZH"));
+        context.arrival_airport = Some(airport("CYUL", "CA", "CA-QC"));
+        assert_eq!(LanguageSettings::default().sequence(&context), ["de", "en"]);
+        context.arrival_airport = Some(airport("LSGG", "CH", "CH-GE"));
+        assert_eq!(LanguageSettings::default().sequence(&context), ["de", "fr", "en"]);
+    }
+
+    #[test]
+    fn sequence_honours_the_settings() {
+        let context = flight("LFPG", "EDDF");
+        let mut settings = LanguageSettings::default();
+        assert_eq!(settings.sequence(&context), ["fr", "de", "en"]);
+
+        settings.languages.insert("de".to_string(), false);
+        assert_eq!(settings.sequence(&context), ["fr", "en"]);
+
+        // The base language isn't repeated, and can be dropped while another plays
+        settings.base_language = "fr".to_string();
+        settings.languages.insert("fr".to_string(), false);
+        settings.languages.insert("de".to_string(), true);
+        assert_eq!(settings.sequence(&context), ["de"]);
+        settings.languages.insert("de".to_string(), false);
+        assert_eq!(settings.sequence(&context), ["fr"]);
+
+        settings.enabled = false;
+        settings.languages.clear();
+        assert_eq!(settings.sequence(&context), ["fr"]);
+        assert_eq!(LanguageSettings::default().sequence(&FlightContext::default()), ["en"]);
+    }
+}
@@ -1,6 +1,36 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 mod simconnect_data;
 mod check_simconnect_status;
+mod touchdown;
+mod flight_phase;
+mod logbook;
+mod track;
+mod ofp;
+mod flight_context;
+mod soundpack;
+mod audio_engine;
+mod announcement_template;
+mod cabin_service;
+mod turbulence;
+mod cabin_chime;
+mod interphone;
+mod dsp;
+mod captain_pa;
+mod acoustics;
+mod ambience;
+mod mixer;
+mod playlist;
+mod asset_analyzer;
+mod languages;
+mod airports;
+mod altitude_triggers;
+mod descent_planner;
+mod sim_flight_plan;
+mod weather;
+mod sim_clock;
+mod aircraft_profile;
+mod sim_connection;
+mod xplane;
 
 use std::sync::Arc;
 use tauri::Manager;
@@ -9,9 +39,56 @@ use crate::simconnect_data::{
     start_simconnect_data_collection,
     stop_simconnect_data_collection,
     toggle_wing_light,
+    set_touchdown_announcements,
+    set_altitude_triggers,
+    get_altitude_triggers,
+    set_descent_settings,
+    get_descent_settings,
+    set_xplane_settings,
+    get_xplane_settings,
     SimConnectState,
 };
 use crate::check_simconnect_status::check_simconnect_status;
+use crate::logbook::{
+    list_flights,
+    get_flight,
+    delete_flight,
+    export_flight,
+    get_current_flight,
+    LogbookState,
+};
+use crate::track::{ set_track_sample_interval, export_track, TrackState };
+use crate::ofp::{ import_ofp, clear_ofp };
+use crate::flight_context::{ get_flight_context, FlightContextState };
+use crate::xplane::{ send_xplane_command, set_xplane_dataref };
+use crate::soundpack::{ set_soundpack_dir, SoundpackState };
+use crate::audio_engine::{
+    stop_native_audio,
+    set_listener_zone,
+    set_cockpit_door,
+    set_zone_acoustics,
+    get_zone_acoustics,
+    set_ambience_volume,
+    set_mixer_settings,
+    get_mixer_settings,
+    set_announcement_playing,
+    AudioEngineState,
+};
+use crate::announcement_template::play_announcement_template;
+use crate::cabin_chime::{ set_chime_settings, get_chime_settings, play_cabin_chime, ChimeState };
+use crate::interphone::{ call_cabin, request_cabin_ready, set_cabin_call_lvar, InterphoneState };
+use crate::captain_pa::{
+    start_pa_recording,
+    stop_pa_recording,
+    set_captain_pa,
+    play_captain_pa,
+    CaptainPaState,
+};
+use crate::playlist::{ set_music_dir, list_tracks, next_track, PlaylistState };
+use crate::asset_analyzer::{ analyze_soundpack, set_loudness_normalization, get_loudness_normalization };
+use crate::languages::{ set_language_settings, get_language_settings, get_announcement_languages };
+use crate::airports::{ load_airport_database, get_nearest_airport, find_airport, AirportState };
+use crate::sim_clock::SimClock;
 
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -19,15 +96,82 @@ pub fn run() {
     tauri::Builder::default()
         // Manage an Arc<SimConnectState> so it can be safely shared in commands
         .manage(Arc::new(SimConnectState::new()))
+        .manage(Arc::new(LogbookState::new()))
+        .manage(Arc::new(TrackState::new()))
+        .manage(Arc::new(FlightContextState::new()))
+        .manage(Arc::new(SoundpackState::new()))
+        .manage(Arc::new(AudioEngineState::new()))
+        .manage(Arc::new(ChimeState::new()))
+        .manage(Arc::new(InterphoneState::new()))
+        .manage(Arc::new(CaptainPaState::new()))
+        .manage(Arc::new(PlaylistState::new()))
+        .manage(Arc::new(AirportState::new()))
+        .manage(Arc::new(SimClock::new()))
         .invoke_handler(
             tauri::generate_handler![
                 start_simconnect_data_collection,
                 stop_simconnect_data_collection,
                 toggle_wing_light,
-                check_simconnect_status
+                set_touchdown_announcements,
+                set_altitude_triggers,
+                get_altitude_triggers,
+                set_descent_settings,
+                get_descent_settings,
+                check_simconnect_status,
+                list_flights,
+                get_flight,
+                delete_flight,
+                export_flight,
+                get_current_flight,
+                set_track_sample_interval,
+                export_track,
+                import_ofp,
+                clear_ofp,
+                set_soundpack_dir,
+                play_announcement_template,
+                stop_native_audio,
+                set_listener_zone,
+                set_cockpit_door,
+                set_zone_acoustics,
+                get_zone_acoustics,
+                set_ambience_volume,
+                set_mixer_settings,
+                get_mixer_settings,
+                set_announcement_playing,
+                set_chime_settings,
+                get_chime_settings,
+                play_cabin_chime,
+                call_cabin,
+                request_cabin_ready,
+                set_cabin_call_lvar,
+                start_pa_recording,
+                stop_pa_recording,
+                set_captain_pa,
+                play_captain_pa,
+                set_music_dir,
+                list_tracks,
+                next_track,
+                analyze_soundpack,
+                set_loudness_normalization,
+                get_loudness_normalization,
+                set_language_settings,
+                get_language_settings,
+                get_announcement_languages,
+                load_airport_database,
+                get_nearest_airport,
+                find_airport,
+                get_flight_context,
+                set_xplane_settings,
+                get_xplane_settings,
+                send_xplane_command,
+                set_xplane_dataref
             ]
         )
         .setup(|app| {
+            // Parsing the airport database takes a moment; don't hold up the window
+            let handle = app.handle().clone();
+            std::thread::spawn(move || airports::load_default(&handle));
+
             #[cfg(debug_assertions)]
             {
                 if let Some(window) = app.get_webview_window("main") {
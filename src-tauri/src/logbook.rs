@@ -0,0 +1,407 @@
+use serde::{ Deserialize, Serialize };
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{ AtomicU32, Ordering };
+use std::sync::{ Arc, Mutex };
+use std::time::{ Instant, SystemTime, UNIX_EPOCH };
+use tauri::{ AppHandle, Manager, State, Window };
+
+use crate::flight_phase::FlightPhase;
+use crate::touchdown::{ FlightDynamicsData, TouchdownReport };
+
+/// Seconds since the Unix epoch, used for all logbook timestamps.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_ft: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTiming {
+    pub phase: FlightPhase,
+    pub started_at: u64,
+    pub duration_secs: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementEntry {
+    pub kind: String,
+    pub played_at: u64,
+}
+
+/// A single flight as stored on disk.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightRecord {
+    pub id: String,
+    pub aircraft: String,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub departure: Option<GeoPosition>,
+    pub arrival: Option<GeoPosition>,
+    pub phases: Vec<PhaseTiming>,
+    pub announcements: Vec<AnnouncementEntry>,
+    pub touchdown: Option<TouchdownReport>,
+    pub comfort_score: f64,
+}
+
+// Tells apart records opened within the same second, e.g. around a flight reset
+static NEXT_SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+impl FlightRecord {
+    fn new(aircraft: &str, departure: Option<GeoPosition>) -> Self {
+        let started_at = unix_now();
+        FlightRecord {
+            id: format!("{}-{}", started_at, NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed)),
+            aircraft: aircraft.to_string(),
+            started_at,
+            ended_at: None,
+            departure,
+            arrival: None,
+            phases: Vec::new(),
+            announcements: Vec::new(),
+            touchdown: None,
+            comfort_score: 100.0,
+        }
+    }
+
+    fn close_current_phase(&mut self, now: u64) {
+        if let Some(last) = self.phases.last_mut() {
+            if last.duration_secs.is_none() {
+                last.duration_secs = Some(now.saturating_sub(last.started_at));
+            }
+        }
+    }
+}
+
+/// Short listing entry returned by `list_flights`.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightSummary {
+    pub id: String,
+    pub aircraft: String,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub touchdown_rating: Option<String>,
+    pub comfort_score: f64,
+}
+
+impl From<&FlightRecord> for FlightSummary {
+    fn from(record: &FlightRecord) -> Self {
+        FlightSummary {
+            id: record.id.clone(),
+            aircraft: record.aircraft.clone(),
+            started_at: record.started_at,
+            ended_at: record.ended_at,
+            touchdown_rating: record.touchdown.as_ref().map(|t| t.rating.as_str().to_string()),
+            comfort_score: record.comfort_score,
+        }
+    }
+}
+
+// Minimum time between two comfort penalties so one bumpy patch isn't counted every frame
+const COMFORT_PENALTY_COOLDOWN_SECS: u64 = 5;
+
+/// Scores passenger comfort from load factor, bank and sink rate while airborne.
+pub struct ComfortTracker {
+    last_penalty: Option<Instant>,
+}
+
+impl ComfortTracker {
+    pub fn new() -> Self {
+        ComfortTracker { last_penalty: None }
+    }
+
+    /// Returns the penalty to subtract from the comfort score for this sample.
+    pub fn update(&mut self, sample: &FlightDynamicsData) -> f64 {
+        self.update_at(sample, Instant::now())
+    }
+
+    fn update_at(&mut self, sample: &FlightDynamicsData, now: Instant) -> f64 {
+        if sample.sim_on_ground != 0.0 {
+            return 0.0;
+        }
+
+        let g_deviation = (sample.g_force - 1.0).abs();
+        let bank = sample.bank.abs();
+        let vertical_speed = sample.vertical_speed;
+
+        let mut penalty = 0.0;
+        if g_deviation > 0.4 {
+            penalty += 3.0;
+        } else if g_deviation > 0.25 {
+            penalty += 1.0;
+        }
+        if bank > 35.0 {
+            penalty += 2.0;
+        }
+        if vertical_speed < -3000.0 {
+            penalty += 1.0;
+        }
+        if penalty == 0.0 {
+            return 0.0;
+        }
+
+        if let Some(last) = self.last_penalty {
+            if now.duration_since(last).as_secs() < COMFORT_PENALTY_COOLDOWN_SECS {
+                return 0.0;
+            }
+        }
+        self.last_penalty = Some(now);
+        penalty
+    }
+}
+
+/// Holds the flight currently being recorded.
+pub struct LogbookState {
+    pub current: Mutex<Option<FlightRecord>>,
+}
+
+impl LogbookState {
+    pub fn new() -> Self {
+        LogbookState {
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Opens a new record, saving any record that is still open.
+    pub fn open(&self, app: &AppHandle, aircraft: &str, departure: Option<GeoPosition>) {
+        self.close(app, None);
+        let record = FlightRecord::new(aircraft, departure);
+        println!("Logbook: opened flight {} ({})", record.id, record.aircraft);
+        *self.current.lock().unwrap() = Some(record);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.current.lock().unwrap().is_some()
+    }
+
+    pub fn record_phase(&self, phase: FlightPhase) {
+        if let Some(record) = self.current.lock().unwrap().as_mut() {
+            let now = unix_now();
+            record.close_current_phase(now);
+            record.phases.push(PhaseTiming {
+                phase,
+                started_at: now,
+                duration_secs: None,
+            });
+        }
+    }
+
+    pub fn record_announcement(&self, kind: &str) {
+        if let Some(record) = self.current.lock().unwrap().as_mut() {
+            record.announcements.push(AnnouncementEntry {
+                kind: kind.to_string(),
+                played_at: unix_now(),
+            });
+        }
+    }
+
+    pub fn record_touchdown(&self, report: &TouchdownReport) {
+        if let Some(record) = self.current.lock().unwrap().as_mut() {
+            record.touchdown = Some(report.clone());
+        }
+    }
+
+    pub fn apply_comfort_penalty(&self, penalty: f64) {
+        if let Some(record) = self.current.lock().unwrap().as_mut() {
+            record.comfort_score = (record.comfort_score - penalty).max(0.0);
+        }
+    }
+
+    /// Saves the current record without closing it.
+    pub fn checkpoint(&self, app: &AppHandle) {
+        if let Some(record) = self.current.lock().unwrap().as_ref() {
+            if let Err(e) = save_record(app, record) {
+                println!("Logbook: failed to save flight {}: {}", record.id, e);
+            }
+        }
+    }
+
+    /// Closes and saves the current record.
+    pub fn close(&self, app: &AppHandle, arrival: Option<GeoPosition>) {
+        let record = self.current.lock().unwrap().take();
+        if let Some(mut record) = record {
+            let now = unix_now();
+            record.close_current_phase(now);
+            record.ended_at = Some(now);
+            if arrival.is_some() {
+                record.arrival = arrival;
+            }
+            match save_record(app, &record) {
+                Ok(path) => println!("Logbook: saved flight {} to {}", record.id, path.display()),
+                Err(e) => println!("Logbook: failed to save flight {}: {}", record.id, e),
+            }
+        }
+    }
+}
+
+/// Records an announcement against the open flight, if any.
+/// Usable from helper threads that only hold a `Window`.
+pub fn record_announcement(window: &Window, kind: &str) {
+    let logbook = window.state::<Arc<LogbookState>>();
+    logbook.record_announcement(kind);
+}
+
+fn logbook_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+        .join("logbook");
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create logbook dir: {}", e))?;
+    Ok(dir)
+}
+
+fn record_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    // Ids are generated by us: digits and a dash; reject anything that could escape the dir
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid flight id: {}", id));
+    }
+    Ok(logbook_dir(app)?.join(format!("{}.json", id)))
+}
+
+fn save_record(app: &AppHandle, record: &FlightRecord) -> Result<PathBuf, String> {
+    let path = record_path(app, &record.id)?;
+    let json = serde_json::to_string_pretty(record)
+        .map_err(|e| format!("Failed to serialize flight: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn load_record(app: &AppHandle, id: &str) -> Result<FlightRecord, String> {
+    let path = record_path(app, id)?;
+    let json = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read flight {}: {}", id, e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse flight {}: {}", id, e))
+}
+
+/// Lists all stored flights, newest first.
+#[tauri::command]
+pub fn list_flights(app: AppHandle) -> Result<Vec<FlightSummary>, String> {
+    let dir = logbook_dir(&app)?;
+    let entries = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read logbook dir: {}", e))?;
+
+    let mut flights = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        match fs::read_to_string(&path).map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<FlightRecord>(&json).map_err(|e| e.to_string()))
+        {
+            Ok(record) => flights.push(FlightSummary::from(&record)),
+            Err(e) => println!("Logbook: skipping {}: {}", path.display(), e),
+        }
+    }
+
+    flights.sort_by_key(|f| std::cmp::Reverse(f.started_at));
+    Ok(flights)
+}
+
+/// Returns the full record of a stored flight.
+#[tauri::command]
+pub fn get_flight(app: AppHandle, id: String) -> Result<FlightRecord, String> {
+    load_record(&app, &id)
+}
+
+/// Deletes a stored flight.
+#[tauri::command]
+pub fn delete_flight(app: AppHandle, id: String) -> Result<(), String> {
+    let path = record_path(&app, &id)?;
+    fs::remove_file(&path)
+        .map_err(|e| format!("Failed to delete flight {}: {}", id, e))
+}
+
+/// Writes a stored flight as pretty-printed JSON to `path`.
+#[tauri::command]
+pub fn export_flight(app: AppHandle, id: String, path: String) -> Result<(), String> {
+    let record = load_record(&app, &id)?;
+    let json = serde_json::to_string_pretty(&record)
+        .map_err(|e| format!("Failed to serialize flight: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Returns the flight currently being recorded, if any.
+#[tauri::command]
+pub fn get_current_flight(state: State<Arc<LogbookState>>) -> Option<FlightRecord> {
+    state.current.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn airborne(g_force: f64, bank: f64, vertical_speed: f64) -> FlightDynamicsData {
+        FlightDynamicsData { g_force, bank, vertical_speed, ..FlightDynamicsData::default() }
+    }
+
+    #[test]
+    fn comfort_penalties_add_up_and_cool_down() {
+        let start = Instant::now();
+        let mut tracker = ComfortTracker::new();
+        assert_eq!(tracker.update_at(&airborne(1.1, 10.0, -500.0), start), 0.0);
+        assert_eq!(tracker.update_at(&airborne(1.5, 40.0, -3500.0), start), 6.0);
+        // Still the same bumpy patch
+        assert_eq!(tracker.update_at(&airborne(1.3, 0.0, 0.0), start + Duration::from_secs(4)), 0.0);
+        assert_eq!(tracker.update_at(&airborne(1.3, 0.0, 0.0), start + Duration::from_secs(5)), 1.0);
+        // Nothing counts on the ground
+        let taxiing = FlightDynamicsData { sim_on_ground: 1.0, ..airborne(2.0, 0.0, 0.0) };
+        assert_eq!(tracker.update_at(&taxiing, start + Duration::from_secs(60)), 0.0);
+    }
+
+    #[test]
+    fn phases_are_timed_and_closed() {
+        let mut record = FlightRecord::new("A320", None);
+        record.phases.push(PhaseTiming { phase: FlightPhase::Climb, started_at: 100, duration_secs: None });
+        record.close_current_phase(160);
+        record.close_current_phase(200);
+        assert_eq!(record.phases[0].duration_secs, Some(60));
+
+        let logbook = LogbookState::new();
+        logbook.record_phase(FlightPhase::Cruise);
+        assert!(!logbook.is_open());
+        *logbook.current.lock().unwrap() = Some(record);
+        logbook.record_phase(FlightPhase::Cruise);
+        logbook.record_phase(FlightPhase::Descent);
+        let record = logbook.current.lock().unwrap().clone().unwrap();
+        let phases: Vec<_> = record.phases.iter().map(|timing| timing.phase).collect();
+        assert_eq!(phases, [FlightPhase::Climb, FlightPhase::Cruise, FlightPhase::Descent]);
+        assert!(record.phases[1].duration_secs.is_some());
+        assert!(record.phases[2].duration_secs.is_none());
+    }
+
+    #[test]
+    fn records_announcements_and_penalties() {
+        let logbook = LogbookState::new();
+        *logbook.current.lock().unwrap() = Some(FlightRecord::new("B738", None));
+        logbook.record_announcement("10k_feet");
+        logbook.apply_comfort_penalty(30.0);
+        logbook.apply_comfort_penalty(80.0);
+
+        let record = logbook.current.lock().unwrap().clone().unwrap();
+        assert_eq!(record.announcements.len(), 1);
+        assert_eq!(record.announcements[0].kind, "10k_feet");
+        assert_eq!(record.comfort_score, 0.0);
+        let summary = FlightSummary::from(&record);
+        assert_eq!(summary.aircraft, "B738");
+        assert!(summary.touchdown_rating.is_none());
+        assert_ne!(FlightRecord::new("B738", None).id, record.id);
+    }
+}
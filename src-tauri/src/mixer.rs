@@ -0,0 +1,281 @@
+use std::time::Duration;
+use rodio::{ OutputStreamHandle, Sink, Source };
+use serde::{ Deserialize, Serialize };
+
+/// Mixer buses. Sounds on a higher-priority bus duck the buses below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bus {
+    Music,
+    Ambience,
+    Chimes,
+    Pa,
+}
+
+impl Bus {
+    fn priority(&self) -> u8 {
+        match self {
+            Bus::Music | Bus::Ambience => 0,
+            Bus::Chimes => 1,
+            Bus::Pa => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuckCurve {
+    /// Constant rate over the attack/release time
+    Linear,
+    /// Fast at first, settling gently; sounds more natural on music
+    Exponential,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BusVolumes {
+    pub music: f32,
+    pub ambience: f32,
+    pub chimes: f32,
+    pub pa: f32,
+}
+
+impl Default for BusVolumes {
+    fn default() -> Self {
+        BusVolumes { music: 0.8, ambience: 0.6, chimes: 0.8, pa: 1.0 }
+    }
+}
+
+impl BusVolumes {
+    pub fn get(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Music => self.music,
+            Bus::Ambience => self.ambience,
+            Bus::Chimes => self.chimes,
+            Bus::Pa => self.pa,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DuckSettings {
+    /// Gain of each bus while ducked; the PA bus is never ducked
+    pub music_gain: f32,
+    pub ambience_gain: f32,
+    pub chimes_gain: f32,
+    pub attack_ms: u64,
+    pub release_ms: u64,
+    pub curve: DuckCurve,
+}
+
+impl Default for DuckSettings {
+    fn default() -> Self {
+        DuckSettings {
+            music_gain: 0.25,
+            ambience_gain: 0.3,
+            chimes_gain: 0.6,
+            attack_ms: 300,
+            release_ms: 1500,
+            curve: DuckCurve::Exponential,
+        }
+    }
+}
+
+impl DuckSettings {
+    fn gain(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Music => self.music_gain,
+            Bus::Ambience => self.ambience_gain,
+            Bus::Chimes => self.chimes_gain,
+            Bus::Pa => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MixerSettings {
+    pub volumes: BusVolumes,
+    pub ducking: DuckSettings,
+    /// How long a new music track fades in over the previous one
+    pub crossfade_ms: u64,
+}
+
+impl Default for MixerSettings {
+    fn default() -> Self {
+        MixerSettings {
+            volumes: BusVolumes::default(),
+            ducking: DuckSettings::default(),
+            crossfade_ms: 4000,
+        }
+    }
+}
+
+const BUSES: [Bus; 4] = [Bus::Music, Bus::Ambience, Bus::Chimes, Bus::Pa];
+
+fn bus_index(bus: Bus) -> usize {
+    BUSES.iter().position(|b| *b == bus).unwrap()
+}
+
+/// Moves `current` towards `target` over about `time`.
+fn ramp(current: f32, target: f32, elapsed: Duration, time: Duration, curve: DuckCurve) -> f32 {
+    let fraction = if time.is_zero() { 1.0 } else { (elapsed.as_secs_f32() / time.as_secs_f32()).min(1.0) };
+    match curve {
+        // Full scale is 0..1, so the step is the fraction of the ramp time elapsed
+        DuckCurve::Linear if current < target => (current + fraction).min(target),
+        DuckCurve::Linear => (current - fraction).max(target),
+        DuckCurve::Exponential => current + (target - current) * fraction,
+    }
+}
+
+struct Voice {
+    sink: Sink,
+    bus: Bus,
+    volume: f32,
+}
+
+struct MusicTrack {
+    sink: Sink,
+    // Crossfade level, 0..1
+    level: f32,
+    fading_out: bool,
+}
+
+/// The audio thread's view of everything playing. Owns the sinks, so it lives on
+/// the audio thread with the output stream.
+pub struct Mixer {
+    handle: OutputStreamHandle,
+    voices: Vec<Voice>,
+    // The current track last; earlier ones are fading out
+    music: Vec<MusicTrack>,
+    ambience: Vec<Option<Sink>>,
+    ambience_levels: [f32; 4],
+    ambience_targets: [f32; 4],
+    // Current duck gain per bus, in `BUSES` order
+    duck: [f32; 4],
+    external_duck: bool,
+}
+
+// Ambience layers cross-fade to new levels over this long
+const AMBIENCE_FADE: Duration = Duration::from_secs(3);
+
+impl Mixer {
+    pub fn new(handle: OutputStreamHandle) -> Self {
+        Mixer {
+            handle,
+            voices: Vec::new(),
+            music: Vec::new(),
+            ambience: Vec::new(),
+            ambience_levels: [0.0; 4],
+            ambience_targets: [0.0; 4],
+            duck: [1.0; 4],
+            external_duck: false,
+        }
+    }
+
+    fn new_sink(&self, source: Box<dyn Source<Item = f32> + Send>) -> Option<Sink> {
+        match Sink::try_new(&self.handle) {
+            Ok(sink) => {
+                sink.set_volume(0.0);
+                sink.append(source);
+                Some(sink)
+            },
+            Err(e) => {
+                println!("Failed to create audio sink: {}", e);
+                None
+            },
+        }
+    }
+
+    pub fn play(&mut self, source: Box<dyn Source<Item = f32> + Send>, bus: Bus, volume: f32) {
+        if let Some(sink) = self.new_sink(source) {
+            self.voices.push(Voice { sink, bus, volume });
+        }
+    }
+
+    /// Stops announcements, chimes and effects; music and ambience keep going.
+    pub fn stop_all(&mut self) {
+        for voice in self.voices.drain(..) {
+            voice.sink.stop();
+        }
+    }
+
+    /// Crossfades to a new music track, or fades the music out with None.
+    pub fn set_music(&mut self, source: Option<Box<dyn Source<Item = f32> + Send>>) {
+        for track in &mut self.music {
+            track.fading_out = true;
+        }
+        if let Some(sink) = source.and_then(|source| self.new_sink(source)) {
+            self.music.push(MusicTrack { sink, level: 0.0, fading_out: false });
+        }
+    }
+
+    pub fn set_ambience(&mut self, loops: Vec<Option<Box<dyn Source<Item = f32> + Send>>>) {
+        for sink in self.ambience.drain(..).flatten() {
+            sink.stop();
+        }
+        self.ambience = loops.into_iter()
+            .map(|source| source.and_then(|source| self.new_sink(source)))
+            .collect();
+        // Fade the new loops in from silence
+        self.ambience_levels = [0.0; 4];
+    }
+
+    pub fn set_ambience_levels(&mut self, levels: [f32; 4]) {
+        self.ambience_targets = levels;
+    }
+
+    /// Announcements playing outside the engine (the webview) count as PA.
+    pub fn set_external_duck(&mut self, active: bool) {
+        self.external_duck = active;
+    }
+
+    /// Advances ducking and fades by `elapsed` and applies all volumes.
+    pub fn tick(&mut self, elapsed: Duration, settings: &MixerSettings) {
+        self.voices.retain(|voice| !voice.sink.empty());
+
+        let top_priority = self.voices.iter()
+            .map(|voice| voice.bus.priority())
+            .chain(self.external_duck.then_some(Bus::Pa.priority()))
+            .max();
+
+        let ducking = settings.ducking;
+        for bus in BUSES {
+            let ducked = top_priority.is_some_and(|top| top > bus.priority());
+            let (target, time) = if ducked {
+                (ducking.gain(bus), Duration::from_millis(ducking.attack_ms))
+            } else {
+                (1.0, Duration::from_millis(ducking.release_ms))
+            };
+            let duck = &mut self.duck[bus_index(bus)];
+            *duck = ramp(*duck, target, elapsed, time, ducking.curve);
+        }
+
+        let gain = |bus: Bus| settings.volumes.get(bus) * self.duck[bus_index(bus)];
+        for voice in &self.voices {
+            voice.sink.set_volume(voice.volume * gain(voice.bus));
+        }
+        let crossfade = Duration::from_millis(settings.crossfade_ms);
+        for track in &mut self.music {
+            let target = if track.fading_out { 0.0 } else { 1.0 };
+            track.level = ramp(track.level, target, elapsed, crossfade, DuckCurve::Linear);
+            track.sink.set_volume(track.level * gain(Bus::Music));
+        }
+        self.music.retain(|track| {
+            let done = track.sink.empty() || (track.fading_out && track.level <= 0.0);
+            if done {
+                track.sink.stop();
+            }
+            !done
+        });
+        for (level, target) in self.ambience_levels.iter_mut().zip(self.ambience_targets) {
+            *level = ramp(*level, target, elapsed, AMBIENCE_FADE, DuckCurve::Exponential);
+        }
+        for (sink, level) in self.ambience.iter().zip(self.ambience_levels) {
+            if let Some(sink) = sink {
+                sink.set_volume(level * gain(Bus::Ambience));
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{ Emitter, State, Window };
+
+use crate::flight_context::FlightContextState;
+
+/// Airport block of a SimBrief OFP.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfpAirport {
+    pub icao: String,
+    pub iata: Option<String>,
+    pub name: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub elevation_ft: Option<f64>,
+    /// Hours ahead of UTC
+    pub utc_offset_hours: Option<f64>,
+    pub metar: Option<String>,
+}
+
+/// The subset of a SimBrief OFP that simpa uses for announcements.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfpData {
+    pub airline_icao: Option<String>,
+    pub flight_number: Option<String>,
+    pub callsign: Option<String>,
+    pub origin: OfpAirport,
+    pub destination: OfpAirport,
+    pub cruise_altitude_ft: Option<u32>,
+    pub block_time_secs: Option<u64>,
+    pub air_distance_nm: Option<f64>,
+}
+
+impl OfpData {
+    /// Airline code and flight number, e.g. `BAW123`.
+    pub fn flight_designator(&self) -> Option<String> {
+        match (&self.airline_icao, &self.flight_number) {
+            (Some(airline), Some(number)) => Some(format!("{}{}", airline, number)),
+            (None, Some(number)) => Some(number.clone()),
+            _ => self.callsign.clone(),
+        }
+    }
+
+    pub fn cruise_flight_level(&self) -> Option<u32> {
+        self.cruise_altitude_ft.map(|alt| alt / 100)
+    }
+}
+
+/// Loads an OFP downloaded from SimBrief in either XML or JSON format.
+pub fn load_ofp(path: &Path) -> Result<OfpData, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_ofp(&contents)
+}
+
+fn parse_ofp(contents: &str) -> Result<OfpData, String> {
+    let trimmed = contents.trim_start_matches('\u{feff}').trim_start();
+
+    let ofp = if trimmed.starts_with('{') {
+        parse_json(trimmed)?
+    } else if trimmed.starts_with('<') {
+        parse_xml(trimmed)?
+    } else {
+        return Err("Unrecognised OFP format, expected SimBrief XML or JSON".to_string());
+    };
+
+    if ofp.origin.icao.is_empty() || ofp.destination.icao.is_empty() {
+        return Err("OFP does not contain an origin and destination".to_string());
+    }
+    Ok(ofp)
+}
+
+fn parse_json(contents: &str) -> Result<OfpData, String> {
+    let root: Value = serde_json::from_str(contents)
+        .map_err(|e| format!("Invalid OFP JSON: {}", e))?;
+
+    // SimBrief returns numbers as strings in its JSON output
+    Ok(build_ofp(|section, key| {
+        match root.get(section)?.get(key)? {
+            Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }))
+}
+
+fn parse_xml(contents: &str) -> Result<OfpData, String> {
+    if xml_section(contents, "OFP").is_none() {
+        return Err("Invalid OFP XML: missing <OFP> root".to_string());
+    }
+
+    Ok(build_ofp(|section, key| {
+        let value = xml_unescape(xml_section(xml_section(contents, section)?, key)?.trim());
+        if value.is_empty() { None } else { Some(value) }
+    }))
+}
+
+/// Maps SimBrief's `section.key` fields onto `OfpData`. Both the XML and the
+/// JSON output share the same field names.
+fn build_ofp(text: impl Fn(&str, &str) -> Option<String>) -> OfpData {
+    // `prefix` names the airport in the times and weather sections
+    let airport = |section: &str, prefix: &str| OfpAirport {
+        icao: text(section, "icao_code").unwrap_or_default(),
+        iata: text(section, "iata_code"),
+        name: text(section, "name"),
+        latitude: text(section, "pos_lat").and_then(|v| v.parse().ok()),
+        longitude: text(section, "pos_long").and_then(|v| v.parse().ok()),
+        elevation_ft: text(section, "elevation").and_then(|v| v.parse().ok()),
+        utc_offset_hours: text("times", &format!("{}_timezone", prefix)).and_then(|v| v.parse().ok()),
+        metar: text("weather", &format!("{}_metar", prefix)),
+    };
+
+    OfpData {
+        airline_icao: text("general", "icao_airline"),
+        flight_number: text("general", "flight_number"),
+        callsign: text("atc", "callsign"),
+        origin: airport("origin", "orig"),
+        destination: airport("destination", "dest"),
+        cruise_altitude_ft: text("general", "initial_altitude").and_then(|v| v.parse().ok()),
+        block_time_secs: text("times", "est_block")
+            .or_else(|| text("times", "sched_block"))
+            .and_then(|v| v.parse().ok()),
+        air_distance_nm: text("general", "air_distance").and_then(|v| v.parse().ok()),
+    }
+}
+
+/// Returns the inner text of the first `<tag>...</tag>` element.
+/// SimBrief OFPs use plain elements without attributes, which is all this handles.
+pub(crate) fn xml_section<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+    Some(&xml[start..end])
+}
+
+pub(crate) fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Imports a SimBrief OFP file into the flight context.
+#[tauri::command]
+pub fn import_ofp(
+    window: Window,
+    state: State<Arc<FlightContextState>>,
+    path: String
+) -> Result<OfpData, String> {
+    let ofp = load_ofp(Path::new(&path))?;
+    println!("Imported OFP: {} {} -> {}",
+        ofp.flight_designator().unwrap_or_default(), ofp.origin.icao, ofp.destination.icao);
+
+    state.context.lock().unwrap().ofp = Some(ofp.clone());
+    let _ = window.emit("flight-context-changed", state.snapshot());
+    Ok(ofp)
+}
+
+/// Forgets the imported OFP.
+#[tauri::command]
+pub fn clear_ofp(window: Window, state: State<Arc<FlightContextState>>) {
+    state.context.lock().unwrap().ofp = None;
+    let _ = window.emit("flight-context-changed", state.snapshot());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = "\u{feff}<?xml version=\"1.0\"?>
+<OFP>
+  <general><icao_airline>BAW</icao_airline><flight_number>123</flight_number>
+    <initial_altitude>35000</initial_altitude><air_distance>560</air_distance></general>
+  <origin><icao_code>EGLL</icao_code><iata_code>LHR</iata_code><name>London &amp; Heathrow</name>
+    <pos_lat>51.4706</pos_lat><pos_long>-0.461941</pos_long><elevation>83</elevation></origin>
+  <destination><icao_code>LFPG</icao_code><name>Paris</name><elevation> </elevation></destination>
+  <atc><callsign>SPEEDBIRD123</callsign></atc>
+  <times><est_block>4500</est_block><sched_block>4800</sched_block>
+    <orig_timezone>1</orig_timezone><dest_timezone>2</dest_timezone></times>
+  <weather><dest_metar>LFPG 181230Z 24010KT 9999 12/08 Q1015</dest_metar></weather>
+</OFP>";
+
+    #[test]
+    fn reads_simbrief_xml() {
+        let ofp = parse_ofp(XML).unwrap();
+        assert_eq!(ofp.flight_designator().as_deref(), Some("BAW123"));
+        assert_eq!(ofp.cruise_flight_level(), Some(350));
+        assert_eq!(ofp.block_time_secs, Some(4500));
+        assert_eq!(ofp.air_distance_nm, Some(560.0));
+        assert_eq!(ofp.origin.name.as_deref(), Some("London & Heathrow"));
+        assert_eq!(ofp.origin.utc_offset_hours, Some(1.0));
+        assert_eq!(ofp.destination.utc_offset_hours, Some(2.0));
+        assert_eq!(ofp.destination.metar.as_deref(), Some("LFPG 181230Z 24010KT 9999 12/08 Q1015"));
+        // Blank and missing fields stay unknown
+        assert_eq!(ofp.destination.elevation_ft, None);
+        assert_eq!(ofp.destination.iata, None);
+        assert_eq!(ofp.origin.metar, None);
+    }
+
+    #[test]
+    fn reads_simbrief_json_with_string_numbers() {
+        let json = r#"{
+            "general": { "flight_number": "4410", "initial_altitude": "37000" },
+            "origin": { "icao_code": "KJFK", "elevation": 13 },
+            "destination": { "icao_code": "KLAX", "pos_lat": "33.9425" },
+            "atc": { "callsign": "DAL4410" },
+            "times": { "sched_block": "21600", "orig_timezone": "-4", "dest_timezone": "-7" },
+            "weather": { "orig_metar": "KJFK 181251Z 31012KT 10SM FEW250 18/04 A3012", "dest_metar": "" }
+        }"#;
+        let ofp = parse_ofp(json).unwrap();
+        assert_eq!(ofp.flight_designator().as_deref(), Some("4410"));
+        assert_eq!(ofp.cruise_altitude_ft, Some(37000));
+        // Without an estimate the scheduled block time is used
+        assert_eq!(ofp.block_time_secs, Some(21600));
+        assert_eq!(ofp.origin.elevation_ft, Some(13.0));
+        assert_eq!(ofp.destination.latitude, Some(33.9425));
+        assert_eq!(ofp.origin.utc_offset_hours, Some(-4.0));
+        assert!(ofp.origin.metar.is_some());
+        assert_eq!(ofp.destination.metar, None);
+        assert_eq!(ofp.air_distance_nm, None);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(parse_ofp("{ \"general\": ").unwrap_err().starts_with("Invalid OFP JSON"));
+        assert!(parse_ofp("<html><body>Not an OFP</body></html>").unwrap_err().contains("missing <OFP> root"));
+        assert!(parse_ofp("ICAO: EGLL").unwrap_err().starts_with("Unrecognised OFP format"));
+        let no_destination = "<OFP><origin><icao_code>EGLL</icao_code></origin></OFP>";
+        assert!(parse_ofp(no_destination).unwrap_err().contains("origin and destination"));
+        // An unclosed element reads as missing rather than swallowing the rest
+        let unclosed = "<OFP><general><flight_number>12</general><origin><icao_code>EGLL</icao_code></origin>\
+            <destination><icao_code>LFPG</icao_code></destination></OFP>";
+        assert_eq!(parse_ofp(unclosed).unwrap().flight_number, None);
+    }
+}
@@ -0,0 +1,368 @@
+use serde::{ Deserialize, Serialize };
+use std::collections::HashMap;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::atomic::{ AtomicU64, Ordering };
+use std::sync::{ Arc, Mutex };
+use std::thread;
+use std::time::{ Duration, Instant };
+use rand::seq::SliceRandom;
+use rodio::Source;
+use tauri::{ AppHandle, Manager, State, Window };
+
+use crate::audio_engine::AudioEngineState;
+use crate::soundpack::{ self, SoundpackState, CHANNELS, CLIP_EXTENSIONS, SAMPLE_RATE };
+
+/// Saved playlist positions, in the app data dir.
+const PLAYLISTS_FILE: &str = "playlists.json";
+// How often the player checks whether the next track is due
+const POLL: Duration = Duration::from_millis(250);
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where a playlist was left off, keyed by its directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedPosition {
+    /// Shuffled play order, as file names
+    order: Vec<String>,
+    index: usize,
+    position_secs: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackInfo {
+    pub name: String,
+    pub playing: bool,
+}
+
+/// Counts the samples played so the player knows where it is in the track.
+struct Counted<S> {
+    source: S,
+    played: Arc<AtomicU64>,
+}
+
+impl<S: Source<Item = f32>> Iterator for Counted<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        self.played.fetch_add(1, Ordering::Relaxed);
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Counted<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+struct Playing {
+    dir: PathBuf,
+    order: Vec<String>,
+    index: usize,
+    // Where the current track was resumed from
+    offset: Duration,
+    played: Arc<AtomicU64>,
+    // Filled in by the player thread once the track has been measured
+    length: Option<Duration>,
+}
+
+impl Playing {
+    fn track(&self) -> PathBuf {
+        self.dir.join(&self.order[self.index])
+    }
+
+    fn position(&self) -> Duration {
+        let samples = self.played.load(Ordering::Relaxed) as f64;
+        self.offset + Duration::from_secs_f64(samples / (CHANNELS as u32 * SAMPLE_RATE) as f64)
+    }
+
+    fn saved(&self) -> SavedPosition {
+        SavedPosition {
+            order: self.order.clone(),
+            index: self.index,
+            position_secs: self.position().as_secs_f64(),
+        }
+    }
+}
+
+/// Boarding music played natively from a user folder.
+pub struct PlaylistState {
+    pub dir: Mutex<Option<PathBuf>>,
+    playing: Mutex<Option<Playing>>,
+    // Bumped on every start/stop so a stale player thread exits
+    generation: Mutex<u64>,
+}
+
+impl PlaylistState {
+    pub fn new() -> Self {
+        PlaylistState {
+            dir: Mutex::new(None),
+            playing: Mutex::new(None),
+            generation: Mutex::new(0),
+        }
+    }
+}
+
+/// Audio files directly in `dir`, sorted by name.
+fn scan(dir: &Path) -> Vec<String> {
+    let mut tracks: Vec<String> = fs::read_dir(dir).into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter(|entry| {
+            entry.path().extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| CLIP_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        })
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .collect();
+    tracks.sort();
+    tracks
+}
+
+/// Picks the airline's playlist: a subfolder named after the OFP airline code (e.g. `DLH`),
+/// else one whose name appears in the aircraft title (the livery), else the folder itself.
+fn playlist_dir(root: &Path, airline: Option<&str>, aircraft: &str) -> PathBuf {
+    let subdirs: Vec<(String, PathBuf)> = fs::read_dir(root).into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| Some((entry.file_name().to_str()?.to_lowercase(), entry.path())))
+        .filter(|(_, path)| !scan(path).is_empty())
+        .collect();
+
+    let aircraft = aircraft.to_lowercase();
+    airline
+        .and_then(|airline| subdirs.iter().find(|(name, _)| *name == airline.to_lowercase()))
+        .or_else(|| subdirs.iter().find(|(name, _)| aircraft.contains(name.as_str())))
+        .map(|(_, path)| path.clone())
+        .unwrap_or_else(|| root.to_path_buf())
+}
+
+fn playlists_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir.join(PLAYLISTS_FILE))
+}
+
+fn load_positions(app: &AppHandle) -> HashMap<String, SavedPosition> {
+    playlists_path(app).ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_position(app: &AppHandle, playing: &Playing) {
+    let result = playlists_path(app).and_then(|path| {
+        let mut positions = load_positions(app);
+        positions.insert(playing.dir.to_string_lossy().to_string(), playing.saved());
+        let json = serde_json::to_string_pretty(&positions)
+            .map_err(|e| format!("Failed to serialize playlists: {}", e))?;
+        fs::write(&path, json)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    });
+    if let Err(e) = result {
+        println!("Failed to save playlist position: {}", e);
+    }
+}
+
+/// Crossfades to the current track of `playing`, from its offset.
+fn start_track(engine: &AudioEngineState, playing: &mut Playing) -> Result<(), String> {
+    let path = playing.track();
+    playing.played = Arc::new(AtomicU64::new(0));
+    playing.length = None;
+    let source = Counted {
+        source: soundpack::stream_file(&path)?.skip_duration(playing.offset),
+        played: playing.played.clone(),
+    };
+    println!("Boarding music: playing {} from {:.0}s", path.display(), playing.offset.as_secs_f32());
+    engine.play_music(Box::new(source))
+}
+
+/// Moves to the next track, reshuffling after the last one. Returns its name.
+fn advance(app: &AppHandle) -> Result<String, String> {
+    let state = app.state::<Arc<PlaylistState>>();
+    let mut playing = state.playing.lock().unwrap();
+    let playing = playing.as_mut().ok_or_else(|| "No boarding music is playing".to_string())?;
+
+    playing.index += 1;
+    if playing.index >= playing.order.len() {
+        playing.order.shuffle(&mut rand::thread_rng());
+        playing.index = 0;
+    }
+    playing.offset = Duration::ZERO;
+    start_track(&app.state::<Arc<AudioEngineState>>(), playing)?;
+    save_position(app, playing);
+    Ok(playing.order[playing.index].clone())
+}
+
+/// Measures each track as it starts and moves on in time to crossfade into the next.
+fn run_player(app: AppHandle, generation: u64) {
+    let state = app.state::<Arc<PlaylistState>>().inner().clone();
+    let engine = app.state::<Arc<AudioEngineState>>().inner().clone();
+    let analyzer = app.state::<Arc<SoundpackState>>().analyzer.clone();
+    if let Err(e) = analyzer.open_cache(&app) {
+        println!("Track lengths won't be cached: {}", e);
+    }
+    let mut last_save = Instant::now();
+
+    loop {
+        thread::sleep(POLL);
+        if *state.generation.lock().unwrap() != generation {
+            break;
+        }
+
+        let unmeasured = state.playing.lock().unwrap().as_ref()
+            .filter(|playing| playing.length.is_none())
+            .map(Playing::track);
+        if let Some(path) = unmeasured {
+            match analyzer.analysis(&path, None) {
+                Ok(analysis) => {
+                    let length = Duration::from_secs_f64(analysis.duration_secs);
+                    if let Some(playing) = state.playing.lock().unwrap().as_mut().filter(|p| p.track() == path) {
+                        playing.length = Some(length);
+                    }
+                },
+                Err(e) => println!("Failed to measure {}: {}", path.display(), e),
+            }
+        }
+
+        let crossfade = Duration::from_millis(engine.mixer.lock().unwrap().crossfade_ms);
+        let due = state.playing.lock().unwrap().as_ref()
+            .and_then(|playing| Some(playing.position() + crossfade >= playing.length?))
+            .unwrap_or(false);
+        if due {
+            if let Err(e) = advance(&app) {
+                println!("Failed to start the next track: {}", e);
+            }
+            last_save = Instant::now();
+        } else if last_save.elapsed() >= SAVE_INTERVAL {
+            if let Some(playing) = state.playing.lock().unwrap().as_ref() {
+                save_position(&app, playing);
+            }
+            last_save = Instant::now();
+        }
+    }
+}
+
+/// Starts the boarding music playlist for the airline, resuming where it was left off.
+/// Returns false when no music folder is set or it has no tracks.
+pub fn start(window: &Window, airline: Option<&str>, aircraft: &str) -> Result<bool, String> {
+    let app = window.app_handle().clone();
+    let state = app.state::<Arc<PlaylistState>>();
+    let root = match state.dir.lock().unwrap().clone() {
+        Some(root) => root,
+        None => return Ok(false),
+    };
+    let dir = playlist_dir(&root, airline, aircraft);
+    let tracks = scan(&dir);
+    if tracks.is_empty() {
+        return Ok(false);
+    }
+
+    // Resume the saved order unless tracks were added or removed since
+    let saved = load_positions(&app).remove(&*dir.to_string_lossy())
+        .filter(|saved| {
+            let mut order = saved.order.clone();
+            order.sort();
+            order == tracks && saved.index < tracks.len()
+        });
+    let mut playing = match saved {
+        Some(saved) => Playing {
+            dir,
+            order: saved.order,
+            index: saved.index,
+            offset: Duration::from_secs_f64(saved.position_secs.max(0.0)),
+            played: Arc::new(AtomicU64::new(0)),
+            length: None,
+        },
+        None => {
+            let mut order = tracks;
+            order.shuffle(&mut rand::thread_rng());
+            Playing {
+                dir,
+                order,
+                index: 0,
+                offset: Duration::ZERO,
+                played: Arc::new(AtomicU64::new(0)),
+                length: None,
+            }
+        },
+    };
+
+    start_track(&app.state::<Arc<AudioEngineState>>(), &mut playing)?;
+    *state.playing.lock().unwrap() = Some(playing);
+    let generation = {
+        let mut current = state.generation.lock().unwrap();
+        *current += 1;
+        *current
+    };
+    thread::spawn(move || run_player(app, generation));
+    Ok(true)
+}
+
+/// Fades the boarding music out and saves where it got to.
+pub fn stop(window: &Window) {
+    let state = window.state::<Arc<PlaylistState>>();
+    *state.generation.lock().unwrap() += 1;
+    let playing = state.playing.lock().unwrap().take();
+    if let Some(playing) = playing {
+        save_position(window.app_handle(), &playing);
+        if let Err(e) = window.state::<Arc<AudioEngineState>>().stop_music() {
+            println!("Failed to stop boarding music: {}", e);
+        }
+    }
+}
+
+/// Sets the folder boarding music is played from, or clears it with None.
+/// Subfolders named after an airline code or livery hold airline-specific playlists.
+#[tauri::command]
+pub fn set_music_dir(state: State<Arc<PlaylistState>>, path: Option<String>) -> Result<(), String> {
+    let dir = path.map(PathBuf::from);
+    if let Some(dir) = &dir {
+        if !dir.is_dir() {
+            return Err(format!("Music directory {} does not exist", dir.display()));
+        }
+        println!("Boarding music directory set to {}", dir.display());
+    }
+    *state.dir.lock().unwrap() = dir;
+    Ok(())
+}
+
+/// Lists the playing playlist in play order, or the music folder's tracks when nothing plays.
+#[tauri::command]
+pub fn list_tracks(state: State<Arc<PlaylistState>>) -> Result<Vec<TrackInfo>, String> {
+    if let Some(playing) = state.playing.lock().unwrap().as_ref() {
+        return Ok(playing.order.iter().enumerate()
+            .map(|(index, name)| TrackInfo { name: name.clone(), playing: index == playing.index })
+            .collect());
+    }
+    let dir = state.dir.lock().unwrap().clone()
+        .ok_or_else(|| "No music directory set".to_string())?;
+    Ok(scan(&dir).into_iter()
+        .map(|name| TrackInfo { name, playing: false })
+        .collect())
+}
+
+/// Crossfades to the next track. Returns its name.
+#[tauri::command]
+pub fn next_track(app: AppHandle) -> Result<String, String> {
+    advance(&app)
+}
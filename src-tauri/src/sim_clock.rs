@@ -0,0 +1,194 @@
+use serde_json::json;
+use std::sync::atomic::{ AtomicU64, Ordering };
+use std::sync::{ Arc, Mutex };
+use std::thread;
+use std::time::{ Duration, Instant };
+use tauri::{ Emitter, Manager, Window };
+
+/// Sleeping threads wake this often to follow pauses and rate changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug)]
+struct ClockState {
+    /// Sim time reached at `since`
+    base: Duration,
+    since: Instant,
+    rate: f64,
+    paused: bool,
+    /// Out of the flight: main menu, loading or crashed
+    stopped: bool,
+}
+
+impl ClockState {
+    fn now(&self) -> Duration {
+        if self.paused || self.stopped {
+            self.base
+        } else {
+            self.base + self.since.elapsed().mul_f64(self.rate)
+        }
+    }
+}
+
+/// Time as the sim experiences it: stands still while paused or out of the flight and
+/// runs faster under time acceleration. Announcement timers are measured against it.
+pub struct SimClock {
+    state: Mutex<ClockState>,
+    /// Bumped to cancel sequences waiting on the clock
+    generation: AtomicU64,
+}
+
+impl SimClock {
+    pub fn new() -> Self {
+        SimClock {
+            state: Mutex::new(ClockState {
+                base: Duration::ZERO,
+                since: Instant::now(),
+                rate: 1.0,
+                paused: false,
+                stopped: false,
+            }),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Sim time since simpa started.
+    pub fn now(&self) -> Duration {
+        self.state.lock().unwrap().now()
+    }
+
+    /// Sim time passed since `earlier`, a value of `now()`.
+    pub fn since(&self, earlier: Duration) -> Duration {
+        self.now().saturating_sub(earlier)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ClockState)) {
+        let mut state = self.state.lock().unwrap();
+        state.base = state.now();
+        state.since = Instant::now();
+        change(&mut state);
+    }
+
+    pub fn set_rate(&self, rate: f64) {
+        if rate > 0.0 {
+            self.update(|state| state.rate = rate);
+        }
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.update(|state| state.paused = paused);
+    }
+
+    pub fn set_stopped(&self, stopped: bool) {
+        self.update(|state| state.stopped = stopped);
+    }
+
+    /// Sleeps for `duration` of sim time. Gives up early, returning false, once
+    /// `keep_waiting` returns false.
+    pub fn sleep_while(&self, duration: Duration, keep_waiting: impl Fn() -> bool) -> bool {
+        let until = self.now() + duration;
+        loop {
+            if !keep_waiting() {
+                return false;
+            }
+            if self.now() >= until {
+                return true;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Cancels every `run_after` still waiting, e.g. when the flight is reset.
+    pub fn cancel_pending(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Runs `action` after `delay` of sim time, from a background thread.
+pub fn run_after(window: &Window, delay: Duration, action: impl FnOnce(&Window) + Send + 'static) {
+    let clock = window.state::<Arc<SimClock>>().inner().clone();
+    // Read before the thread starts, so a reset in between still cancels it
+    let generation = clock.generation.load(Ordering::SeqCst);
+    let window = window.clone();
+    thread::spawn(move || {
+        if clock.sleep_while(delay, || clock.generation.load(Ordering::SeqCst) == generation) {
+            action(&window);
+        }
+    });
+}
+
+/// Tells the frontend how sim time is running.
+pub fn emit_state(window: &Window, clock: &SimClock) {
+    let state = *clock.state.lock().unwrap();
+    let _ = window.emit("sim-time-changed", json!({
+        "paused": state.paused,
+        "stopped": state.stopped,
+        "rate": state.rate
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pretends `secs` of real time passed since the clock last changed.
+    fn wait(clock: &SimClock, secs: u64) {
+        clock.state.lock().unwrap().since -= Duration::from_secs(secs);
+    }
+
+    fn seconds(clock: &SimClock) -> f64 {
+        clock.now().as_secs_f64()
+    }
+
+    #[test]
+    fn stands_still_while_paused_or_stopped() {
+        let clock = SimClock::new();
+        wait(&clock, 10);
+        assert!((seconds(&clock) - 10.0).abs() < 0.1);
+
+        clock.set_paused(true);
+        wait(&clock, 60);
+        assert!((seconds(&clock) - 10.0).abs() < 0.1);
+        clock.set_paused(false);
+        clock.set_stopped(true);
+        wait(&clock, 60);
+        assert!((seconds(&clock) - 10.0).abs() < 0.1);
+
+        clock.set_stopped(false);
+        wait(&clock, 5);
+        assert!((seconds(&clock) - 15.0).abs() < 0.1);
+        assert!((clock.since(Duration::from_secs(12)).as_secs_f64() - 3.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn runs_at_the_sim_rate() {
+        let clock = SimClock::new();
+        clock.set_rate(4.0);
+        wait(&clock, 10);
+        assert!((seconds(&clock) - 40.0).abs() < 0.5);
+        // Nonsense rates are ignored
+        clock.set_rate(0.0);
+        wait(&clock, 10);
+        assert!((seconds(&clock) - 80.0).abs() < 0.5);
+
+        // Timers follow: ten sim seconds go by in a tenth of a real one at 100x
+        clock.set_rate(100.0);
+        let started = Instant::now();
+        assert!(clock.sleep_while(Duration::from_secs(10), || true));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn cancelling_stops_pending_waits() {
+        let clock = Arc::new(SimClock::new());
+        clock.set_paused(true);
+        let generation = clock.generation.load(Ordering::SeqCst);
+        let sleeper = {
+            let clock = clock.clone();
+            // A paused clock would never get there on its own
+            thread::spawn(move || clock.sleep_while(Duration::from_secs(1), || clock.generation.load(Ordering::SeqCst) == generation))
+        };
+        clock.cancel_pending();
+        assert!(!sleeper.join().unwrap());
+        assert!(clock.sleep_while(Duration::ZERO, || true));
+    }
+}
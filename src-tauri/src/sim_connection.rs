@@ -0,0 +1,131 @@
+use simconnect::{ DispatchResult, SimConnector };
+
+/// A message from the sim, in SimConnect terms.
+pub enum SimMessage {
+    /// Values of a data definition, laid out like its `add_data_definition` calls.
+    /// Only valid until the next message is fetched.
+    Data { define_id: u32, data: *const u8 },
+    /// A subscribed system event or mapped client event
+    Event { id: u32, data: u32 },
+    /// A system event naming a file, like FlightLoaded's .flt or AircraftLoaded's .air
+    FileLoaded { id: u32, path: String },
+    Open,
+    Quit,
+    Exception(u32),
+    /// Nothing simpa handles
+    Other,
+}
+
+/// The calls the collection loop makes on the sim. Shaped after `SimConnector`, so
+/// variables, units and events are named as in SimConnect and other sims translate them.
+pub trait SimConnection {
+    fn add_data_definition(
+        &self,
+        define_id: u32,
+        name: &str,
+        unit: &str,
+        datatype: simconnect::SIMCONNECT_DATATYPE,
+        datum_id: u32,
+        epsilon: f32
+    ) -> bool;
+
+    #[allow(clippy::too_many_arguments)]
+    fn request_data_on_sim_object(
+        &self,
+        request_id: u32,
+        define_id: u32,
+        object_id: u32,
+        period: simconnect::SIMCONNECT_PERIOD,
+        flags: u32,
+        origin: u32,
+        interval: u32,
+        limit: u32
+    ) -> bool;
+
+    fn subscribe_to_system_event(&self, event_id: u32, name: &str) -> bool;
+
+    fn map_client_event_to_sim_event(&self, event_id: u32, name: &str) -> bool;
+
+    fn add_client_event_to_notification_group(&self, group_id: u32, event_id: u32, maskable: bool) -> bool;
+
+    fn set_notification_group_priority(&self, group_id: u32, priority: u32) -> bool;
+
+    fn set_system_event_state(&self, event_id: u32, state: simconnect::SIMCONNECT_STATE) -> bool;
+
+    fn next_message(&self) -> Result<SimMessage, String>;
+}
+
+impl SimConnection for SimConnector {
+    fn add_data_definition(
+        &self,
+        define_id: u32,
+        name: &str,
+        unit: &str,
+        datatype: simconnect::SIMCONNECT_DATATYPE,
+        datum_id: u32,
+        epsilon: f32
+    ) -> bool {
+        SimConnector::add_data_definition(self, define_id, name, unit, datatype, datum_id, epsilon)
+    }
+
+    fn request_data_on_sim_object(
+        &self,
+        request_id: u32,
+        define_id: u32,
+        object_id: u32,
+        period: simconnect::SIMCONNECT_PERIOD,
+        flags: u32,
+        origin: u32,
+        interval: u32,
+        limit: u32
+    ) -> bool {
+        SimConnector::request_data_on_sim_object(self, request_id, define_id, object_id, period, flags, origin, interval, limit)
+    }
+
+    fn subscribe_to_system_event(&self, event_id: u32, name: &str) -> bool {
+        SimConnector::subscribe_to_system_event(self, event_id, name)
+    }
+
+    fn map_client_event_to_sim_event(&self, event_id: u32, name: &str) -> bool {
+        SimConnector::map_client_event_to_sim_event(self, event_id, name)
+    }
+
+    fn add_client_event_to_notification_group(&self, group_id: u32, event_id: u32, maskable: bool) -> bool {
+        SimConnector::add_client_event_to_notification_group(self, group_id, event_id, maskable)
+    }
+
+    fn set_notification_group_priority(&self, group_id: u32, priority: u32) -> bool {
+        SimConnector::set_notification_group_priority(self, group_id, priority)
+    }
+
+    fn set_system_event_state(&self, event_id: u32, state: simconnect::SIMCONNECT_STATE) -> bool {
+        SimConnector::set_system_event_state(self, event_id, state)
+    }
+
+    fn next_message(&self) -> Result<SimMessage, String> {
+        match self.get_next_message() {
+            Ok(DispatchResult::SimObjectData(data)) => Ok(SimMessage::Data {
+                define_id: data.dwDefineID,
+                data: std::ptr::addr_of!(data.dwData) as *const u8,
+            }),
+            Ok(DispatchResult::Event(event)) => Ok(SimMessage::Event {
+                id: event.uEventID,
+                data: event.dwData,
+            }),
+            Ok(DispatchResult::EventFilename(event)) => {
+                // Packed, so copied out rather than borrowed
+                let name = event.szFileName;
+                let bytes: Vec<u8> = name.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+                Ok(SimMessage::FileLoaded {
+                    id: event._base.uEventID,
+                    path: String::from_utf8_lossy(&bytes).into_owned(),
+                })
+            },
+            Ok(DispatchResult::Open(_)) => Ok(SimMessage::Open),
+            Ok(DispatchResult::Quit(_)) => Ok(SimMessage::Quit),
+            Ok(DispatchResult::Exception(exception)) => Ok(SimMessage::Exception(exception.dwException)),
+            Ok(_) => Ok(SimMessage::Other),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
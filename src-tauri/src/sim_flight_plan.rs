@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use crate::ofp::{ xml_section, xml_unescape };
+
+/// GPS readings for the flight plan loaded in the sim.
+#[derive(Clone, Debug, Default)]
+pub struct GpsReading {
+    pub waypoint_index: u32,
+    pub waypoint_count: u32,
+    pub previous_waypoint: Option<String>,
+    pub next_waypoint: Option<String>,
+    pub next_waypoint_nm: f64,
+    /// Time to the end of the plan at the current ground speed
+    pub ete_secs: f64,
+    pub ground_speed_kts: f64,
+    /// Set once an approach is loaded
+    pub approach_airport: Option<String>,
+}
+
+/// The sim's active flight plan, for flights without an OFP.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimFlightPlan {
+    pub origin: Option<String>,
+    pub destination: Option<String>,
+    /// Origin and destination were read from the activated `.pln`, not guessed from the GPS
+    pub from_file: bool,
+    pub next_waypoint: Option<String>,
+    pub next_waypoint_nm: f64,
+    pub waypoint_index: u32,
+    pub waypoint_count: u32,
+    pub remaining_secs: Option<f64>,
+    pub remaining_nm: Option<f64>,
+}
+
+impl SimFlightPlan {
+    /// Reads the departure and destination airports of an MSFS `.pln` flight plan.
+    pub fn from_pln(contents: &str) -> Result<Self, String> {
+        let plan = xml_section(contents, "FlightPlan.FlightPlan")
+            .ok_or("Not an MSFS flight plan")?;
+        let airport = |tag: &str| xml_section(plan, tag)
+            .map(|value| xml_unescape(value.trim()).to_ascii_uppercase())
+            .filter(|value| !value.is_empty());
+        Ok(SimFlightPlan {
+            origin: airport("DepartureID"),
+            destination: airport("DestinationID"),
+            from_file: true,
+            ..Default::default()
+        })
+    }
+
+    /// Updates the plan from the GPS. Without a `.pln` the GPS variables are all we have,
+    /// and they don't name the airports: the origin is then the previous waypoint on the
+    /// first leg, the destination the approach airport or the last waypoint once it is next.
+    pub fn update(&mut self, gps: &GpsReading) {
+        self.next_waypoint = gps.next_waypoint.clone();
+        self.next_waypoint_nm = gps.next_waypoint_nm;
+        self.waypoint_index = gps.waypoint_index;
+        self.waypoint_count = gps.waypoint_count;
+
+        if !self.from_file {
+            if gps.waypoint_index <= 1 && gps.previous_waypoint.is_some() {
+                self.origin = gps.previous_waypoint.clone();
+            }
+            if gps.approach_airport.is_some() {
+                self.destination = gps.approach_airport.clone();
+            } else if gps.waypoint_count > 0 && gps.waypoint_index + 1 == gps.waypoint_count && gps.next_waypoint.is_some() {
+                self.destination = gps.next_waypoint.clone();
+            }
+        }
+
+        let remaining = (gps.ete_secs > 0.0).then_some(gps.ete_secs);
+        self.remaining_secs = remaining;
+        self.remaining_nm = remaining
+            .filter(|_| gps.ground_speed_kts > 0.0)
+            .map(|secs| secs / 3600.0 * gps.ground_speed_kts);
+    }
+}
+
+/// Reads the flight plan the sim activated, as named by its `FlightPlanActivated` event.
+pub fn load_pln(path: &Path) -> Result<SimFlightPlan, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    SimFlightPlan::from_pln(&contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLN: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<SimBase.Document Type="AceXML" version="1,0">
+    <FlightPlan.FlightPlan>
+        <Title>EGLL to LEMD</Title>
+        <DepartureID>EGLL</DepartureID>
+        <DestinationID> lemd </DestinationID>
+        <ATCWaypoint id="EGLL"><ATCWaypointType>Airport</ATCWaypointType></ATCWaypoint>
+    </FlightPlan.FlightPlan>
+</SimBase.Document>"#;
+
+    fn gps(index: u32, count: u32, previous: &str, next: &str) -> GpsReading {
+        GpsReading {
+            waypoint_index: index,
+            waypoint_count: count,
+            previous_waypoint: Some(previous.to_string()),
+            next_waypoint: Some(next.to_string()),
+            ete_secs: 1800.0,
+            ground_speed_kts: 420.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_the_airports_of_a_pln() {
+        let plan = SimFlightPlan::from_pln(PLN).unwrap();
+        assert_eq!(plan.origin.as_deref(), Some("EGLL"));
+        assert_eq!(plan.destination.as_deref(), Some("LEMD"));
+        assert!(plan.from_file);
+
+        let plan = SimFlightPlan::from_pln("<FlightPlan.FlightPlan><DepartureID></DepartureID></FlightPlan.FlightPlan>").unwrap();
+        assert_eq!((plan.origin, plan.destination), (None, None));
+        assert!(SimFlightPlan::from_pln("<OFP></OFP>").is_err());
+    }
+
+    #[test]
+    fn gps_guesses_the_airports_without_a_pln() {
+        let mut plan = SimFlightPlan::default();
+        plan.update(&gps(1, 5, "EGLL", "MID"));
+        assert_eq!(plan.origin.as_deref(), Some("EGLL"));
+        assert_eq!(plan.destination, None);
+        assert_eq!(plan.remaining_nm, Some(210.0));
+
+        plan.update(&gps(4, 5, "BRITO", "LEMD"));
+        assert_eq!(plan.origin.as_deref(), Some("EGLL"));
+        assert_eq!(plan.destination.as_deref(), Some("LEMD"));
+        plan.update(&GpsReading { approach_airport: Some("LEVD".to_string()), ..gps(4, 5, "BRITO", "LEMD") });
+        assert_eq!(plan.destination.as_deref(), Some("LEVD"));
+    }
+
+    #[test]
+    fn pln_airports_are_kept() {
+        let mut plan = SimFlightPlan::from_pln(PLN).unwrap();
+        plan.update(&gps(1, 5, "DET", "MID"));
+        plan.update(&GpsReading { approach_airport: Some("LEVD".to_string()), ..gps(4, 5, "BRITO", "NEXT") });
+        assert_eq!(plan.origin.as_deref(), Some("EGLL"));
+        assert_eq!(plan.destination.as_deref(), Some("LEMD"));
+        assert_eq!(plan.next_waypoint.as_deref(), Some("NEXT"));
+    }
+}
@@ -1,24 +1,113 @@
 use serde_json::json;
+use rodio::buffer::SamplesBuffer;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{ Arc, Mutex };
 use std::thread;
 use std::time::Duration;
-use simconnect::{ DispatchResult, SimConnector };
-use tauri::{ Window, State, Emitter };
+use simconnect::SimConnector;
+use crate::sim_connection::{ SimConnection, SimMessage };
+use crate::xplane::{ XPlaneConnection, XPlaneSettings };
+use tauri::{ Window, State, Emitter, Manager };
 use rand;
+use crate::flight_phase::{ FlightPhase, FlightPhaseTracker };
+use crate::logbook::{ self, ComfortTracker, GeoPosition, LogbookState };
+use crate::track::{ self, TrackPoint, TrackState };
+use crate::flight_context::FlightContextState;
+use crate::cabin_service::{ self, CabinService, ServiceStep };
+use crate::cabin_chime::{ self, ChimeKind, ChimeState, SignDebouncer };
+use crate::interphone::{ self, CallDefinition, CallWatcher, InterphoneState };
+use crate::aircraft_profile::AircraftProfile;
+use crate::captain_pa;
+use crate::playlist;
+use crate::languages;
+use crate::airports::{ self, AirportState };
+use crate::altitude_triggers::{ AltitudeSample, AltitudeTriggerSettings, AltitudeTriggers, FRONTEND_EVENTS };
+use crate::descent_planner::{ self, ArrivalEstimate, DescentPlanner, DescentSettings, RouteProgress };
+use crate::announcement_template::{ self, RenderOptions };
+use crate::sim_flight_plan::{ self, GpsReading, SimFlightPlan };
+use crate::weather::{ Precipitation, WeatherSample };
+use crate::sim_clock::{ self, SimClock };
+use crate::ambience;
+use crate::audio_engine::{ AudioCommand, AudioEngineState };
+use crate::mixer::Bus;
+use crate::soundpack::{ SoundpackState, CHANNELS, SAMPLE_RATE };
+use crate::turbulence::{ self, TurbulenceDetector, TurbulenceEvent, TurbulenceLevel };
+use crate::touchdown::{ FlightDynamicsData, TouchdownMonitor, TouchdownRating, TouchdownReport, TouchdownSettings };
 
 /// Holds a mutex-guarded `running: bool` to signal if SimConnect is active.
 pub struct SimConnectState {
     pub running: Mutex<bool>,
+    pub touchdown_settings: Mutex<TouchdownSettings>,
+    pub altitude_triggers: Mutex<AltitudeTriggerSettings>,
+    pub descent_settings: Mutex<DescentSettings>,
+    pub xplane: Mutex<XPlaneSettings>,
 }
 
 impl SimConnectState {
     pub fn new() -> Self {
         SimConnectState {
             running: Mutex::new(false),
+            touchdown_settings: Mutex::new(TouchdownSettings::default()),
+            altitude_triggers: Mutex::new(AltitudeTriggerSettings::default()),
+            descent_settings: Mutex::new(DescentSettings::default()),
+            xplane: Mutex::new(XPlaneSettings::default()),
         }
     }
 }
 
+/// Altitudes sampled once per second (DefineID 0).
+/// Field order must match the `add_data_definition` calls below.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default)]
+struct AltitudeData {
+    msl: f64,
+    agl: f64,
+    pressure_altitude: f64,
+}
+
+/// Sim flight plan progress and time (DefineID 21).
+/// Field order must match the `add_data_definition` calls below.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug)]
+struct RouteData {
+    gps_active: f64,
+    gps_ete: f64,
+    zulu_time: f64,
+    waypoint_index: f64,
+    waypoint_count: f64,
+    next_waypoint_nm: f64,
+    ground_speed: f64,
+    previous_waypoint: [u8; 64],
+    next_waypoint: [u8; 64],
+    approach_airport: [u8; 64],
+}
+
+/// Ambient weather around the aircraft (DefineID 22).
+/// Field order must match the `add_data_definition` calls below.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default)]
+struct AmbientData {
+    temperature: f64,
+    wind_velocity: f64,
+    wind_direction: f64,
+    precip_state: f64,
+    visibility: f64,
+}
+
+/// Aircraft position and speeds sampled once per second (DefineID 17).
+/// Field order must match the `add_data_definition` calls below.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PositionData {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+    pub ground_velocity: f64,
+    pub airspeed_indicated: f64,
+    pub vertical_speed: f64,
+}
+
 // Add a struct to hold our flight data state
 #[derive(Clone)]
 struct FlightDataState {
@@ -27,6 +116,8 @@ struct FlightDataState {
     last_request_was_attach: bool,  // Track if last request was to attach
     // Add cached flight data
     last_alt: f64,
+    altitude: AltitudeSample,
+    transition_altitude_ft: f64,
     // Add jetway state tracking
     jetway_attached: bool,
     jetway_moving: bool,
@@ -38,6 +129,7 @@ struct FlightDataState {
     ten_k_announced: bool,  // Add this field
     arrive_soon_announced: bool,  // Add this field
     landing_soon_announced: bool,  // Add this field
+    descended_through_10k: bool,
     camera_position: String,  // Add this field
     x_position: f64,
     y_position: f64,
@@ -54,15 +146,30 @@ struct FlightDataState {
     landing_lights: bool,  // Add landing lights state
     wing_light: bool,  // Add wing light state
     aircraft_type: String,  // Add aircraft type field
+    last_touchdown: Option<TouchdownReport>,
+    flight_phase: FlightPhase,
+    last_position: Option<GeoPosition>,
+    takeoff_time: Option<Duration>,  // Sim time
+    vertical_speed: f64,
+    ground_speed: f64,
+    arrival_estimate: Option<ArrivalEstimate>,
+    turbulence: Option<TurbulenceLevel>,
+    flight_context: Arc<FlightContextState>,
+    jetway_attached_at: Option<Duration>,  // Sim time
+    // Bumped on every jetway attach/detach so a stale boarding loop stops
+    boarding_generation: Arc<Mutex<u64>>,
+    gsx_passengers: Option<(f64, f64)>,  // Boarded so far, total
 }
 
 impl FlightDataState {
-    fn new() -> Self {
+    fn new(flight_context: Arc<FlightContextState>) -> Self {
         FlightDataState {
             jetway_state: false,
             last_toggle_time: std::time::Instant::now(),
             last_request_was_attach: false,
             last_alt: 0.0,
+            altitude: AltitudeSample::default(),
+            transition_altitude_ft: AltitudeTriggerSettings::default().transition_altitude_ft,
             jetway_attached: false,
             jetway_moving: false,
             boarding_music_playing: false,
@@ -72,6 +179,7 @@ impl FlightDataState {
             ten_k_announced: false,
             arrive_soon_announced: false,
             landing_soon_announced: false,
+            descended_through_10k: false,
             camera_position: String::from("exterior"),
             x_position: 0.0,
             y_position: 0.0,
@@ -88,6 +196,18 @@ impl FlightDataState {
             landing_lights: false,
             wing_light: false,
             aircraft_type: String::from("Unknown"),  // Initialize with Unknown
+            last_touchdown: None,
+            flight_phase: FlightPhase::Preflight,
+            last_position: None,
+            takeoff_time: None,
+            vertical_speed: 0.0,
+            ground_speed: 0.0,
+            arrival_estimate: None,
+            turbulence: None,
+            flight_context,
+            jetway_attached_at: None,
+            boarding_generation: Arc::new(Mutex::new(0)),
+            gsx_passengers: None,
         }
     }
 
@@ -127,47 +247,48 @@ impl FlightDataState {
         let window_clone = window.clone();
         thread::spawn(move || {
             thread::sleep(delay);
-            let _ = window_clone.emit("audio-event", json!({
+            emit_audio_event(&window_clone, json!({
                 "type": "welcome_aboard"
             }));
         });
     }
 
-    fn update_flight_data(&mut self, alt: f64) {
+    /// Records an altitude sample and returns the altitude triggers it crossed.
+    fn update_flight_data(
+        &mut self,
+        sample: AltitudeSample,
+        triggers: &mut AltitudeTriggers,
+        settings: &AltitudeTriggerSettings
+    ) -> Vec<String> {
         // Log altitude changes for debugging
-        println!("Altitude update: {:.2} feet (Previous: {:.2} feet)", alt, self.last_alt);
-        
-        // Check if we're ascending through 10k feet
-        if self.last_alt < 10000.0 && alt >= 10000.0 && !self.ten_k_announced {
-            println!("Passing through 10k feet during ascent - triggering announcement");
-            self.ten_k_announced = true;
-        }
-        
-        // Reset the announcement flags when descending below their thresholds
-        if alt < 10000.0 {
-            println!("Descending below 10k feet, resetting announcement flags");
-            self.ten_k_announced = false;
-            self.landing_soon_announced = false;
-        }
-        
-        // Check for arrival announcement at 18,000 feet
-        if alt < 18000.0 && !self.arrive_soon_announced {
-            println!("Descending below 18,000 feet - triggering arrival soon announcement");
-            self.arrive_soon_announced = true;
-        }
-        
-        // Check for landing announcement at 10,000 feet
-        if alt < 10000.0 && !self.landing_soon_announced {
-            println!("Descending below 10,000 feet - triggering landing soon announcement");
-            self.landing_soon_announced = true;
-        }
-        
-        self.last_alt = alt;
+        println!("Altitude update: {:.2} feet (Previous: {:.2} feet), {:.0} feet AGL",
+            sample.msl_ft, self.last_alt, sample.agl_ft);
+
+        let fired: Vec<String> = triggers.update(settings, &sample).into_iter()
+            .map(|trigger| {
+                println!("Altitude trigger {}: {:.0} feet {:?}, {:?}", trigger.event,
+                    trigger.threshold_ft, trigger.reference, trigger.direction);
+                trigger.event.clone()
+            })
+            .collect();
+
+        self.ten_k_announced = triggers.has_fired(settings, "10k_feet");
+        self.arrive_soon_announced = triggers.has_fired(settings, "arrive_soon");
+        self.landing_soon_announced = triggers.has_fired(settings, "landing_soon");
+        self.descended_through_10k = triggers.descended_through_10k();
+        self.last_alt = sample.msl_ft;
+        self.altitude = sample;
+        self.transition_altitude_ft = settings.transition_altitude_ft;
+        fired
     }
 
     fn get_payload(&self) -> serde_json::Value {
+        let context = self.flight_context.snapshot();
+        let mut altitudes = self.altitude.report(self.transition_altitude_ft);
+        altitudes["descendedThrough10k"] = json!(self.descended_through_10k);
         json!({
             "alt": format_number(self.last_alt, 0),
+            "altitudes": altitudes,
             "jetwayMoving": self.jetway_moving,
             "jetwayState": self.jetway_attached,
             "lastRequestWasAttach": self.last_request_was_attach,
@@ -187,10 +308,272 @@ impl FlightDataState {
             "landingLights": self.landing_lights,
             "wingLight": self.wing_light,
             "aircraftType": self.aircraft_type,  // Add aircraft type to the payload
+            "touchdown": self.last_touchdown,
+            "flightPhase": self.flight_phase.as_str(),
+            "turbulence": self.turbulence.map(|level| level.as_str()),
+            "arrival": self.arrival_estimate,
+            "ofp": context.ofp,
+            "nearestAirport": context.nearest_airport,
         })
     }
 }
 
+const BOARDING_MUSIC_CLIP: &str = "music/boarding";
+const WELCOME_ABOARD_CLIP: &str = "announcements/welcome_aboard";
+const FIRST_WELCOME_ABOARD: Duration = Duration::from_secs(30);
+// How long the frontend's welcome aboard runs when there's no clip to measure
+const FALLBACK_WELCOME_ABOARD_LENGTH: Duration = Duration::from_secs(5);
+
+/// Starts the boarding music and repeats the welcome aboard PA every 30-120s until the
+/// jetway detaches. The music comes from the user's playlist, else the soundpack. Native
+/// clips are ducked by the mixer for their real length; without them the frontend plays
+/// both from the audio events, and the music is ducked around the frontend's PA.
+fn start_boarding_audio(window: &Window, boarding_generation: &Arc<Mutex<u64>>, aircraft: &str) {
+    let generation = {
+        let mut current = boarding_generation.lock().unwrap();
+        *current += 1;
+        *current
+    };
+    let engine = window.state::<Arc<AudioEngineState>>().inner().clone();
+    let soundpack = window.state::<Arc<SoundpackState>>().get().ok();
+
+    let airline = window.state::<Arc<FlightContextState>>().snapshot().ofp.and_then(|ofp| ofp.airline_icao);
+    let playlist_started = playlist::start(window, airline.as_deref(), aircraft).unwrap_or_else(|e| {
+        println!("Failed to start boarding playlist: {}", e);
+        false
+    });
+    let native_music = playlist_started || match soundpack.as_ref().map(|pack| pack.open_loop(BOARDING_MUSIC_CLIP)) {
+        Some(Ok(music)) => {
+            if let Err(e) = engine.play_music(music) {
+                println!("Failed to play boarding music: {}", e);
+            }
+            true
+        },
+        _ => {
+            emit_audio_event(window, json!({
+                "type": "boarding_music",
+                "volume": 100
+            }));
+            false
+        },
+    };
+
+    let window = window.clone();
+    let boarding_generation = boarding_generation.clone();
+    thread::spawn(move || {
+        let clock = window.state::<Arc<SimClock>>().inner().clone();
+        let mut delay = FIRST_WELCOME_ABOARD;
+        loop {
+            // Sim time, so a paused sim doesn't keep welcoming passengers
+            if !clock.sleep_while(delay, || *boarding_generation.lock().unwrap() == generation) {
+                break;
+            }
+
+            let context = window.state::<Arc<FlightContextState>>();
+            let clip = soundpack.as_ref().and_then(|pack| {
+                let packs = languages::soundpacks(pack, &context);
+                languages::render_sequence(&packs, &context, |pack| pack.decode(WELCOME_ABOARD_CLIP)).ok()
+            });
+            match clip {
+                Some(samples) => {
+                    let length = Duration::from_secs_f64(
+                        samples.len() as f64 / (CHANNELS as u32 * SAMPLE_RATE) as f64
+                    );
+                    match engine.play_pa(SamplesBuffer::new(CHANNELS, SAMPLE_RATE, samples), 1.0) {
+                        Ok(()) => {
+                            logbook::record_announcement(&window, "welcome_aboard");
+                            track::record_announcement(&window, "welcome_aboard");
+                            // Don't count the announcement itself towards the next delay
+                            thread::sleep(length);
+                        },
+                        Err(e) => println!("Failed to play welcome aboard: {}", e),
+                    }
+                },
+                None => {
+                    // The mixer can't see the frontend's PA, so duck the music around it
+                    duck_boarding_music(&window, &engine, native_music, true);
+                    emit_audio_event(&window, json!({
+                        "type": "welcome_aboard"
+                    }));
+                    thread::sleep(FALLBACK_WELCOME_ABOARD_LENGTH);
+                    duck_boarding_music(&window, &engine, native_music, false);
+                },
+            }
+
+            delay = Duration::from_secs(rand::random::<u64>() % 90 + 30); // 30-120 seconds
+        }
+    });
+}
+
+/// Lowers (or restores) the boarding music for a PA the mixer isn't playing, through the
+/// mixer for native music or with a short fade of the frontend's music.
+fn duck_boarding_music(window: &Window, engine: &AudioEngineState, native_music: bool, duck: bool) {
+    if native_music {
+        if let Err(e) = engine.send(AudioCommand::ExternalDuck(duck)) {
+            println!("Failed to duck boarding music: {}", e);
+        }
+        return;
+    }
+    for step in 0..=20u64 {
+        let volume = if duck { 100 - step * 5 } else { step * 5 };
+        emit_audio_event(window, json!({
+            "type": "boarding_music",
+            "volume": volume
+        }));
+        thread::sleep(Duration::from_millis(40));
+    }
+}
+
+/// How a clip is played natively, and where in the soundpack it lives.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Playback {
+    /// `announcements/<clip>` over the cabin PA, in the flight's languages
+    Pa,
+    /// `interphone/<clip>`, the crew on the flight deck handset
+    Interphone,
+    /// `cabin/<clip>`, a sound in the cabin such as the trolley or applause
+    Cabin,
+}
+
+impl Playback {
+    /// The soundpack folder the clip lives in.
+    fn folder(self) -> &'static str {
+        match self {
+            Playback::Pa => "announcements",
+            Playback::Interphone => "interphone",
+            Playback::Cabin => "cabin",
+        }
+    }
+}
+
+/// Plays `announcements/<event>` from the soundpack in the flight's languages.
+fn play_soundpack_announcement(window: &Window, event: &str) -> Result<(), String> {
+    let soundpack = window.state::<Arc<SoundpackState>>().get()?;
+    let context = window.state::<Arc<FlightContextState>>();
+    let key = format!("announcements/{}", event);
+    let packs = languages::soundpacks(&soundpack, &context);
+    let samples = languages::render_sequence(&packs, &context, |pack| pack.decode(&key))?;
+    window.state::<Arc<AudioEngineState>>()
+        .play_pa(SamplesBuffer::new(CHANNELS, SAMPLE_RATE, samples), 1.0)
+}
+
+fn play_natively(window: &Window, playback: Playback, clip: &str) -> Result<(), String> {
+    let bus = match playback {
+        Playback::Pa => return play_soundpack_announcement(window, clip),
+        Playback::Interphone => Bus::Pa,
+        Playback::Cabin => Bus::Ambience,
+    };
+    let soundpack = window.state::<Arc<SoundpackState>>().get()?;
+    let samples = soundpack.decode(&format!("{}/{}", playback.folder(), clip))?;
+    window.state::<Arc<AudioEngineState>>()
+        .play(SamplesBuffer::new(CHANNELS, SAMPLE_RATE, samples), bus, 1.0)
+}
+
+/// Renders the arrival PA from the soundpack's phrases with the flight's template variables.
+fn play_arrival_template(window: &Window, variables: &HashMap<String, String>) -> Result<(), String> {
+    let soundpack = window.state::<Arc<SoundpackState>>().get()?;
+    let context = window.state::<Arc<FlightContextState>>();
+    let template = descent_planner::arrival_template(variables);
+    let packs = languages::soundpacks(&soundpack, &context);
+    let buffer = announcement_template::render(&packs, &context, &template, variables, RenderOptions::default())?;
+    window.state::<Arc<AudioEngineState>>().play_pa(buffer, 1.0)
+}
+
+/// Announces the arrival with whatever we know of the time to landing and the local time
+/// and weather at the destination. The frontend plays its fixed recording if this can't.
+fn announce_arrival(window: &Window, minutes_to_landing: Option<f64>, destination_local_time: Option<String>) {
+    let context = window.state::<Arc<FlightContextState>>().snapshot();
+    let mut variables = context.template_variables();
+    if let Some(minutes) = minutes_to_landing {
+        variables.insert("minutes_to_landing".to_string(), format!("{:.0}", minutes));
+    }
+    if let Some(time) = &destination_local_time {
+        variables.insert("destination_local_time".to_string(), time.clone());
+    }
+
+    let played = play_arrival_template(window, &variables);
+    if let Err(e) = &played {
+        println!("Leaving the arrival PA to the frontend: {}", e);
+    }
+    emit_audio_event(window, json!({
+        "type": "arrive_soon",
+        "native": played.is_ok(),
+        "minutesToLanding": minutes_to_landing.map(f64::round),
+        "destinationLocalTime": destination_local_time,
+        "destinationWeather": context.destination_weather().map(|weather| weather.report()),
+        "destinationMetar": context.ofp.as_ref().and_then(|ofp| ofp.destination.metar.clone()),
+    }));
+}
+
+/// Plays a clip natively, leaving it to the frontend when the soundpack or the native
+/// output can't. The frontend gets the `audio-event` either way, with `native` set once
+/// it has been played.
+pub(crate) fn play_clip(window: &Window, playback: Playback, clip: &str, mut payload: serde_json::Value) {
+    let played = play_natively(window, playback, clip);
+    if let Err(e) = &played {
+        println!("Leaving {} to the frontend: {}", clip, e);
+    }
+    payload["clip"] = json!(clip);
+    payload["folder"] = json!(playback.folder());
+    payload["native"] = json!(played.is_ok());
+    emit_audio_event(window, payload);
+}
+
+/// Stops the boarding music and any pending welcome aboard.
+fn stop_boarding_audio(window: &Window, boarding_generation: &Arc<Mutex<u64>>) {
+    *boarding_generation.lock().unwrap() += 1;
+    playlist::stop(window);
+    if let Err(e) = window.state::<Arc<AudioEngineState>>().stop_music() {
+        println!("Failed to stop boarding music: {}", e);
+    }
+    emit_audio_event(window, json!({
+        "type": "boarding_music",
+        "volume": 0
+    }));
+}
+
+/// Emits an `audio-event` and records it in the logbook as a played announcement.
+/// Announcements carry the route details from the flight context when one is loaded.
+pub(crate) fn emit_audio_event(window: &Window, mut payload: serde_json::Value) {
+    if let Some(kind) = payload.get("type").and_then(|t| t.as_str()).map(str::to_string) {
+        // Music volume changes are not announcements
+        if kind != "boarding_music" {
+            logbook::record_announcement(window, &kind);
+            track::record_announcement(window, &kind);
+
+            let context = window.state::<Arc<FlightContextState>>();
+            let flight = context.snapshot().announcement_fields();
+            if !flight.is_null() {
+                payload["flight"] = flight;
+            }
+            payload["languages"] = json!(context.announcement_languages());
+        }
+    }
+    let _ = window.emit("audio-event", payload);
+}
+
+/// Plays cabin service steps and notifies the frontend.
+fn emit_cabin_service_steps(window: &Window, steps: Vec<ServiceStep>) {
+    for step in steps {
+        println!("Cabin service: {}", step.as_str());
+        let playback = match step {
+            ServiceStep::TrolleyStart | ServiceStep::TrolleyStop => Playback::Cabin,
+            _ => Playback::Pa,
+        };
+        play_clip(window, playback, step.as_str(), step.audio_payload());
+        let _ = window.emit("cabin-service", json!({
+            "step": step.as_str()
+        }));
+    }
+}
+
+/// Reads a NUL-terminated SimConnect string, None when blank.
+fn sim_string(bytes: &[u8]) -> Option<String> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let text = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+    if text.is_empty() { None } else { Some(text) }
+}
+
 /// Helper to round floating values to `decimals` places.
 fn format_number(value: f64, decimals: usize) -> f64 {
     let multiplier = (10f64).powi(decimals as i32);
@@ -218,11 +601,71 @@ fn try_connect_with_retry(max_attempts: u32, delay_ms: u64) -> Option<SimConnect
     None
 }
 
+/// Asks the sim for the aircraft TITLE and ATC MODEL; the replies update the aircraft type.
+fn request_aircraft_identity(conn: &dyn SimConnection) {
+    for define_id in [14, 15] { // TITLE, ATC MODEL
+        conn.request_data_on_sim_object(
+            define_id,
+            define_id,
+            0,
+            simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_ONCE,
+            0,
+            0,
+            0,
+            0
+        );
+    }
+}
+
+/// Starts or stops the periodic request for a set of cabin call L-vars.
+fn request_call_lvars(conn: &dyn SimConnection, definition: &CallDefinition, watch: bool) {
+    if definition.lvars.is_empty() {
+        return;
+    }
+    let period = if watch {
+        simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SIM_FRAME
+    } else {
+        simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_NEVER
+    };
+    conn.request_data_on_sim_object(definition.define_id, definition.define_id, 0, period, 0, 0, 0, 0);
+}
+
+/// Adapts simpa to a newly loaded aircraft: the cabin chimes, which cabin call L-vars
+/// are requested, and the frontend.
+fn change_aircraft(
+    window: &Window,
+    conn: &dyn SimConnection,
+    profile: &AircraftProfile,
+    call_watcher: &mut Option<CallWatcher>,
+    custom_call_lvar: Option<&str>
+) {
+    println!("Aircraft changed: {} ({})", profile.title,
+        profile.call_profile.map_or("no call profile", |call_profile| call_profile.name));
+    *window.state::<Arc<ChimeState>>().family.lock().unwrap() = profile.family;
+
+    let definition = profile.call_definition(custom_call_lvar);
+    if call_watcher.as_ref().map(|watcher| &watcher.definition) != Some(&definition) {
+        if let Some(watcher) = call_watcher.as_ref() {
+            request_call_lvars(conn, &watcher.definition, false);
+        }
+        request_call_lvars(conn, &definition, true);
+        *call_watcher = Some(CallWatcher::new(definition));
+    }
+
+    let _ = window.emit("aircraft-changed", profile.report());
+    let _ = window.emit("aircraft-type-changed", json!({
+        "type": profile.title
+    }));
+}
+
 /// Starts the SimConnect data collection in a background thread.
 #[tauri::command]
 pub fn start_simconnect_data_collection(
     window: Window,
-    state: State<Arc<SimConnectState>>
+    state: State<Arc<SimConnectState>>,
+    logbook: State<Arc<LogbookState>>,
+    track: State<Arc<TrackState>>,
+    flight_context: State<Arc<FlightContextState>>
 ) {
     // Check if already running
     {
@@ -238,38 +681,64 @@ pub fn start_simconnect_data_collection(
 
     // Clone the Arc<SimConnectState> so the thread can own it
     let arc_state = state.inner().clone();
+    let logbook = logbook.inner().clone();
+    let track = track.inner().clone();
+    track.recorder.lock().unwrap().clear();
+    let flight_context = flight_context.inner().clone();
 
     thread::spawn(move || {
-        // Try to connect with 5 attempts, 2 seconds between attempts
-        let conn = match try_connect_with_retry(5, 2000) {
-            Some(conn) => conn,
-            None => {
-                println!("Failed to connect to SimConnect after multiple attempts.");
-                *arc_state.running.lock().unwrap() = false;
-                let _ = window.emit("simconnect-error", json!({
-                    "message": "Failed to connect to MSFS. Please ensure the simulator is running and try again."
-                }));
-                return;
+        let xplane = arc_state.xplane.lock().unwrap().clone();
+        let conn: Box<dyn SimConnection> = if xplane.enabled {
+            match XPlaneConnection::connect(&xplane.address) {
+                Ok(conn) => Box::new(conn),
+                Err(e) => {
+                    println!("Failed to reach X-Plane: {}", e);
+                    *arc_state.running.lock().unwrap() = false;
+                    let _ = window.emit("simconnect-error", json!({
+                        "message": format!("Failed to reach X-Plane at {}: {}", xplane.address, e)
+                    }));
+                    return;
+                }
+            }
+        } else {
+            // Try to connect with 5 attempts, 2 seconds between attempts
+            match try_connect_with_retry(5, 2000) {
+                Some(conn) => Box::new(conn),
+                None => {
+                    println!("Failed to connect to SimConnect after multiple attempts.");
+                    *arc_state.running.lock().unwrap() = false;
+                    let _ = window.emit("simconnect-error", json!({
+                        "message": "Failed to connect to MSFS. Please ensure the simulator is running and try again."
+                    }));
+                    return;
+                }
             }
         };
 
-        println!("Connected to SimConnect.");
+        println!("Connected to {}.", if xplane.enabled { "X-Plane" } else { "SimConnect" });
         let _ = window.emit("simconnect-open", json!({}));
 
         // Create flight data state tracker
-        let mut flight_state = FlightDataState::new();
+        let mut flight_state = FlightDataState::new(flight_context);
+
+        // Cabin call buttons are read from add-on L-vars
+        let interphone_state = window.state::<Arc<InterphoneState>>().inner().clone();
+        let custom_call_lvar = interphone_state.custom_lvar.lock().unwrap().clone();
+        let call_definitions = interphone::call_definitions(custom_call_lvar.as_deref());
 
         // Add data definitions with error handling
         let setup_result = || -> Result<(), String> {
-            // Define a structure for flight data
-            conn.add_data_definition(
-                0,
-                "PLANE ALTITUDE",
-                "Feet",
-                simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
-                0,
-                0.0
-            );
+            // Define a structure for flight data. Order must match `AltitudeData`.
+            for name in ["PLANE ALTITUDE", "PLANE ALT ABOVE GROUND", "PRESSURE ALTITUDE"] {
+                conn.add_data_definition(
+                    0,
+                    name,
+                    "Feet",
+                    simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
+                    0,
+                    0.0
+                );
+            }
 
             // Request flight data with a more reasonable frequency
             conn.request_data_on_sim_object(
@@ -568,6 +1037,217 @@ pub fn start_simconnect_data_collection(
                 0
             );
 
+            // Flight dynamics used for touchdown analysis, sampled every frame.
+            // Order must match `FlightDynamicsData`.
+            for (name, unit) in [
+                ("SIM ON GROUND", "Bool"),
+                ("VERTICAL SPEED", "Feet per minute"),
+                ("G FORCE", "GForce"),
+                ("PLANE TOUCHDOWN NORMAL VELOCITY", "Feet per second"),
+                ("PLANE BANK DEGREES", "Degrees"),
+                ("PLANE PITCH DEGREES", "Degrees"),
+                ("GROUND VELOCITY", "Knots"),
+                ("ATC RUNWAY SELECTED", "Bool"),
+                ("ATC RUNWAY RELATIVE POSITION Z", "Feet"),
+                ("PLANE ALT ABOVE GROUND", "Feet"),
+                ("ACCELERATION BODY Y", "Feet per second squared"),
+            ] {
+                conn.add_data_definition(
+                    16,
+                    name,
+                    unit,
+                    simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
+                    0,
+                    0.0
+                );
+            }
+
+            conn.request_data_on_sim_object(
+                16,
+                16,
+                0,
+                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SIM_FRAME,
+                0,
+                0,
+                0,
+                0
+            );
+
+            // Aircraft position for the logbook and track. Order must match `PositionData`.
+            for (name, unit) in [
+                ("PLANE LATITUDE", "Degrees"),
+                ("PLANE LONGITUDE", "Degrees"),
+                ("PLANE ALTITUDE", "Feet"),
+                ("GROUND VELOCITY", "Knots"),
+                ("AIRSPEED INDICATED", "Knots"),
+                ("VERTICAL SPEED", "Feet per minute"),
+            ] {
+                conn.add_data_definition(
+                    17,
+                    name,
+                    unit,
+                    simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
+                    0,
+                    0.0
+                );
+            }
+
+            conn.request_data_on_sim_object(
+                17,
+                17,
+                0,
+                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND,
+                0,
+                0,
+                0,
+                0
+            );
+
+            // No-smoking sign, chimes like the seatbelt sign
+            conn.add_data_definition(
+                18,
+                "CABIN NO SMOKING ALERT SWITCH",
+                "Bool",
+                simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_INT32,
+                0,
+                0.0
+            );
+
+            conn.request_data_on_sim_object(
+                18,
+                18,
+                0,
+                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND,
+                0,
+                0,
+                0,
+                0
+            );
+
+            // Cabin call L-vars, one definition per aircraft profile. The loaded aircraft's
+            // is requested once its TITLE is known.
+            for definition in &call_definitions {
+                for lvar in &definition.lvars {
+                    conn.add_data_definition(
+                        definition.define_id,
+                        lvar,
+                        "Number",
+                        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
+                        0,
+                        0.0
+                    );
+                }
+            }
+
+            // GSX boarding progress (DefineID 20), order must match `gsx_passengers`
+            for lvar in ["L:FSDT_GSX_NUMPASSENGERS_BOARDING_TOTAL", "L:FSDT_GSX_NUMPASSENGERS"] {
+                conn.add_data_definition(
+                    20,
+                    lvar,
+                    "Number",
+                    simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
+                    0,
+                    0.0
+                );
+            }
+
+            conn.request_data_on_sim_object(
+                20,
+                20,
+                0,
+                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND,
+                0,
+                0,
+                0,
+                0
+            );
+
+            // Sim flight plan progress and zulu time (DefineID 21), order must match `RouteData`
+            let float = simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64;
+            let string = simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING64;
+            for (name, unit, datatype) in [
+                ("GPS IS ACTIVE FLIGHT PLAN", "Bool", float),
+                ("GPS ETE", "Seconds", float),
+                ("ZULU TIME", "Seconds", float),
+                ("GPS FLIGHT PLAN WP INDEX", "Number", float),
+                ("GPS FLIGHT PLAN WP COUNT", "Number", float),
+                ("GPS WP DISTANCE", "Nautical miles", float),
+                ("GPS GROUND SPEED", "Knots", float),
+                ("GPS WP PREV ID", "String64", string),
+                ("GPS WP NEXT ID", "String64", string),
+                ("GPS APPROACH AIRPORT ID", "String64", string),
+            ] {
+                conn.add_data_definition(
+                    21,
+                    name,
+                    unit,
+                    datatype,
+                    0,
+                    0.0
+                );
+            }
+
+            conn.request_data_on_sim_object(
+                21,
+                21,
+                0,
+                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND,
+                0,
+                0,
+                0,
+                0
+            );
+
+            // Ambient weather (DefineID 22), order must match `AmbientData`
+            for (name, unit) in [
+                ("AMBIENT TEMPERATURE", "Celsius"),
+                ("AMBIENT WIND VELOCITY", "Knots"),
+                ("AMBIENT WIND DIRECTION", "Degrees"),
+                ("AMBIENT PRECIP STATE", "Mask"),
+                ("AMBIENT VISIBILITY", "Meters"),
+            ] {
+                conn.add_data_definition(
+                    22,
+                    name,
+                    unit,
+                    simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
+                    0,
+                    0.0
+                );
+            }
+
+            conn.request_data_on_sim_object(
+                22,
+                22,
+                0,
+                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND,
+                0,
+                0,
+                0,
+                0
+            );
+
+            // Time acceleration (DefineID 23)
+            conn.add_data_definition(
+                23,
+                "SIMULATION RATE",
+                "Number",
+                simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64,
+                0,
+                0.0
+            );
+
+            conn.request_data_on_sim_object(
+                23,
+                23,
+                0,
+                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND,
+                0,
+                0,
+                0,
+                0
+            );
+
             println!("GSX bypass pin data definitions and requests set up");
 
             // Map the TOGGLE_JETWAY event
@@ -576,8 +1256,17 @@ pub fn start_simconnect_data_collection(
             conn.set_notification_group_priority(0, simconnect::SIMCONNECT_GROUP_PRIORITY_HIGHEST);
             conn.set_system_event_state(3, simconnect::SIMCONNECT_STATE_SIMCONNECT_STATE_ON);
 
-            // Subscribe to frame events
-            conn.subscribe_to_system_event(7, "Frame");
+            // Loading or clearing a flight plan in the sim
+            conn.subscribe_to_system_event(22, "FlightPlanActivated");
+            conn.subscribe_to_system_event(23, "FlightPlanDeactivated");
+
+            // Sim time stops while paused, in the menus or after a crash
+            conn.subscribe_to_system_event(24, "Pause");
+            conn.subscribe_to_system_event(25, "Sim");
+            conn.subscribe_to_system_event(26, "Crashed");
+            conn.subscribe_to_system_event(27, "CrashReset");
+            conn.subscribe_to_system_event(28, "FlightLoaded");
+            conn.subscribe_to_system_event(29, "AircraftLoaded");
 
             println!("SimConnect data definitions and requests set up successfully");
             println!("Waiting for altitude data...");
@@ -602,54 +1291,85 @@ pub fn start_simconnect_data_collection(
 
         // Read data in a loop while running is true
         let mut prev_beacon_state = -1;
-        let mut prev_seatbelt_state = -1;
         let mut prev_landing_lights_state = -1;  // Add this line
         let mut prev_wing_light_state = -1;  // Track wing light state
-        let mut last_seatbelt_event_time = std::time::Instant::now();
-        let seatbelt_debounce_ms = std::time::Duration::from_millis(3000); // 3 second debounce
+        let chimes = window.state::<Arc<ChimeState>>().inner().clone();
+        let debounce = chimes.settings.lock().unwrap().debounce;
+        let mut seatbelt_debouncer = SignDebouncer::new(debounce, std::time::Instant::now());
+        let mut no_smoking_debouncer = SignDebouncer::new(debounce, std::time::Instant::now());
+        let mut touchdown_monitor = TouchdownMonitor::new();
+        let mut altitude_triggers = AltitudeTriggers::new();
+        let mut planner = DescentPlanner::new();
+        let mut sim_rate = 1.0;
+        let mut phase_tracker = FlightPhaseTracker::new();
+        let mut comfort_tracker = ComfortTracker::new();
+        let mut cabin_service = CabinService::new();
+        let mut turbulence_detector = TurbulenceDetector::new();
+        let mut aircraft_profile: Option<AircraftProfile> = None;
+        // From the last AircraftLoaded event; a new one re-selects the profile
+        let mut aircraft_path: Option<String> = None;
+        let mut call_watcher: Option<CallWatcher> = None;
+        let engine = window.state::<Arc<AudioEngineState>>().inner().clone();
+        let airport_state = window.state::<Arc<AirportState>>().inner().clone();
+        let clock = window.state::<Arc<SimClock>>().inner().clone();
+        // Why the flight-scoped state must be reset, done at the end of the iteration
+        let mut pending_reset: Option<&str> = None;
         
         // Debug logging for initial state values
-        println!("[DEBUG] Initial state: prev_beacon_state={}, prev_seatbelt_state={}, prev_landing_lights_state={}, prev_wing_light_state={}", 
-            prev_beacon_state, prev_seatbelt_state, prev_landing_lights_state, prev_wing_light_state);
+        println!("[DEBUG] Initial state: prev_beacon_state={}, prev_landing_lights_state={}, prev_wing_light_state={}", 
+            prev_beacon_state, prev_landing_lights_state, prev_wing_light_state);
         
         while *arc_state.running.lock().unwrap() {
-            match conn.get_next_message() {
-                Ok(DispatchResult::SimObjectData(data)) => {
+            match conn.next_message() {
+                Ok(SimMessage::Data { define_id, data }) => {
                     consecutive_errors = 0; // Reset error counter on successful data
                     unsafe {
-                        let define_id = std::ptr::read_unaligned(std::ptr::addr_of!(data.dwDefineID));
                         println!("[DEBUG] Received SimObjectData with DefineID: {}", define_id);
                         
                         // Log raw data for debugging
-                        let data_ptr = std::ptr::addr_of!(data.dwData);
+                        let data_ptr = data;
                         let data_size = std::mem::size_of::<i32>();
                         println!("[DEBUG] Data size: {} bytes", data_size);
                         
                         match define_id {
                             0 => { // Flight data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const f64;
-                                let alt = *data_ptr;
+                                let data_ptr = data as *const AltitudeData;
+                                let altitudes = std::ptr::read_unaligned(data_ptr);
+                                let context = flight_state.flight_context.snapshot();
+                                let sample = AltitudeSample {
+                                    msl_ft: altitudes.msl,
+                                    agl_ft: altitudes.agl,
+                                    pressure_altitude_ft: altitudes.pressure_altitude,
+                                    departure_elevation_ft: context.origin_elevation_ft(),
+                                    destination_elevation_ft: context.destination_elevation_ft(),
+                                };
 
                                 // Update flight state with altitude
-                                flight_state.update_flight_data(alt);
-                                
-                                // Check for 10k feet announcement
-                                if flight_state.last_alt < 10000.0 && alt >= 10000.0 && !flight_state.ten_k_announced {
-                                    println!("Passing through 10k feet - triggering announcement");
-                                    flight_state.ten_k_announced = true;
-                                    let _ = window.emit("audio-event", json!({
-                                        "type": "10k-feet"
-                                    }));
+                                let settings = arc_state.altitude_triggers.lock().unwrap().clone();
+                                for event in flight_state.update_flight_data(sample, &mut altitude_triggers, &settings) {
+                                    // The descent planner usually announced the arrival already
+                                    if event == "arrive_soon" {
+                                        if planner.claim() {
+                                            announce_arrival(&window, None, None);
+                                        }
+                                        continue;
+                                    }
+                                    if FRONTEND_EVENTS.contains(&event.as_str()) {
+                                        emit_audio_event(&window, json!({
+                                            "type": event
+                                        }));
+                                    } else {
+                                        play_clip(&window, Playback::Pa, &event, json!({
+                                            "type": event
+                                        }));
+                                    }
                                 }
                                 
                                 // Always emit the data
                                 let _ = window.emit("simconnect-data", flight_state.get_payload());
-                                
-                                // Debug logging
-                                println!("Altitude data received: {:.2} feet", alt);
                             },
                             1 => { // Beacon light data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let beacon_state = *data_ptr;
                                 
                                 if beacon_state != prev_beacon_state {
@@ -660,62 +1380,77 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             2 => { // Seatbelt sign data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let seatbelt_state = *data_ptr;
                                 
-                                println!("[DEBUG] Seatbelt sign data received: current={}, previous={}", 
-                                    seatbelt_state, prev_seatbelt_state);
+                                println!("[DEBUG] Seatbelt sign data received: current={}, previous={:?}", 
+                                    seatbelt_state, seatbelt_debouncer.state());
                                 
                                 // Initialize previous state if this is the first time
-                                if prev_seatbelt_state == -1 {
+                                if seatbelt_debouncer.state().is_none() {
                                     println!("[DEBUG] Initializing seatbelt sign state: {}", seatbelt_state);
-                                    prev_seatbelt_state = seatbelt_state;
+                                    seatbelt_debouncer.update(seatbelt_state == 1, std::time::Instant::now());
                                     
                                     // Update flight state silently (no event)
                                     flight_state.seatbelt_sign = seatbelt_state == 1;
                                     continue;
                                 }
                                 
-                                // Only send event if state actually changed from previous value
-                                if seatbelt_state != prev_seatbelt_state {
-                                    let now = std::time::Instant::now();
-                                    let time_since_last = now.duration_since(last_seatbelt_event_time);
-                                    
-                                    // Handle debouncing with longer grace period for OFF→ON transitions
-                                    let min_interval = if seatbelt_state == 1 && prev_seatbelt_state == 0 {
-                                        // Longer debounce when turning ON from OFF (genuine state change)
-                                        std::time::Duration::from_millis(500)
-                                    } else {
-                                        // Normal debounce for other state changes
-                                        std::time::Duration::from_millis(3000)
-                                    };
-                                    
-                                    if time_since_last >= min_interval {
+                                // Only send event if the change survives the debounce
+                                seatbelt_debouncer.config = chimes.settings.lock().unwrap().debounce;
+                                if seatbelt_debouncer.state() != Some(seatbelt_state == 1) {
+                                    if let Some(sign_on) = seatbelt_debouncer.update(seatbelt_state == 1, std::time::Instant::now()) {
                                         println!("[DEBUG] Seatbelt sign state changed: {} -> {}, sending event", 
-                                            prev_seatbelt_state, seatbelt_state);
-                                        
-                                        // Set previous state before emitting event
-                                        prev_seatbelt_state = seatbelt_state;
-                                        last_seatbelt_event_time = now;
+                                            !sign_on, sign_on);
                                         
+                                        let native = cabin_chime::play_chime(&window, ChimeKind::SignChange);
+
+                                        // Tell turbulence apart from the normal departure/descent use of the sign
+                                        let reason = if sign_on {
+                                            Some(turbulence::seatbelt_on_reason(
+                                                flight_state.flight_phase,
+                                                flight_state.vertical_speed,
+                                                turbulence_detector.recently_rough()
+                                            ))
+                                        } else {
+                                            None
+                                        };
+
                                         // Emit event with boolean state (true=on, false=off)
                                         let _ = window.emit("seatbelt-switch-changed", json!({
-                                            "state": seatbelt_state == 1
+                                            "state": sign_on,
+                                            "reason": reason,
+                                            "native": native
                                         }));
                                         
                                         // Also update flight state
-                                        flight_state.seatbelt_sign = seatbelt_state == 1;
+                                        flight_state.seatbelt_sign = sign_on;
+
+                                        // Crew stop the service while the sign is on
+                                        if flight_state.seatbelt_sign {
+                                            emit_cabin_service_steps(&window, cabin_service.suspend(clock.now()));
+                                        } else if flight_state.flight_phase == FlightPhase::Cruise
+                                            && turbulence_detector.active().is_none() {
+                                            cabin_service.resume(clock.now());
+                                        }
+
+                                        if reason == Some("turbulence") {
+                                            println!("Seatbelt sign on in cruise - playing return to seats PA");
+                                            play_clip(&window, Playback::Pa, "turbulence_seats", json!({
+                                                "type": "turbulence_seats",
+                                                "weather": flight_state.flight_context.snapshot().weather.map(|w| w.report())
+                                            }));
+                                        }
                                         
                                         // Always emit simconnect data event with updated state
                                         let _ = window.emit("simconnect-data", flight_state.get_payload());
                                     } else {
-                                        println!("[DEBUG] Ignoring seatbelt state change due to debounce. Time since last: {:?}ms", 
-                                            time_since_last.as_millis());
+                                        println!("[DEBUG] Ignoring seatbelt state change due to debounce");
                                     }
                                 }
                             },
                             3 => { // Jetway state data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const f64;
+                                let data_ptr = data as *const f64;
                                 let exit_open = *data_ptr;
                                 
                                 // Only process jetway updates if enough time has passed
@@ -730,6 +1465,7 @@ pub fn start_simconnect_data_collection(
                                     if is_attached != was_attached {
                                         // Update jetway state based on exit door position
                                         flight_state.jetway_attached = is_attached;
+                                        flight_state.jetway_attached_at = if is_attached { Some(clock.now()) } else { None };
                                         flight_state.jetway_moving = false;
                                         flight_state.last_request_was_attach = is_attached;
                                         
@@ -739,107 +1475,18 @@ pub fn start_simconnect_data_collection(
                                             println!("Jetway is now fully attached");
                                             flight_state.boarding_music_playing = true;
                                             
-                                            // Start boarding music immediately at full volume
-                                            let _ = window.emit("audio-event", json!({
-                                                "type": "boarding_music",
-                                                "volume": 100
-                                            }));
-                                            
-                                            // Schedule first welcome aboard after 30 seconds
-                                            let window_clone = window.clone();
-                                            thread::spawn(move || {
-                                                thread::sleep(Duration::from_secs(30));
-                                                println!("Lowering boarding music volume and playing welcome aboard");
-                                                
-                                                // Smooth fade out over 2 seconds before welcome aboard starts
-                                                for i in (0..=100).rev().step_by(5) {
-                                                    let _ = window_clone.emit("audio-event", json!({
-                                                        "type": "boarding_music",
-                                                        "volume": i
-                                                    }));
-                                                    thread::sleep(Duration::from_millis(40));
-                                                }
-                                                
-                                                // Wait for fade to complete before playing welcome aboard
-                                                thread::sleep(Duration::from_millis(100));
-                                                
-                                                let _ = window_clone.emit("audio-event", json!({
-                                                    "type": "welcome_aboard"
-                                                }));
-                                                
-                                                // Wait for welcome aboard to finish before scheduling next one
-                                                let window_clone2 = window_clone.clone();
-                                                thread::spawn(move || {
-                                                    // Wait for welcome aboard to finish (assuming it's about 5 seconds)
-                                                    thread::sleep(Duration::from_secs(5));
-                                                    
-                                                    // Smooth fade in over 2 seconds after welcome aboard finishes
-                                                    for i in (0..=100).step_by(5) {
-                                                        let _ = window_clone2.emit("audio-event", json!({
-                                                            "type": "boarding_music",
-                                                            "volume": i
-                                                        }));
-                                                        thread::sleep(Duration::from_millis(40));
-                                                    }
-                                                    
-                                                    // Start the random interval loop for subsequent announcements
-                                                    while flight_state.jetway_attached {
-                                                        // Generate random delay between 30 seconds and 2 minutes
-                                                        let random_seconds = rand::random::<u64>() % 90 + 30; // 30-120 seconds
-                                                        thread::sleep(Duration::from_secs(random_seconds));
-                                                        
-                                                        // Check if jetbridge is still attached before proceeding
-                                                        if !flight_state.jetway_attached {
-                                                            break;
-                                                        }
-                                                        
-                                                        // Smooth fade out over 2 seconds before welcome aboard starts
-                                                        for i in (0..=100).rev().step_by(5) {
-                                                            let _ = window_clone2.emit("audio-event", json!({
-                                                                "type": "boarding_music",
-                                                                "volume": i
-                                                            }));
-                                                            thread::sleep(Duration::from_millis(40));
-                                                        }
-                                                        
-                                                        // Wait for fade to complete before playing welcome aboard
-                                                        thread::sleep(Duration::from_millis(100));
-                                                        
-                                                        let _ = window_clone2.emit("audio-event", json!({
-                                                            "type": "welcome_aboard"
-                                                        }));
-                                                        
-                                                        // Wait for welcome aboard to finish before next loop
-                                                        thread::sleep(Duration::from_secs(5));
-                                                        
-                                                        // Smooth fade in over 2 seconds after welcome aboard finishes
-                                                        for i in (0..=100).step_by(5) {
-                                                            let _ = window_clone2.emit("audio-event", json!({
-                                                                "type": "boarding_music",
-                                                                "volume": i
-                                                            }));
-                                                            thread::sleep(Duration::from_millis(40));
-                                                        }
-                                                    }
-                                                });
-                                            });
+                                            start_boarding_audio(&window, &flight_state.boarding_generation, &flight_state.aircraft_type);
                                         } else {
                                             // Jetway just detached
                                             println!("Jetway is now fully detached");
                                             flight_state.boarding_music_playing = false;
                                             
-                                            // Stop all audio immediately
-                                            let _ = window.emit("audio-event", json!({
-                                                "type": "boarding_music",
-                                                "volume": 0
-                                            }));
+                                            stop_boarding_audio(&window, &flight_state.boarding_generation);
                                             
                                             // Schedule doors to auto announcement after 15 seconds
-                                            let window_clone = window.clone();
-                                            thread::spawn(move || {
-                                                thread::sleep(Duration::from_secs(15));
-                                                println!("Playing doors to auto announcement");
-                                                let _ = window_clone.emit("audio-event", json!({
+                                            println!("Scheduling doors to auto announcement");
+                                            sim_clock::run_after(&window, Duration::from_secs(15), |window| {
+                                                emit_audio_event(window, json!({
                                                     "type": "doors_auto"
                                                 }));
                                             });
@@ -850,7 +1497,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             4 => { // Landing lights data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let landing_lights_state = *data_ptr;
                                 
                                 if landing_lights_state != prev_landing_lights_state {
@@ -864,7 +1511,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             5 => { // Camera state data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let camera_state = *data_ptr;
                                 
                                 // For debugging - show camera state
@@ -904,7 +1551,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             6 => { // Camera substate data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let camera_substate = *data_ptr;
                                 
                                 // Only log if enough time has passed since the last update
@@ -915,7 +1562,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             7 => { // Camera X position
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const f64;
+                                let data_ptr = data as *const f64;
                                 let camera_x = *data_ptr;
                                 
                                 // For debugging - show X position with more detail
@@ -943,7 +1590,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             8 => { // Camera Y position
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const f64;
+                                let data_ptr = data as *const f64;
                                 let camera_y = *data_ptr;
                                 
                                 // For debugging - show Y position with more detail
@@ -971,7 +1618,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             9 => { // Camera Z position
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const f64;
+                                let data_ptr = data as *const f64;
                                 let camera_z = *data_ptr;
                                 
                                 // For debugging - show Z position with more detail
@@ -1001,7 +1648,7 @@ pub fn start_simconnect_data_collection(
                             10 => { // Frame event - do nothing special here
                             },
                             11 => { // GSX bypass pin data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let gsx_bypass_pin_state = *data_ptr;
                                 
                                 // Log raw state value
@@ -1019,7 +1666,8 @@ pub fn start_simconnect_data_collection(
                                     // If pin was just inserted, play safety video
                                     if new_state {
                                         println!("GSX bypass pin inserted - playing safety video");
-                                        let _ = window.emit("audio-event", json!({
+                                        *interphone_state.safety_demo_started.lock().unwrap() = Some(clock.now());
+                                        emit_audio_event(&window, json!({
                                             "type": "safety_video",
                                             "volume": flight_state.volume_level
                                         }));
@@ -1030,7 +1678,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             12 => { // Alternative GSX bypass pin data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let gsx_bypass_pin_state = *data_ptr;
                                 
                                 // Log raw state value
@@ -1050,7 +1698,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             13 => { // Taxi light data to use as wing light
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const i32;
+                                let data_ptr = data as *const i32;
                                 let light_value = *data_ptr;
                                 
                                 // Print the raw value for debugging
@@ -1098,7 +1746,7 @@ pub fn start_simconnect_data_collection(
                                 }
                             },
                             14 => { // Aircraft title data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const u8;
+                                let data_ptr = data as *const u8;
                                 let mut aircraft_title = String::new();
                                 
                                 // Safely read the string data
@@ -1117,18 +1765,18 @@ pub fn start_simconnect_data_collection(
                                 // Update flight state with aircraft type from title
                                 if !aircraft_title.is_empty() {
                                     flight_state.aircraft_type = aircraft_title.clone();
-                                    
-                                    // Emit aircraft type event
-                                    let _ = window.emit("aircraft-type-changed", json!({
-                                        "type": aircraft_title
-                                    }));
+                                    if !aircraft_profile.as_ref().is_some_and(|profile| profile.is_for(&aircraft_title, aircraft_path.as_deref())) {
+                                        let profile = AircraftProfile::select(&aircraft_title, aircraft_path.as_deref());
+                                        change_aircraft(&window, &conn, &profile, &mut call_watcher, custom_call_lvar.as_deref());
+                                        aircraft_profile = Some(profile);
+                                    }
                                     
                                     // Also emit regular simconnect data with updated aircraft type
                                     let _ = window.emit("simconnect-data", flight_state.get_payload());
                                 }
                             },
                             15 => { // ATC model data
-                                let data_ptr = std::ptr::addr_of!(data.dwData) as *const u8;
+                                let data_ptr = data as *const u8;
                                 let mut atc_model = String::new();
                                 
                                 // Safely read the string data
@@ -1147,29 +1795,343 @@ pub fn start_simconnect_data_collection(
                                 // Only update if we don't already have a title and this isn't empty
                                 if flight_state.aircraft_type == "Unknown" && !atc_model.is_empty() {
                                     flight_state.aircraft_type = atc_model.clone();
-                                    
-                                    // Emit aircraft type event
-                                    let _ = window.emit("aircraft-type-changed", json!({
-                                        "type": atc_model
-                                    }));
+                                    if aircraft_profile.is_none() {
+                                        let profile = AircraftProfile::select(&atc_model, aircraft_path.as_deref());
+                                        change_aircraft(&window, &conn, &profile, &mut call_watcher, custom_call_lvar.as_deref());
+                                        aircraft_profile = Some(profile);
+                                    }
                                     
                                     // Also emit regular simconnect data with updated aircraft type
                                     let _ = window.emit("simconnect-data", flight_state.get_payload());
                                 }
                             },
+                            16 => { // Flight dynamics for touchdown analysis
+                                let data_ptr = data as *const FlightDynamicsData;
+                                let dynamics = std::ptr::read_unaligned(data_ptr);
+
+                                flight_state.vertical_speed = dynamics.vertical_speed;
+                                flight_state.ground_speed = dynamics.ground_velocity;
+
+                                match turbulence_detector.update(
+                                    dynamics.sim_on_ground != 0.0,
+                                    dynamics.accel_body_y,
+                                    dynamics.g_force
+                                ) {
+                                    Some(TurbulenceEvent::Started(level)) | Some(TurbulenceEvent::Intensified(level)) => {
+                                        println!("Turbulence detected: {}", level.as_str());
+                                        flight_state.turbulence = Some(level);
+                                        emit_cabin_service_steps(&window, cabin_service.suspend(clock.now()));
+
+                                        // The crew react on their own, even if the sign is still off
+                                        if flight_state.flight_phase != FlightPhase::Approach {
+                                            play_clip(&window, Playback::Pa, "turbulence_crew", json!({
+                                                "type": "turbulence_crew",
+                                                "level": level.as_str(),
+                                                "seatbeltSign": flight_state.seatbelt_sign,
+                                                "weather": flight_state.flight_context.snapshot().weather.map(|w| w.report())
+                                            }));
+                                        }
+                                        let _ = window.emit("turbulence", json!({
+                                            "active": true,
+                                            "level": level.as_str()
+                                        }));
+                                    },
+                                    Some(TurbulenceEvent::Ended) => {
+                                        println!("Turbulence has ended");
+                                        flight_state.turbulence = None;
+                                        if !flight_state.seatbelt_sign && flight_state.flight_phase == FlightPhase::Cruise {
+                                            cabin_service.resume(clock.now());
+                                        }
+                                        let _ = window.emit("turbulence", json!({
+                                            "active": false,
+                                            "level": null
+                                        }));
+                                    },
+                                    None => {}
+                                }
+
+                                let penalty = comfort_tracker.update(&dynamics);
+                                if penalty > 0.0 {
+                                    logbook.apply_comfort_penalty(penalty);
+                                }
+
+                                if let Some((previous, phase)) = phase_tracker.update(
+                                    dynamics.sim_on_ground != 0.0,
+                                    dynamics.vertical_speed,
+                                    dynamics.ground_velocity,
+                                    dynamics.altitude_above_ground
+                                ) {
+                                    println!("Flight phase changed: {} -> {}", previous.as_str(), phase.as_str());
+                                    flight_state.flight_phase = phase;
+                                    *interphone_state.phase.lock().unwrap() = phase;
+                                    logbook.record_phase(phase);
+                                    // The cruise PA was made when the cruise first began
+                                    if !(previous == FlightPhase::Descent && phase == FlightPhase::Cruise) {
+                                        captain_pa::play_for_phase(&window, phase);
+                                    }
+                                    track.recorder.lock().unwrap().add_waypoint("phase", phase.as_str());
+                                    if phase == FlightPhase::Arrived {
+                                        logbook.checkpoint(window.app_handle());
+                                    }
+
+                                    match phase {
+                                        FlightPhase::Takeoff => {
+                                            planner = DescentPlanner::new();
+                                            // Weather seen at the last destination isn't this one's
+                                            flight_state.flight_context.context.lock().unwrap().arrival_weather = None;
+                                        },
+                                        FlightPhase::Climb if flight_state.takeoff_time.is_none() => {
+                                            flight_state.takeoff_time = Some(clock.now());
+                                        },
+                                        FlightPhase::Cruise if !cabin_service.is_started() => {
+                                            let block_time = flight_state.flight_context.snapshot().ofp
+                                                .and_then(|ofp| ofp.block_time_secs)
+                                                .map(Duration::from_secs);
+                                            let since_takeoff = flight_state.takeoff_time
+                                                .map(|t| clock.since(t))
+                                                .unwrap_or_default();
+                                            cabin_service.start(
+                                                cabin_service::expected_cruise(block_time, since_takeoff),
+                                                flight_state.seatbelt_sign,
+                                                clock.now()
+                                            );
+                                        },
+                                        // Back in cruise after a step descent
+                                        FlightPhase::Cruise => {
+                                            if !flight_state.seatbelt_sign && turbulence_detector.active().is_none() {
+                                                cabin_service.resume(clock.now());
+                                            }
+                                        },
+                                        // Descent may be a step down to a lower cruise level
+                                        FlightPhase::Descent => {
+                                            emit_cabin_service_steps(&window, cabin_service.suspend(clock.now()));
+                                        },
+                                        FlightPhase::Approach => {
+                                            emit_cabin_service_steps(&window, cabin_service.cancel());
+                                        },
+                                        _ => {}
+                                    }
+
+                                    let _ = window.emit("flight-phase-changed", json!({
+                                        "previous": previous.as_str(),
+                                        "phase": phase.as_str()
+                                    }));
+                                    let _ = window.emit("simconnect-data", flight_state.get_payload());
+                                }
+
+                                if let Some(report) = touchdown_monitor.update(&dynamics) {
+                                    println!("Touchdown: {:.0} fpm, {:.2} G, bank {:.1}, pitch {:.1} - rated {}",
+                                        report.touchdown_rate_fpm, report.g_force, report.bank_deg,
+                                        report.pitch_deg, report.rating.as_str());
+
+                                    let _ = window.emit("touchdown", &report);
+
+                                    let settings = arc_state.touchdown_settings.lock().unwrap().clone();
+                                    if settings.applause_enabled && report.rating <= settings.applause_max_rating {
+                                        play_clip(&window, Playback::Cabin, "applause", json!({
+                                            "type": "applause",
+                                            "rating": report.rating.as_str()
+                                        }));
+                                    }
+
+                                    // A hard landing doesn't get the cheerful welcome right away.
+                                    // The report comes once the bounce window closed, 10 s after touchdown.
+                                    if settings.welcome_enabled {
+                                        let delay = if report.rating >= TouchdownRating::Firm { 35 } else { 10 };
+                                        sim_clock::run_after(&window, Duration::from_secs(delay), |window| {
+                                            play_clip(window, Playback::Pa, "weve_arrived", json!({
+                                                "type": "weve_arrived"
+                                            }));
+                                        });
+                                    }
+
+                                    logbook.record_touchdown(&report);
+                                    logbook.checkpoint(window.app_handle());
+                                    flight_state.last_touchdown = Some(report);
+                                    let _ = window.emit("simconnect-data", flight_state.get_payload());
+                                }
+                            },
+                            17 => { // Aircraft position
+                                let data_ptr = data as *const PositionData;
+                                let sample = std::ptr::read_unaligned(data_ptr);
+                                let position = GeoPosition {
+                                    latitude: sample.latitude,
+                                    longitude: sample.longitude,
+                                    altitude_ft: sample.altitude,
+                                };
+                                flight_state.last_position = Some(position);
+
+                                track.recorder.lock().unwrap().record_position(TrackPoint {
+                                    timestamp: logbook::unix_now(),
+                                    latitude: sample.latitude,
+                                    longitude: sample.longitude,
+                                    altitude_ft: sample.altitude,
+                                    ground_speed_kts: sample.ground_velocity,
+                                    indicated_airspeed_kts: sample.airspeed_indicated,
+                                    vertical_speed_fpm: sample.vertical_speed,
+                                });
+
+                                // The flight begins once we know both the aircraft and where it is
+                                if !logbook.is_open() && flight_state.aircraft_type != "Unknown" {
+                                    logbook.open(window.app_handle(), &flight_state.aircraft_type, Some(position));
+                                }
+
+                                if let Some(database) = airport_state.get() {
+                                    airports::update_context(
+                                        &flight_state.flight_context,
+                                        &database,
+                                        sample.latitude,
+                                        sample.longitude,
+                                        flight_state.flight_phase
+                                    );
+                                }
+
+                                let boarding = ambience::boarding_progress(
+                                    flight_state.gsx_passengers,
+                                    flight_state.jetway_attached_at.map(|t| clock.since(t))
+                                );
+                                let _ = engine.update_ambience(flight_state.flight_phase, boarding);
+                            },
+                            18 => { // No-smoking sign
+                                let data_ptr = data as *const i32;
+                                let sign_on = *data_ptr == 1;
+
+                                no_smoking_debouncer.config = chimes.settings.lock().unwrap().debounce;
+                                if let Some(sign_on) = no_smoking_debouncer.update(sign_on, std::time::Instant::now()) {
+                                    println!("No smoking sign {}", if sign_on { "ON" } else { "OFF" });
+                                    cabin_chime::play_chime(&window, ChimeKind::SignChange);
+                                    let _ = window.emit("no-smoking-switch-changed", json!({
+                                        "state": sign_on
+                                    }));
+                                }
+                            },
+                            20 => { // GSX passenger counts
+                                let data_ptr = data as *const f64;
+                                let boarded = std::ptr::read_unaligned(data_ptr);
+                                let total = std::ptr::read_unaligned(data_ptr.add(1));
+
+                                // Both read zero when GSX isn't installed or not boarding
+                                flight_state.gsx_passengers = if total > 0.0 { Some((boarded, total)) } else { None };
+                            },
+                            21 => { // Sim flight plan and route progress
+                                let data_ptr = data as *const RouteData;
+                                let route = std::ptr::read_unaligned(data_ptr);
+
+                                // The GPS flag also covers activations we missed the event for
+                                let route_changed = {
+                                    let mut context = flight_state.flight_context.context.lock().unwrap();
+                                    let route_of = |plan: &Option<SimFlightPlan>| plan.as_ref()
+                                        .map(|plan| (plan.origin.clone(), plan.destination.clone()));
+                                    let before = route_of(&context.sim_flight_plan);
+                                    if route.gps_active != 0.0 {
+                                        context.sim_flight_plan.get_or_insert_with(SimFlightPlan::default).update(&GpsReading {
+                                            waypoint_index: route.waypoint_index.max(0.0) as u32,
+                                            waypoint_count: route.waypoint_count.max(0.0) as u32,
+                                            previous_waypoint: sim_string(&route.previous_waypoint),
+                                            next_waypoint: sim_string(&route.next_waypoint),
+                                            next_waypoint_nm: route.next_waypoint_nm,
+                                            ete_secs: route.gps_ete,
+                                            ground_speed_kts: route.ground_speed,
+                                            approach_airport: sim_string(&route.approach_airport),
+                                        });
+                                    } else if !context.sim_flight_plan.as_ref().is_some_and(|plan| plan.from_file) {
+                                        // A plan read from its file stays until the sim deactivates it
+                                        context.sim_flight_plan = None;
+                                    }
+                                    before != route_of(&context.sim_flight_plan)
+                                };
+                                let context = flight_state.flight_context.snapshot();
+                                if route_changed {
+                                    println!("Sim flight plan: {:?} -> {:?}", context.origin_icao(), context.destination_icao());
+                                    let _ = window.emit("flight-context-changed", &context);
+                                }
+
+                                // What's left of the sim's flight plan along its route, else the
+                                // straight-line distance to the destination
+                                let remaining_nm = context.sim_flight_plan.as_ref()
+                                    .and_then(|plan| plan.remaining_nm)
+                                    .or_else(|| match (context.destination_position(), flight_state.last_position) {
+                                        (Some((latitude, longitude)), Some(position)) => Some(airports::distance_nm(
+                                            position.latitude, position.longitude, latitude, longitude
+                                        )),
+                                        _ => None,
+                                    });
+
+                                let settings = arc_state.descent_settings.lock().unwrap().clone();
+                                flight_state.arrival_estimate = remaining_nm.and_then(|remaining_nm| {
+                                    descent_planner::estimate(&RouteProgress {
+                                        remaining_nm,
+                                        ground_speed_kts: flight_state.ground_speed,
+                                        altitude_ft: flight_state.last_alt,
+                                        destination_elevation_ft: context.destination_elevation_ft(),
+                                    }, &settings)
+                                });
+
+                                if let Some(estimate) = flight_state.arrival_estimate {
+                                    if planner.is_due(flight_state.flight_phase, &estimate, &settings, clock.now()) && planner.claim() {
+                                        println!("Arriving in about {:.0} minutes ({:.0} nm) - triggering arrival soon announcement",
+                                            estimate.minutes_to_landing, estimate.remaining_nm);
+                                        let local_time = context.ofp.as_ref()
+                                            .and_then(|ofp| ofp.destination.utc_offset_hours)
+                                            .map(|offset| descent_planner::local_time(route.zulu_time, offset));
+                                        announce_arrival(&window, Some(estimate.minutes_to_landing), local_time);
+                                    }
+                                }
+                            },
+                            22 => { // Ambient weather
+                                let data_ptr = data as *const AmbientData;
+                                let ambient = std::ptr::read_unaligned(data_ptr);
+                                let weather = WeatherSample {
+                                    temperature_c: ambient.temperature,
+                                    wind_speed_kts: ambient.wind_velocity,
+                                    wind_direction_deg: ambient.wind_direction,
+                                    precipitation: Precipitation::from_state(ambient.precip_state as u32),
+                                    visibility_m: ambient.visibility,
+                                };
+
+                                let mut context = flight_state.flight_context.context.lock().unwrap();
+                                context.weather = Some(weather);
+                                // Near landing the weather around us is the destination's
+                                if matches!(flight_state.flight_phase,
+                                    FlightPhase::Approach | FlightPhase::Landing | FlightPhase::TaxiIn | FlightPhase::Arrived)
+                                {
+                                    context.arrival_weather = Some(weather.at_surface(flight_state.altitude.agl_ft));
+                                }
+                            },
+                            23 => { // Simulation rate
+                                let data_ptr = data as *const f64;
+                                let rate = std::ptr::read_unaligned(data_ptr);
+                                if rate != sim_rate {
+                                    println!("Simulation rate: {}x", rate);
+                                    sim_rate = rate;
+                                    clock.set_rate(rate);
+                                    sim_clock::emit_state(&window, &clock);
+                                }
+                            },
+                            id if call_watcher.as_ref().is_some_and(|watcher| watcher.definition.define_id == id) => { // Cabin call L-vars
+                                if let Some(watcher) = call_watcher.as_mut() {
+                                    let data_ptr = data as *const f64;
+                                    let values: Vec<f64> = (0..watcher.definition.lvars.len())
+                                        .map(|i| std::ptr::read_unaligned(data_ptr.add(i)))
+                                        .collect();
+
+                                    if watcher.update(&values) {
+                                        interphone::call(&window);
+                                    }
+                                }
+                            },
                             _ => {
                                 // Only log unknown DefineIDs if we're in debug mode
                                 #[cfg(debug_assertions)]
                                 {
-                                    let define_id = std::ptr::read_unaligned(std::ptr::addr_of!(data.dwDefineID));
                                 println!("Received data with unknown DefineID: {}", define_id);
                                 }
                             }
                         }
                     }
                 },
-                Ok(DispatchResult::Event(event)) => {
-                    if event.uEventID == 3 { // TOGGLE_JETWAY event
+                Ok(SimMessage::Event { id: event_id, data: event_data }) => {
+                    if event_id == 3 { // TOGGLE_JETWAY event
                         println!("TOGGLE_JETWAY event received");
                         let now = std::time::Instant::now();
                         if now.duration_since(flight_state.last_toggle_time).as_secs() >= 5 {
@@ -1194,61 +2156,64 @@ pub fn start_simconnect_data_collection(
                             
                             let _ = window.emit("simconnect-data", flight_state.get_payload());
                         }
-                    } else if event.uEventID == 7 { // Frame event
-                        // Periodically re-request aircraft type data (every 30 seconds)
-                        // This helps detect aircraft changes during a session
-                        static mut LAST_AIRCRAFT_CHECK: Option<std::time::Instant> = None;
-                        
-                        let now = std::time::Instant::now();
-                        let should_check = unsafe {
-                            match LAST_AIRCRAFT_CHECK {
-                                Some(last) if now.duration_since(last).as_secs() < 30 => false,
-                                _ => {
-                                    LAST_AIRCRAFT_CHECK = Some(now);
-                                    true
-                                }
-                            }
-                        };
-                        
-                        if should_check {
-                            println!("[DEBUG] Periodically checking aircraft type...");
-                            
-                            // Request the aircraft type data again
-                            conn.request_data_on_sim_object(
-                                14, // TITLE
-                                14,
-                                0,
-                                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_ONCE,
-                                0,
-                                0,
-                                0,
-                                0
-                            );
-                            
-                            conn.request_data_on_sim_object(
-                                15, // ATC MODEL
-                                15,
-                                0,
-                                simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_ONCE,
-                                0,
-                                0,
-                                0,
-                                0
-                            );
-                        }
+                    } else if event_id == 23 { // FlightPlanDeactivated; activations come with their file
+                        println!("Sim flight plan deactivated");
+                        flight_state.flight_context.context.lock().unwrap().sim_flight_plan = None;
+                        let _ = window.emit("flight-context-changed", flight_state.flight_context.snapshot());
+                    } else if event_id == 24 { // Pause
+                        let paused = event_data != 0;
+                        println!("Sim {}", if paused { "paused" } else { "unpaused" });
+                        clock.set_paused(paused);
+                        sim_clock::emit_state(&window, &clock);
+                    } else if event_id == 25 { // Sim started or stopped
+                        let running = event_data != 0;
+                        println!("Sim {}", if running { "running" } else { "stopped" });
+                        clock.set_stopped(!running);
+                        sim_clock::emit_state(&window, &clock);
+                    } else if event_id == 26 { // Crashed
+                        println!("Aircraft crashed");
+                        clock.set_stopped(true);
+                        sim_clock::emit_state(&window, &clock);
+                        // The flight is over; the rest is reset once the sim restarts it
+                        clock.cancel_pending();
+                        stop_boarding_audio(&window, &flight_state.boarding_generation);
+                        logbook.close(window.app_handle(), flight_state.last_position);
+                    } else if event_id == 27 { // CrashReset
+                        println!("Crash reset");
+                        clock.set_stopped(false);
+                        sim_clock::emit_state(&window, &clock);
+                        pending_reset = Some("crash reset");
+                    }
+                },
+                Ok(SimMessage::FileLoaded { id, path }) => {
+                    if id == 28 { // FlightLoaded
+                        println!("Flight loaded: {}", path);
+                        pending_reset = Some("flight loaded");
+                    } else if id == 22 { // FlightPlanActivated, naming the .pln
+                        let plan = sim_flight_plan::load_pln(Path::new(&path)).unwrap_or_else(|e| {
+                            println!("Failed to read the activated flight plan: {}", e);
+                            SimFlightPlan::default()
+                        });
+                        println!("Sim flight plan activated: {:?} -> {:?}", plan.origin, plan.destination);
+                        flight_state.flight_context.context.lock().unwrap().sim_flight_plan = Some(plan);
+                        let _ = window.emit("flight-context-changed", flight_state.flight_context.snapshot());
+                    } else if id == 29 { // AircraftLoaded
+                        println!("Aircraft loaded: {}", path);
+                        aircraft_path = Some(path);
+                        request_aircraft_identity(&conn);
                     }
                 },
-                Ok(DispatchResult::Open(_)) => {
+                Ok(SimMessage::Open) => {
                     println!("SimConnect connection opened. Waiting for simulator to be ready...");
                     let _ = window.emit("simconnect-open", json!({}));
                     consecutive_errors = 0;
                 },
-                Ok(DispatchResult::Quit(_)) => {
+                Ok(SimMessage::Quit) => {
                     println!("SimConnect connection closed.");
                     let _ = window.emit("simconnect-quit", json!({}));
                     break;
                 },
-                Ok(DispatchResult::Exception(exception)) => {
+                Ok(SimMessage::Exception(exception)) => {
                     // Only log exceptions if enough time has passed since the last error
                     if last_error_time.elapsed().as_secs() >= 1 {
                         consecutive_errors += 1;
@@ -1282,10 +2247,48 @@ pub fn start_simconnect_data_collection(
                 }
             }
 
+            emit_cabin_service_steps(&window, cabin_service.poll(clock.now()));
+
+            if let Some(reason) = pending_reset.take() {
+                println!("Resetting flight state: {}", reason);
+                clock.cancel_pending();
+                stop_boarding_audio(&window, &flight_state.boarding_generation);
+                if let Err(e) = engine.send(AudioCommand::StopAll) {
+                    println!("Failed to stop announcements: {}", e);
+                }
+                logbook.close(window.app_handle(), flight_state.last_position);
+                track.recorder.lock().unwrap().clear();
+                interphone_state.reset();
+                flight_state.flight_context.reset_flight();
+
+                flight_state = FlightDataState::new(flight_state.flight_context.clone());
+                prev_beacon_state = -1;
+                prev_landing_lights_state = -1;
+                prev_wing_light_state = -1;
+                let debounce = chimes.settings.lock().unwrap().debounce;
+                seatbelt_debouncer = SignDebouncer::new(debounce, std::time::Instant::now());
+                no_smoking_debouncer = SignDebouncer::new(debounce, std::time::Instant::now());
+                touchdown_monitor = TouchdownMonitor::new();
+                altitude_triggers = AltitudeTriggers::new();
+                planner = DescentPlanner::new();
+                phase_tracker.reset();
+                comfort_tracker = ComfortTracker::new();
+                cabin_service = CabinService::new();
+                turbulence_detector = TurbulenceDetector::new();
+
+                // The logbook opens a new record once the aircraft is known again
+                request_aircraft_identity(&conn);
+                let _ = window.emit("flight-reset", json!({ "reason": reason }));
+                let _ = window.emit("flight-context-changed", flight_state.flight_context.snapshot());
+                let _ = window.emit("simconnect-data", flight_state.get_payload());
+            }
+
             // Add a small sleep at the end of each loop iteration
             std::thread::sleep(std::time::Duration::from_millis(2));
         }
 
+        logbook.close(window.app_handle(), flight_state.last_position);
+
         println!("SimConnect data collection stopped.");
         *arc_state.running.lock().unwrap() = false;
         let _ = window.emit("simconnect-quit", json!({}));
@@ -1325,5 +2328,48 @@ pub fn toggle_wing_light(
     println!("Emitted wing-light-toggle event");
 }
 
+/// Replaces the altitude triggers and the transition altitude.
+#[tauri::command]
+pub fn set_altitude_triggers(state: State<Arc<SimConnectState>>, settings: AltitudeTriggerSettings) {
+    *state.altitude_triggers.lock().unwrap() = settings;
+}
 
+#[tauri::command]
+pub fn get_altitude_triggers(state: State<Arc<SimConnectState>>) -> AltitudeTriggerSettings {
+    state.altitude_triggers.lock().unwrap().clone()
+}
 
+/// Configures when the "arriving soon" PA plays and how the descent is estimated.
+#[tauri::command]
+pub fn set_descent_settings(state: State<Arc<SimConnectState>>, settings: DescentSettings) {
+    *state.descent_settings.lock().unwrap() = settings;
+}
+
+#[tauri::command]
+pub fn get_descent_settings(state: State<Arc<SimConnectState>>) -> DescentSettings {
+    state.descent_settings.lock().unwrap().clone()
+}
+
+/// Chooses between SimConnect and X-Plane; takes effect when data collection next starts.
+#[tauri::command]
+pub fn set_xplane_settings(state: State<Arc<SimConnectState>>, settings: XPlaneSettings) {
+    *state.xplane.lock().unwrap() = settings;
+}
+
+#[tauri::command]
+pub fn get_xplane_settings(state: State<Arc<SimConnectState>>) -> XPlaneSettings {
+    state.xplane.lock().unwrap().clone()
+}
+
+/// Configures the announcements triggered after touchdown.
+#[tauri::command]
+pub fn set_touchdown_announcements(
+    state: State<Arc<SimConnectState>>,
+    applause: bool,
+    welcome: bool
+) {
+    let mut settings = state.touchdown_settings.lock().unwrap();
+    settings.applause_enabled = applause;
+    settings.welcome_enabled = welcome;
+    println!("Touchdown announcements updated: applause={}, welcome={}", applause, welcome);
+}
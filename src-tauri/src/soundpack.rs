@@ -0,0 +1,205 @@
+use std::fs::{ self, File };
+use std::io::{ BufReader, BufWriter, Write };
+use std::path::{ Component, Path, PathBuf };
+use std::sync::{ Arc, Mutex };
+use rodio::{ Decoder, Source, source::UniformSourceIterator };
+use tauri::{ AppHandle, State };
+
+use crate::acoustics::ZoneAcoustics;
+use crate::ambience;
+use crate::asset_analyzer::AssetAnalyzer;
+use crate::audio_engine::AudioEngineState;
+
+/// All clips are decoded to this format so they can be mixed and concatenated freely.
+pub const SAMPLE_RATE: u32 = 44100;
+pub const CHANNELS: u16 = 2;
+
+pub const CLIP_EXTENSIONS: [&str; 4] = ["wav", "mp3", "ogg", "flac"];
+
+/// A directory of announcement clips addressed by key, e.g. `numbers/fifteen`
+/// resolves to `<root>/numbers/fifteen.wav` (or .mp3/.ogg/.flac).
+#[derive(Clone, Debug)]
+pub struct Soundpack {
+    pub root: PathBuf,
+    pub analyzer: Arc<AssetAnalyzer>,
+}
+
+impl Soundpack {
+    pub fn new(root: PathBuf, analyzer: Arc<AssetAnalyzer>) -> Self {
+        Soundpack { root, analyzer }
+    }
+
+    /// Finds the file for a clip key, trying each supported extension.
+    pub fn resolve(&self, key: &str) -> Option<PathBuf> {
+        if !is_valid_key(key) {
+            return None;
+        }
+        CLIP_EXTENSIONS.iter()
+            .map(|ext| self.root.join(format!("{}.{}", key, ext)))
+            .find(|path| path.is_file())
+    }
+
+    /// Decodes a clip to interleaved f32 samples at `SAMPLE_RATE`/`CHANNELS`,
+    /// normalised to the target loudness.
+    pub fn decode(&self, key: &str) -> Result<Vec<f32>, String> {
+        let path = self.resolve(key)
+            .ok_or_else(|| format!("Clip '{}' not found in soundpack {}", key, self.root.display()))?;
+        let mut samples = decode_file(&path)?;
+        if let Err(e) = self.analyzer.normalize(&path, &mut samples) {
+            println!("Playing '{}' without normalisation: {}", key, e);
+        }
+        Ok(samples)
+    }
+
+    /// Opens a clip as an endless loop, streamed from disk.
+    pub fn open_loop(&self, key: &str) -> Result<Box<dyn Source<Item = f32> + Send>, String> {
+        let path = self.resolve(key)
+            .ok_or_else(|| format!("Clip '{}' not found in soundpack {}", key, self.root.display()))?;
+        let file = File::open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let decoder = Decoder::new_looped(BufReader::new(file))
+            .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+        Ok(Box::new(decoder.convert_samples::<f32>()))
+    }
+
+    /// Saves interleaved samples at `SAMPLE_RATE`/`CHANNELS` as a WAV clip under `key`.
+    pub fn write(&self, key: &str, samples: &[f32]) -> Result<PathBuf, String> {
+        if !is_valid_key(key) {
+            return Err(format!("Invalid clip key '{}'", key));
+        }
+        let path = self.root.join(format!("{}.wav", key));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        write_wav(&path, samples)?;
+        Ok(path)
+    }
+}
+
+// Keys are relative paths; never let one climb out of the soundpack. Backslashes and
+// drive letters are refused on every platform so a key means the same everywhere.
+fn is_valid_key(key: &str) -> bool {
+    let mut components = Path::new(key).components().peekable();
+    components.peek().is_some()
+        && components.all(|component| matches!(component, Component::Normal(_)))
+        && !key.contains(['\\', ':'])
+}
+
+/// Writes interleaved samples at `SAMPLE_RATE`/`CHANNELS` as 16-bit PCM WAV.
+pub fn write_wav(path: &Path, samples: &[f32]) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+
+    let data_len = (samples.len() * 2) as u32;
+    let block_align = CHANNELS * 2;
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(36 + data_len).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&CHANNELS.to_le_bytes());
+    header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    header.extend_from_slice(&(SAMPLE_RATE * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+
+    let write_error = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    out.write_all(&header).map_err(write_error)?;
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        out.write_all(&value.to_le_bytes()).map_err(write_error)?;
+    }
+    out.flush().map_err(write_error)
+}
+
+/// Opens any supported audio file as a stream at `SAMPLE_RATE`/`CHANNELS`, decoded as it plays.
+pub fn stream_file(path: &Path) -> Result<UniformSourceIterator<Decoder<BufReader<File>>, f32>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let decoder = Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+    Ok(UniformSourceIterator::new(decoder, CHANNELS, SAMPLE_RATE))
+}
+
+/// Decodes any supported audio file to interleaved f32 samples at `SAMPLE_RATE`/`CHANNELS`.
+pub fn decode_file(path: &Path) -> Result<Vec<f32>, String> {
+    Ok(stream_file(path)?.collect())
+}
+
+/// The soundpack currently used by the native audio engine.
+pub struct SoundpackState {
+    pub current: Mutex<Option<Soundpack>>,
+    /// Shared by every soundpack, so analyses survive switching packs
+    pub analyzer: Arc<AssetAnalyzer>,
+}
+
+impl SoundpackState {
+    pub fn new() -> Self {
+        SoundpackState {
+            current: Mutex::new(None),
+            analyzer: Arc::new(AssetAnalyzer::default()),
+        }
+    }
+
+    pub fn get(&self) -> Result<Soundpack, String> {
+        self.current.lock().unwrap().clone()
+            .ok_or_else(|| "No soundpack selected".to_string())
+    }
+}
+
+/// Selects the soundpack directory used for native announcements, along with its acoustics.
+#[tauri::command]
+pub fn set_soundpack_dir(
+    app: AppHandle,
+    state: State<Arc<SoundpackState>>,
+    engine: State<Arc<AudioEngineState>>,
+    path: String
+) -> Result<(), String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Soundpack directory {} does not exist", path));
+    }
+    *engine.acoustics.lock().unwrap() = ZoneAcoustics::load(&root)?;
+    println!("Soundpack set to {}", root.display());
+    if let Err(e) = state.analyzer.open_cache(&app) {
+        println!("Clip analysis won't be cached: {}", e);
+    }
+    let soundpack = Soundpack::new(root, state.analyzer.clone());
+
+    let loops: Vec<_> = ambience::LAYERS.iter()
+        .map(|layer| soundpack.open_loop(layer.clip_key()).ok())
+        .collect();
+    if let Err(e) = engine.set_ambience(loops) {
+        println!("Failed to start ambience: {}", e);
+    }
+
+    *state.current.lock().unwrap() = Some(soundpack);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_stay_inside_the_soundpack() {
+        assert!(is_valid_key("numbers/fifteen"));
+        assert!(is_valid_key("captain/before-takeoff"));
+        for key in ["", "..", "../x", "numbers/../../x", "/etc/passwd", "./x",
+            "..\\..\\x", "C:\\Windows\\x", "C:x", "\\\\server\\share\\x"] {
+            assert!(!is_valid_key(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn invalid_keys_are_neither_read_nor_written() {
+        let soundpack = Soundpack::new(std::env::temp_dir(), Arc::new(AssetAnalyzer::default()));
+        assert!(soundpack.resolve("../x").is_none());
+        assert!(soundpack.write("..\\x", &[0.0; 4]).unwrap_err().starts_with("Invalid clip key"));
+    }
+}
@@ -0,0 +1,240 @@
+use serde::{ Deserialize, Serialize };
+use std::time::{ Duration, Instant };
+
+/// Raw flight dynamics sampled every sim frame (DefineID 16).
+/// Field order must match the `add_data_definition` calls in `simconnect_data.rs`.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FlightDynamicsData {
+    pub sim_on_ground: f64,
+    pub vertical_speed: f64,             // feet per minute
+    pub g_force: f64,
+    pub touchdown_normal_velocity: f64,  // feet per second, latched by the sim
+    pub bank: f64,                       // degrees
+    pub pitch: f64,                      // degrees
+    pub ground_velocity: f64,            // knots
+    pub runway_selected: f64,
+    pub runway_relative_z: f64,          // feet from the runway threshold along its axis
+    pub altitude_above_ground: f64,      // feet
+    pub accel_body_y: f64,               // feet per second squared
+}
+
+/// Qualitative landing rating derived from the touchdown rate and load factor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TouchdownRating {
+    Butter,
+    Smooth,
+    Acceptable,
+    Firm,
+    Hard,
+}
+
+impl TouchdownRating {
+    /// Rates a landing. The worse of the sink rate and the G load wins.
+    pub fn from_rate(rate_fpm: f64, g_force: f64) -> Self {
+        let by_rate = match rate_fpm.abs() {
+            r if r <= 60.0 => TouchdownRating::Butter,
+            r if r <= 180.0 => TouchdownRating::Smooth,
+            r if r <= 300.0 => TouchdownRating::Acceptable,
+            r if r <= 600.0 => TouchdownRating::Firm,
+            _ => TouchdownRating::Hard,
+        };
+        let by_g = match g_force {
+            g if g <= 1.3 => TouchdownRating::Butter,
+            g if g <= 1.5 => TouchdownRating::Acceptable,
+            g if g <= 1.8 => TouchdownRating::Firm,
+            _ => TouchdownRating::Hard,
+        };
+        by_rate.max(by_g)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TouchdownRating::Butter => "butter",
+            TouchdownRating::Smooth => "smooth",
+            TouchdownRating::Acceptable => "acceptable",
+            TouchdownRating::Firm => "firm",
+            TouchdownRating::Hard => "hard",
+        }
+    }
+}
+
+/// Everything we know about a single touchdown.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TouchdownReport {
+    pub vertical_speed_fpm: f64,
+    pub touchdown_rate_fpm: f64,
+    pub g_force: f64,
+    pub bank_deg: f64,
+    pub pitch_deg: f64,
+    pub ground_speed_kts: f64,
+    pub distance_from_threshold_ft: Option<f64>,
+    pub bounces: u32,
+    pub rating: TouchdownRating,
+}
+
+/// User toggles for the post-landing announcements.
+#[derive(Clone, Debug)]
+pub struct TouchdownSettings {
+    pub applause_enabled: bool,
+    pub welcome_enabled: bool,
+    /// Applause only plays for landings rated at least this well.
+    pub applause_max_rating: TouchdownRating,
+}
+
+impl Default for TouchdownSettings {
+    fn default() -> Self {
+        TouchdownSettings {
+            applause_enabled: false,
+            welcome_enabled: true,
+            applause_max_rating: TouchdownRating::Smooth,
+        }
+    }
+}
+
+// Ground contacts closer together than this are treated as bounces of the same landing
+const BOUNCE_WINDOW: Duration = Duration::from_secs(10);
+// How long after the first contact we keep tracking the peak G load
+const G_PEAK_WINDOW: Duration = Duration::from_millis(1500);
+
+/// Watches the SIM ON GROUND edge and produces a report once per landing.
+pub struct TouchdownMonitor {
+    was_on_ground: Option<bool>,
+    last_airborne: FlightDynamicsData,
+    pending: Option<TouchdownReport>,
+    touchdown_time: Option<Instant>,
+}
+
+impl TouchdownMonitor {
+    pub fn new() -> Self {
+        TouchdownMonitor {
+            was_on_ground: None,
+            last_airborne: FlightDynamicsData::default(),
+            pending: None,
+            touchdown_time: None,
+        }
+    }
+
+    /// Feeds one frame of dynamics. Returns the finished report once the bounce
+    /// window after the first contact has closed, so bounces are counted in it.
+    pub fn update(&mut self, sample: &FlightDynamicsData) -> Option<TouchdownReport> {
+        self.update_at(sample, Instant::now())
+    }
+
+    fn update_at(&mut self, sample: &FlightDynamicsData, now: Instant) -> Option<TouchdownReport> {
+        let on_ground = sample.sim_on_ground != 0.0;
+        let was_on_ground = self.was_on_ground.replace(on_ground);
+
+        if !on_ground {
+            self.last_airborne = *sample;
+        }
+
+        // Only an airborne -> ground edge counts; the first sample after connecting never does
+        if was_on_ground == Some(false) && on_ground {
+            if let Some(report) = self.pending.as_mut() {
+                report.bounces += 1;
+                println!("[DEBUG] Touchdown bounce detected ({} so far)", report.bounces);
+            } else {
+                let last = self.last_airborne;
+                let touchdown_rate_fpm = -sample.touchdown_normal_velocity.abs() * 60.0;
+                let distance_from_threshold_ft = if sample.runway_selected != 0.0 {
+                    Some(sample.runway_relative_z)
+                } else {
+                    None
+                };
+                self.pending = Some(TouchdownReport {
+                    vertical_speed_fpm: last.vertical_speed,
+                    touchdown_rate_fpm,
+                    g_force: sample.g_force.max(last.g_force),
+                    bank_deg: sample.bank,
+                    pitch_deg: sample.pitch,
+                    ground_speed_kts: sample.ground_velocity,
+                    distance_from_threshold_ft,
+                    bounces: 0,
+                    rating: TouchdownRating::Butter,
+                });
+                self.touchdown_time = Some(now);
+            }
+        }
+
+        let elapsed = now.duration_since(self.touchdown_time?);
+        let report = self.pending.as_mut()?;
+        if on_ground && elapsed < G_PEAK_WINDOW {
+            report.g_force = report.g_force.max(sample.g_force);
+        }
+        if elapsed < BOUNCE_WINDOW {
+            return None;
+        }
+        let mut report = self.pending.take()?;
+        report.rating = TouchdownRating::from_rate(report.touchdown_rate_fpm, report.g_force);
+        Some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(on_ground: bool, rate_fps: f64, g_force: f64) -> FlightDynamicsData {
+        FlightDynamicsData {
+            sim_on_ground: if on_ground { 1.0 } else { 0.0 },
+            vertical_speed: -rate_fps * 60.0,
+            touchdown_normal_velocity: rate_fps,
+            g_force,
+            ..FlightDynamicsData::default()
+        }
+    }
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn rating_takes_the_worse_of_rate_and_g() {
+        assert_eq!(TouchdownRating::from_rate(-50.0, 1.1), TouchdownRating::Butter);
+        assert_eq!(TouchdownRating::from_rate(-150.0, 1.1), TouchdownRating::Smooth);
+        assert_eq!(TouchdownRating::from_rate(-250.0, 1.2), TouchdownRating::Acceptable);
+        assert_eq!(TouchdownRating::from_rate(-500.0, 1.2), TouchdownRating::Firm);
+        assert_eq!(TouchdownRating::from_rate(-700.0, 1.0), TouchdownRating::Hard);
+        assert_eq!(TouchdownRating::from_rate(-50.0, 1.6), TouchdownRating::Firm);
+        assert_eq!(TouchdownRating::from_rate(-50.0, 2.0), TouchdownRating::Hard);
+        // Boundaries belong to the better rating
+        assert_eq!(TouchdownRating::from_rate(60.0, 1.3), TouchdownRating::Butter);
+        assert_eq!(TouchdownRating::from_rate(-180.0, 1.0), TouchdownRating::Smooth);
+    }
+
+    #[test]
+    fn report_waits_for_the_bounce_window() {
+        let start = Instant::now();
+        let mut monitor = TouchdownMonitor::new();
+        assert!(monitor.update_at(&sample(false, 2.0, 1.0), start).is_none());
+        assert!(monitor.update_at(&sample(true, 2.0, 1.2), start + ms(100)).is_none());
+        assert!(monitor.update_at(&sample(true, 2.0, 1.4), start + ms(1000)).is_none());
+        // G after the peak window no longer counts
+        assert!(monitor.update_at(&sample(true, 2.0, 2.5), start + ms(2000)).is_none());
+
+        let report = monitor.update_at(&sample(true, 0.0, 1.0), start + ms(10_200)).unwrap();
+        assert_eq!(report.bounces, 0);
+        assert_eq!(report.touchdown_rate_fpm, -120.0);
+        assert_eq!(report.g_force, 1.4);
+        assert_eq!(report.rating, TouchdownRating::Acceptable);
+        assert!(monitor.update_at(&sample(true, 0.0, 1.0), start + ms(11_000)).is_none());
+    }
+
+    #[test]
+    fn bounces_after_the_g_window_are_counted() {
+        let start = Instant::now();
+        let mut monitor = TouchdownMonitor::new();
+        monitor.update_at(&sample(false, 3.0, 1.0), start);
+        monitor.update_at(&sample(true, 3.0, 1.3), start + ms(100));
+        for (at, on_ground) in [(3000, false), (4000, true), (5000, false), (6000, true)] {
+            assert!(monitor.update_at(&sample(on_ground, 1.0, 1.1), start + ms(at)).is_none());
+        }
+        let report = monitor.update_at(&sample(true, 0.0, 1.0), start + ms(10_100)).unwrap();
+        assert_eq!(report.bounces, 2);
+        // The first contact is the one rated
+        assert_eq!(report.touchdown_rate_fpm, -180.0);
+    }
+}
@@ -0,0 +1,322 @@
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::sync::{ Arc, Mutex };
+use std::time::{ Duration, Instant };
+use tauri::{ Manager, State, Window };
+
+use crate::logbook::unix_now;
+
+const FEET_TO_METERS: f64 = 0.3048;
+
+/// One sampled position along the flown track.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackPoint {
+    pub timestamp: u64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_ft: f64,
+    pub ground_speed_kts: f64,
+    pub indicated_airspeed_kts: f64,
+    pub vertical_speed_fpm: f64,
+}
+
+/// A named event pinned to the position where it happened.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackWaypoint {
+    pub timestamp: u64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_ft: f64,
+    pub kind: String,  // "phase" or "announcement"
+    pub name: String,
+}
+
+/// Collects track points at a configurable interval plus annotated waypoints.
+pub struct TrackRecorder {
+    pub points: Vec<TrackPoint>,
+    pub waypoints: Vec<TrackWaypoint>,
+    sample_interval: Duration,
+    last_sample: Option<Instant>,
+    last_point: Option<TrackPoint>,
+}
+
+impl TrackRecorder {
+    pub fn new() -> Self {
+        TrackRecorder {
+            points: Vec::new(),
+            waypoints: Vec::new(),
+            sample_interval: Duration::from_secs(5),
+            last_sample: None,
+            last_point: None,
+        }
+    }
+
+    /// Drops the previous session's track, keeping the sample interval.
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.waypoints.clear();
+        self.last_sample = None;
+        self.last_point = None;
+    }
+
+    pub fn set_sample_interval(&mut self, interval: Duration) {
+        self.sample_interval = interval;
+    }
+
+    /// Offers a position; it is kept if the sample interval has elapsed.
+    pub fn record_position(&mut self, point: TrackPoint) {
+        let now = Instant::now();
+        let due = self.last_sample
+            .map(|last| now.duration_since(last) >= self.sample_interval)
+            .unwrap_or(true);
+        if due {
+            self.last_sample = Some(now);
+            self.points.push(point.clone());
+        }
+        self.last_point = Some(point);
+    }
+
+    /// Adds a waypoint at the latest known position. Ignored until a position is known.
+    pub fn add_waypoint(&mut self, kind: &str, name: &str) {
+        if let Some(point) = &self.last_point {
+            self.waypoints.push(TrackWaypoint {
+                timestamp: unix_now(),
+                latitude: point.latitude,
+                longitude: point.longitude,
+                altitude_ft: point.altitude_ft,
+                kind: kind.to_string(),
+                name: name.to_string(),
+            });
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        // Merge points and waypoints by time so the events sit where they happened
+        let mut rows: Vec<(u64, String)> = Vec::new();
+        for p in &self.points {
+            rows.push((p.timestamp, format!(
+                "{},{:.6},{:.6},{:.0},{:.0},{:.0},{:.0},",
+                p.timestamp, p.latitude, p.longitude, p.altitude_ft,
+                p.ground_speed_kts, p.indicated_airspeed_kts, p.vertical_speed_fpm
+            )));
+        }
+        for w in &self.waypoints {
+            rows.push((w.timestamp, format!(
+                "{},{:.6},{:.6},{:.0},,,,{}",
+                w.timestamp, w.latitude, w.longitude, w.altitude_ft, csv_field(&format!("{}:{}", w.kind, w.name))
+            )));
+        }
+        rows.sort_by_key(|(timestamp, _)| *timestamp);
+
+        let mut csv = String::from("timestamp,latitude,longitude,altitude_ft,ground_speed_kts,ias_kts,vertical_speed_fpm,event\n");
+        for (_, row) in rows {
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn to_gpx(&self) -> String {
+        let mut gpx = String::new();
+        gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        gpx.push_str("<gpx version=\"1.1\" creator=\"simpa\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+        for w in &self.waypoints {
+            let _ = writeln!(gpx,
+                "  <wpt lat=\"{:.6}\" lon=\"{:.6}\"><ele>{:.1}</ele><time>{}</time><name>{}</name><type>{}</type></wpt>",
+                w.latitude, w.longitude, w.altitude_ft * FEET_TO_METERS,
+                iso8601(w.timestamp), xml_escape(&w.name), xml_escape(&w.kind)
+            );
+        }
+        gpx.push_str("  <trk>\n    <name>simpa flight</name>\n    <trkseg>\n");
+        for p in &self.points {
+            let _ = writeln!(gpx,
+                "      <trkpt lat=\"{:.6}\" lon=\"{:.6}\"><ele>{:.1}</ele><time>{}</time></trkpt>",
+                p.latitude, p.longitude, p.altitude_ft * FEET_TO_METERS, iso8601(p.timestamp)
+            );
+        }
+        gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+        gpx
+    }
+
+    pub fn to_kml(&self) -> String {
+        let mut kml = String::new();
+        kml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        kml.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n  <name>simpa flight</name>\n");
+        kml.push_str("  <Placemark>\n    <name>Track</name>\n    <LineString>\n      <altitudeMode>absolute</altitudeMode>\n      <coordinates>\n");
+        for p in &self.points {
+            let _ = writeln!(kml, "        {:.6},{:.6},{:.1}",
+                p.longitude, p.latitude, p.altitude_ft * FEET_TO_METERS);
+        }
+        kml.push_str("      </coordinates>\n    </LineString>\n  </Placemark>\n");
+        for w in &self.waypoints {
+            let _ = writeln!(kml,
+                "  <Placemark>\n    <name>{}</name>\n    <description>{} at {}</description>\n    <TimeStamp><when>{}</when></TimeStamp>\n    <Point><altitudeMode>absolute</altitudeMode><coordinates>{:.6},{:.6},{:.1}</coordinates></Point>\n  </Placemark>",
+                xml_escape(&w.name), xml_escape(&w.kind), iso8601(w.timestamp), iso8601(w.timestamp),
+                w.longitude, w.latitude, w.altitude_ft * FEET_TO_METERS
+            );
+        }
+        kml.push_str("</Document>\n</kml>\n");
+        kml
+    }
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Formats Unix seconds as an ISO 8601 UTC timestamp (e.g. `2024-05-01T12:30:00Z`).
+pub fn iso8601(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let secs = timestamp % 86400;
+
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+/// Holds the track of the current (or most recent) session.
+pub struct TrackState {
+    pub recorder: Mutex<TrackRecorder>,
+}
+
+impl TrackState {
+    pub fn new() -> Self {
+        TrackState {
+            recorder: Mutex::new(TrackRecorder::new()),
+        }
+    }
+}
+
+/// Pins an announcement to the track, if a position is known.
+/// Usable from helper threads that only hold a `Window`.
+pub fn record_announcement(window: &Window, kind: &str) {
+    let track = window.state::<Arc<TrackState>>();
+    track.recorder.lock().unwrap().add_waypoint("announcement", kind);
+}
+
+/// Sets how often track points are kept, in seconds (minimum 1).
+#[tauri::command]
+pub fn set_track_sample_interval(state: State<Arc<TrackState>>, seconds: u64) {
+    let seconds = seconds.max(1);
+    state.recorder.lock().unwrap().set_sample_interval(Duration::from_secs(seconds));
+    println!("Track sample interval set to {}s", seconds);
+}
+
+/// Exports the session track as `csv`, `gpx` or `kml` to `path`.
+#[tauri::command]
+pub fn export_track(
+    state: State<Arc<TrackState>>,
+    format: String,
+    path: String
+) -> Result<(), String> {
+    let contents = {
+        let recorder = state.recorder.lock().unwrap();
+        if recorder.points.is_empty() {
+            return Err("No track has been recorded yet".to_string());
+        }
+        match format.to_lowercase().as_str() {
+            "csv" => recorder.to_csv(),
+            "gpx" => recorder.to_gpx(),
+            "kml" => recorder.to_kml(),
+            other => return Err(format!("Unsupported track format: {}", other)),
+        }
+    };
+
+    fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    println!("Track exported as {} to {}", format, path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorder() -> TrackRecorder {
+        let mut recorder = TrackRecorder::new();
+        let point = |timestamp: u64, latitude: f64| TrackPoint {
+            timestamp,
+            latitude,
+            longitude: -0.45,
+            altitude_ft: 1000.0,
+            ground_speed_kts: 160.0,
+            indicated_airspeed_kts: 150.0,
+            vertical_speed_fpm: 1500.0,
+        };
+        recorder.points.push(point(1_714_566_600, 51.47));
+        recorder.points.push(point(1_714_566_660, 51.50));
+        recorder.waypoints.push(TrackWaypoint {
+            timestamp: 1_714_566_630,
+            latitude: 51.48,
+            longitude: -0.45,
+            altitude_ft: 1000.0,
+            kind: "announcement".to_string(),
+            name: "Ladies & \"gentlemen\", welcome".to_string(),
+        });
+        recorder
+    }
+
+    #[test]
+    fn csv_sorts_rows_and_quotes_events() {
+        let csv = recorder().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("timestamp,"));
+        assert_eq!(lines[1], "1714566600,51.470000,-0.450000,1000,160,150,1500,");
+        assert_eq!(lines[2], "1714566630,51.480000,-0.450000,1000,,,,\"announcement:Ladies & \"\"gentlemen\"\", welcome\"");
+        assert!(lines[3].starts_with("1714566660,"));
+        assert_eq!(csv_field("phase:cruise"), "phase:cruise");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn gpx_has_escaped_waypoints_and_the_track() {
+        let gpx = recorder().to_gpx();
+        assert!(gpx.contains("<wpt lat=\"51.480000\" lon=\"-0.450000\"><ele>304.8</ele><time>2024-05-01T12:30:30Z</time>"));
+        assert!(gpx.contains("<name>Ladies &amp; &quot;gentlemen&quot;, welcome</name><type>announcement</type>"));
+        assert_eq!(gpx.matches("<trkpt ").count(), 2);
+        assert!(gpx.contains("<time>2024-05-01T12:30:00Z</time></trkpt>"));
+        assert!(gpx.trim_end().ends_with("</gpx>"));
+    }
+
+    #[test]
+    fn kml_lists_longitude_first() {
+        let kml = recorder().to_kml();
+        assert!(kml.contains("        -0.450000,51.470000,304.8\n        -0.450000,51.500000,304.8\n"));
+        assert!(kml.contains("<name>Ladies &amp; &quot;gentlemen&quot;, welcome</name>"));
+        assert!(kml.contains("<coordinates>-0.450000,51.480000,304.8</coordinates>"));
+    }
+
+    #[test]
+    fn iso8601_handles_leap_days() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601(951_782_400), "2000-02-29T00:00:00Z");
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+use std::time::{ Duration, Instant };
+
+use crate::flight_phase::FlightPhase;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TurbulenceLevel {
+    Light,
+    Moderate,
+    Severe,
+}
+
+impl TurbulenceLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TurbulenceLevel::Light => "light",
+            TurbulenceLevel::Moderate => "moderate",
+            TurbulenceLevel::Severe => "severe",
+        }
+    }
+
+    /// Classifies the standard deviation of vertical load (in G).
+    fn from_deviation(deviation: f64) -> Option<Self> {
+        match deviation {
+            d if d >= 0.30 => Some(TurbulenceLevel::Severe),
+            d if d >= 0.15 => Some(TurbulenceLevel::Moderate),
+            d if d >= 0.08 => Some(TurbulenceLevel::Light),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TurbulenceEvent {
+    Started(TurbulenceLevel),
+    Intensified(TurbulenceLevel),
+    Ended,
+}
+
+const FEET_PER_SECOND_SQUARED_PER_G: f64 = 32.174;
+// Rolling window the variance is computed over
+const WINDOW: Duration = Duration::from_secs(5);
+// Turbulence has to persist this long before the crew reacts
+const SUSTAIN: Duration = Duration::from_secs(8);
+// Smooth air has to persist this long before we call it over
+const CALM: Duration = Duration::from_secs(30);
+// A bump this recent still explains the captain switching the seatbelt sign on
+const RECENT: Duration = Duration::from_secs(60);
+
+/// Detects sustained turbulence from the variance of the vertical load factor.
+pub struct TurbulenceDetector {
+    samples: VecDeque<(Instant, f64, f64)>,
+    above_since: Option<Instant>,
+    calm_since: Option<Instant>,
+    active: Option<TurbulenceLevel>,
+    last_rough: Option<Instant>,
+}
+
+impl TurbulenceDetector {
+    pub fn new() -> Self {
+        TurbulenceDetector {
+            samples: VecDeque::new(),
+            above_since: None,
+            calm_since: None,
+            active: None,
+            last_rough: None,
+        }
+    }
+
+    pub fn active(&self) -> Option<TurbulenceLevel> {
+        self.active
+    }
+
+    /// Whether the air is rough now or was within the last minute, sustained or not.
+    pub fn recently_rough(&self) -> bool {
+        self.recently_rough_at(Instant::now())
+    }
+
+    fn recently_rough_at(&self, now: Instant) -> bool {
+        self.active.is_some()
+            || self.last_rough.is_some_and(|t| now.duration_since(t) < RECENT)
+    }
+
+    /// Feeds one frame. `accel_body_y` is in ft/s², `g_force` in G.
+    pub fn update(&mut self, on_ground: bool, accel_body_y: f64, g_force: f64) -> Option<TurbulenceEvent> {
+        self.update_at(on_ground, accel_body_y, g_force, Instant::now())
+    }
+
+    fn update_at(&mut self, on_ground: bool, accel_body_y: f64, g_force: f64, now: Instant) -> Option<TurbulenceEvent> {
+        if on_ground {
+            // Runway bumps aren't turbulence
+            self.samples.clear();
+            self.above_since = None;
+            return self.active.take().map(|_| TurbulenceEvent::Ended);
+        }
+
+        self.samples.push_back((now, accel_body_y / FEET_PER_SECOND_SQUARED_PER_G, g_force));
+        while let Some((t, _, _)) = self.samples.front() {
+            if now.duration_since(*t) > WINDOW {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.samples.len() < 10 {
+            return None;
+        }
+
+        // Use whichever reading moves more; some aircraft don't drive G FORCE smoothly
+        let deviation = std_dev(self.samples.iter().map(|(_, accel, _)| *accel))
+            .max(std_dev(self.samples.iter().map(|(_, _, g)| *g)));
+        let level = TurbulenceLevel::from_deviation(deviation);
+
+        match level {
+            Some(level) => {
+                self.calm_since = None;
+                self.last_rough = Some(now);
+                let since = *self.above_since.get_or_insert(now);
+                if now.duration_since(since) < SUSTAIN {
+                    return None;
+                }
+                match self.active {
+                    None => {
+                        self.active = Some(level);
+                        Some(TurbulenceEvent::Started(level))
+                    },
+                    Some(current) if level > current => {
+                        self.active = Some(level);
+                        Some(TurbulenceEvent::Intensified(level))
+                    },
+                    _ => None,
+                }
+            },
+            None => {
+                self.above_since = None;
+                self.active?;
+                let since = *self.calm_since.get_or_insert(now);
+                if now.duration_since(since) >= CALM {
+                    self.active = None;
+                    self.calm_since = None;
+                    return Some(TurbulenceEvent::Ended);
+                }
+                None
+            },
+        }
+    }
+}
+
+fn std_dev(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    let count = values.clone().count() as f64;
+    if count == 0.0 {
+        return 0.0;
+    }
+    let mean = values.clone().sum::<f64>() / count;
+    (values.map(|v| (v - mean).powi(2)).sum::<f64>() / count).sqrt()
+}
+
+/// Why the seatbelt sign was switched on, judged from the phase, vertical speed and
+/// whether the detector has felt rough air lately.
+pub fn seatbelt_on_reason(phase: FlightPhase, vertical_speed_fpm: f64, rough_air: bool) -> &'static str {
+    match phase {
+        FlightPhase::Preflight | FlightPhase::TaxiOut | FlightPhase::Takeoff => "departure",
+        FlightPhase::Landing | FlightPhase::TaxiIn | FlightPhase::Arrived => "arrival",
+        FlightPhase::Descent | FlightPhase::Approach => "descent",
+        FlightPhase::Cruise if vertical_speed_fpm <= -500.0 => "descent",
+        // Level at altitude in bumps: the captain is reacting to rough air
+        FlightPhase::Cruise if rough_air => "turbulence",
+        FlightPhase::Cruise => "cruise",
+        FlightPhase::Climb => "climb",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `seconds` of 20 Hz samples bouncing by `amplitude` G; returns the events.
+    fn fly(detector: &mut TurbulenceDetector, now: &mut Instant, seconds: u64, amplitude: f64) -> Vec<TurbulenceEvent> {
+        let mut events = Vec::new();
+        for i in 0..seconds * 20 {
+            *now += Duration::from_millis(50);
+            let g_force = 1.0 + if i % 2 == 0 { amplitude } else { -amplitude };
+            events.extend(detector.update_at(false, 0.0, g_force, *now));
+        }
+        events
+    }
+
+    #[test]
+    fn bumps_must_be_sustained() {
+        let mut detector = TurbulenceDetector::new();
+        let mut now = Instant::now();
+        // A short jolt is felt but not reacted to
+        assert!(fly(&mut detector, &mut now, 2, 0.2).is_empty());
+        assert!(detector.active().is_none());
+        assert!(detector.recently_rough_at(now));
+        assert!(fly(&mut detector, &mut now, 10, 0.0).is_empty());
+
+        assert_eq!(fly(&mut detector, &mut now, 15, 0.1), [TurbulenceEvent::Started(TurbulenceLevel::Light)]);
+        // Worse air steps up through each level as the window fills
+        assert_eq!(
+            fly(&mut detector, &mut now, 10, 0.4),
+            [TurbulenceEvent::Intensified(TurbulenceLevel::Moderate), TurbulenceEvent::Intensified(TurbulenceLevel::Severe)]
+        );
+        // Easing off doesn't downgrade it
+        assert!(fly(&mut detector, &mut now, 10, 0.1).is_empty());
+        assert_eq!(detector.active(), Some(TurbulenceLevel::Severe));
+    }
+
+    #[test]
+    fn ends_after_calm_air_and_decays() {
+        let mut detector = TurbulenceDetector::new();
+        let mut now = Instant::now();
+        fly(&mut detector, &mut now, 15, 0.2);
+        assert_eq!(detector.active(), Some(TurbulenceLevel::Moderate));
+        assert!(fly(&mut detector, &mut now, 30, 0.0).is_empty());
+        assert_eq!(fly(&mut detector, &mut now, 10, 0.0), [TurbulenceEvent::Ended]);
+        // Still counts as rough air for the seatbelt sign for a minute after the last bump
+        assert!(detector.recently_rough_at(now));
+        assert_eq!(seatbelt_on_reason(FlightPhase::Cruise, 0.0, detector.recently_rough_at(now)), "turbulence");
+        now += RECENT;
+        assert!(!detector.recently_rough_at(now));
+        assert_eq!(seatbelt_on_reason(FlightPhase::Cruise, 0.0, detector.recently_rough_at(now)), "cruise");
+    }
+
+    #[test]
+    fn landing_ends_it_at_once() {
+        let mut detector = TurbulenceDetector::new();
+        let mut now = Instant::now();
+        fly(&mut detector, &mut now, 15, 0.2);
+        assert_eq!(detector.update_at(true, 0.0, 1.5, now), Some(TurbulenceEvent::Ended));
+        assert_eq!(detector.update_at(true, 0.0, 1.5, now), None);
+    }
+
+    #[test]
+    fn seatbelt_reasons() {
+        assert_eq!(seatbelt_on_reason(FlightPhase::TaxiOut, 0.0, true), "departure");
+        assert_eq!(seatbelt_on_reason(FlightPhase::Cruise, -1200.0, true), "descent");
+        assert_eq!(seatbelt_on_reason(FlightPhase::Climb, 2000.0, true), "climb");
+        assert_eq!(seatbelt_on_reason(FlightPhase::TaxiIn, 0.0, false), "arrival");
+    }
+}
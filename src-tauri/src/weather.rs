@@ -0,0 +1,174 @@
+use serde::Serialize;
+use serde_json::{ json, Value };
+use std::collections::HashMap;
+
+/// Standard lapse rate, used to estimate the temperature on the ground from aloft.
+const LAPSE_RATE_C_PER_1000FT: f64 = 1.98;
+/// Below this visibility without precipitation it's foggy.
+const FOG_VISIBILITY_M: f64 = 1000.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precipitation {
+    #[default]
+    None,
+    Rain,
+    Snow,
+}
+
+impl Precipitation {
+    /// From the `AMBIENT PRECIP STATE` mask: 2 none, 4 rain, 8 snow.
+    pub fn from_state(mask: u32) -> Self {
+        if mask & 8 != 0 {
+            Precipitation::Snow
+        } else if mask & 4 != 0 {
+            Precipitation::Rain
+        } else {
+            Precipitation::None
+        }
+    }
+}
+
+/// Ambient weather around the aircraft.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherSample {
+    pub temperature_c: f64,
+    pub wind_speed_kts: f64,
+    pub wind_direction_deg: f64,
+    pub precipitation: Precipitation,
+    pub visibility_m: f64,
+}
+
+impl WeatherSample {
+    /// The same weather with the temperature brought down to the ground, for readings
+    /// taken `height_ft` above it.
+    pub fn at_surface(&self, height_ft: f64) -> Self {
+        WeatherSample {
+            temperature_c: self.temperature_c + height_ft.max(0.0) / 1000.0 * LAPSE_RATE_C_PER_1000FT,
+            ..*self
+        }
+    }
+
+    /// Reads the wind, visibility, precipitation and temperature from a METAR.
+    /// Returns None when it has no temperature group.
+    pub fn from_metar(metar: &str) -> Option<Self> {
+        let mut weather = WeatherSample { visibility_m: 10000.0, ..WeatherSample::default() };
+        let mut temperature = None;
+        // The body sits between the report time and the remarks or trend
+        let is_report_time = |group: &str| group.len() == 7 && group.ends_with('Z')
+            && group[..6].chars().all(|c| c.is_ascii_digit());
+        let body = metar.split_whitespace()
+            .skip_while(|group| !is_report_time(group))
+            .skip(1)
+            .take_while(|group| !["RMK", "TEMPO", "BECMG", "NOSIG"].contains(group));
+        for group in body {
+            if let Some(wind) = group.strip_suffix("KT").or_else(|| group.strip_suffix("MPS")) {
+                let (direction, speed) = wind.split_at(wind.len().min(3));
+                let speed = speed.split('G').next().and_then(|speed| speed.parse::<f64>().ok());
+                if let Some(speed) = speed {
+                    weather.wind_direction_deg = direction.parse().unwrap_or(0.0);
+                    weather.wind_speed_kts = if group.ends_with("MPS") { speed * 1.944 } else { speed };
+                }
+            } else if group == "CAVOK" {
+                weather.visibility_m = 10000.0;
+            } else if group.len() == 4 && group.chars().all(|c| c.is_ascii_digit()) {
+                weather.visibility_m = group.parse().unwrap_or(10000.0);
+            } else if let Some(miles) = group.strip_suffix("SM") {
+                let miles = match miles.split_once('/') {
+                    Some((numerator, denominator)) => numerator.trim_start_matches('M').parse::<f64>().ok()
+                        .zip(denominator.parse::<f64>().ok())
+                        .map(|(numerator, denominator)| numerator / denominator),
+                    None => miles.parse::<f64>().ok(),
+                };
+                if let Some(miles) = miles {
+                    weather.visibility_m = miles * 1609.0;
+                }
+            } else if let Some((air, dew)) = group.split_once('/') {
+                let celsius = |value: &str| match value.strip_prefix('M') {
+                    Some(below) => below.parse::<f64>().ok().map(|c| -c),
+                    None => value.parse::<f64>().ok(),
+                };
+                if dew.len() <= 3 && celsius(dew).is_some() {
+                    temperature = celsius(air);
+                }
+            } else {
+                // Present weather like -RA, +SHSN or TSRA; showers in the vicinity don't count
+                let phenomena = group.trim_start_matches(['-', '+']);
+                if !phenomena.starts_with("VC") {
+                    if ["SN", "SG", "PL", "GS"].iter().any(|code| phenomena.contains(code)) {
+                        weather.precipitation = Precipitation::Snow;
+                    } else if ["RA", "DZ"].iter().any(|code| phenomena.contains(code))
+                        && weather.precipitation == Precipitation::None {
+                        weather.precipitation = Precipitation::Rain;
+                    }
+                }
+            }
+        }
+        weather.temperature_c = temperature?;
+        Some(weather)
+    }
+
+    /// A short description, also the `weather/<conditions>` clip key. Precipitation
+    /// strength is judged by the visibility it leaves.
+    pub fn conditions(&self) -> &'static str {
+        let strength = if self.visibility_m < 1500.0 { 2 } else if self.visibility_m < 5000.0 { 1 } else { 0 };
+        match (self.precipitation, strength) {
+            (Precipitation::Rain, 0) => "light_rain",
+            (Precipitation::Rain, 1) => "rain",
+            (Precipitation::Rain, _) => "heavy_rain",
+            (Precipitation::Snow, 0) => "light_snow",
+            (Precipitation::Snow, 1) => "snow",
+            (Precipitation::Snow, _) => "heavy_snow",
+            (Precipitation::None, _) if self.visibility_m < FOG_VISIBILITY_M => "fog",
+            (Precipitation::None, _) => "clear",
+        }
+    }
+
+    /// The sample with its conditions, for event payloads.
+    pub fn report(&self) -> Value {
+        let mut report = json!(self);
+        report["conditions"] = json!(self.conditions());
+        report
+    }
+
+    /// Template variables, each name starting with `prefix`.
+    pub fn template_variables(&self, prefix: &str) -> HashMap<String, String> {
+        [
+            ("temperature", format!("{:.0}", self.temperature_c)),
+            ("temperature_f", format!("{:.0}", self.temperature_c * 9.0 / 5.0 + 32.0)),
+            ("wind_speed", format!("{:.0}", self.wind_speed_kts)),
+            ("wind_direction", format!("{:03.0}", self.wind_direction_deg.rem_euclid(360.0))),
+            ("visibility_km", format!("{:.0}", self.visibility_m / 1000.0)),
+            ("weather", format!("weather/{}", self.conditions())),
+        ]
+        .into_iter()
+        .map(|(name, value)| (format!("{}{}", prefix, name), value))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_metar() {
+        let weather = WeatherSample::from_metar("EGLL 181250Z 24015G25KT 4000 -RA BKN012 M02/M04 Q1003").unwrap();
+        assert_eq!(weather.wind_direction_deg, 240.0);
+        assert_eq!(weather.wind_speed_kts, 15.0);
+        assert_eq!(weather.visibility_m, 4000.0);
+        assert_eq!(weather.precipitation, Precipitation::Rain);
+        assert_eq!(weather.temperature_c, -2.0);
+        assert_eq!(weather.conditions(), "rain");
+    }
+
+    #[test]
+    fn reads_us_visibility_and_skips_vicinity_showers() {
+        let weather = WeatherSample::from_metar("KSNA 181253Z VRB03KT 1/2SM FG VCSH OVC002 08/07 A2992 RMK AO2 RAE10").unwrap();
+        assert_eq!(weather.wind_speed_kts, 3.0);
+        assert!((weather.visibility_m - 804.5).abs() < 1.0);
+        assert_eq!(weather.precipitation, Precipitation::None);
+        assert_eq!(weather.conditions(), "fog");
+    }
+}
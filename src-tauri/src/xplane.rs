@@ -0,0 +1,808 @@
+use serde::{ Deserialize, Serialize };
+use std::cell::RefCell;
+use std::collections::{ HashMap, VecDeque };
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::time::{ Duration, Instant };
+use tauri::State;
+
+use crate::sim_connection::{ SimConnection, SimMessage };
+use crate::simconnect_data::SimConnectState;
+
+const FEET_PER_METER: f64 = 3.28084;
+const KNOTS_PER_METER_PER_SECOND: f64 = 1.94384;
+const FEET_PER_SECOND_SQUARED_PER_G: f64 = 32.174;
+/// Longest dataref path the RREF and DREF packets have room for
+const RREF_PATH_LEN: usize = 400;
+const DREF_PATH_LEN: usize = 500;
+/// String variables are read this many characters deep
+const MAX_TEXT_LEN: usize = 64;
+/// How often system events are checked for, in updates per second
+const EVENT_FREQUENCY: i32 = 5;
+/// X-Plane counts as gone after this long without a packet
+const SILENCE_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct XPlaneSettings {
+    /// Collect from X-Plane instead of SimConnect
+    pub enabled: bool,
+    /// Where X-Plane listens for UDP, port 49000 unless changed in its network settings
+    pub address: String,
+}
+
+impl Default for XPlaneSettings {
+    fn default() -> Self {
+        XPlaneSettings {
+            enabled: false,
+            address: "127.0.0.1:49000".to_string(),
+        }
+    }
+}
+
+/// Asks X-Plane to send `dataref` as `index` `frequency` times a second; 0 stops it.
+pub fn rref_packet(frequency: i32, index: i32, dataref: &str) -> Vec<u8> {
+    let mut packet = b"RREF\0".to_vec();
+    packet.extend_from_slice(&frequency.to_le_bytes());
+    packet.extend_from_slice(&index.to_le_bytes());
+    packet.extend(padded(dataref, RREF_PATH_LEN));
+    packet
+}
+
+/// The `(index, value)` pairs of an RREF reply.
+pub fn parse_rref(packet: &[u8]) -> Option<Vec<(i32, f32)>> {
+    if packet.len() < 5 || &packet[..4] != b"RREF" {
+        return None;
+    }
+    Some(packet[5..].chunks_exact(8)
+        .map(|pair| (
+            i32::from_le_bytes([pair[0], pair[1], pair[2], pair[3]]),
+            f32::from_le_bytes([pair[4], pair[5], pair[6], pair[7]]),
+        ))
+        .collect())
+}
+
+/// Sets a writable dataref.
+pub fn dref_packet(dataref: &str, value: f32) -> Vec<u8> {
+    let mut packet = b"DREF\0".to_vec();
+    packet.extend_from_slice(&value.to_le_bytes());
+    packet.extend(padded(dataref, DREF_PATH_LEN));
+    packet
+}
+
+/// Runs a command, as if its key or button were pressed.
+pub fn cmnd_packet(command: &str) -> Vec<u8> {
+    let mut packet = b"CMND\0".to_vec();
+    packet.extend_from_slice(command.as_bytes());
+    packet
+}
+
+fn padded(text: &str, len: usize) -> Vec<u8> {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.resize(len, 0);
+    // Always null terminated
+    bytes[len - 1] = 0;
+    bytes
+}
+
+/// Where the value of a SimConnect variable comes from in X-Plane.
+enum Source {
+    Number { dataref: &'static str, convert: fn(f32) -> f64 },
+    /// A byte array dataref holding text
+    Text { dataref: &'static str },
+    /// `dataref` as last read while `on_ground` was zero, for values the sim latches at touchdown
+    LastAirborne { dataref: &'static str, on_ground: &'static str, convert: fn(f32) -> f64 },
+}
+
+struct Variable {
+    name: &'static str,
+    source: Source,
+}
+
+fn same(value: f32) -> f64 { value as f64 }
+fn meters_to_feet(value: f32) -> f64 { value as f64 * FEET_PER_METER }
+fn meters_per_second_to_knots(value: f32) -> f64 { value as f64 * KNOTS_PER_METER_PER_SECOND }
+fn per_minute_to_per_second(value: f32) -> f64 { value as f64 / 60.0 }
+fn g_to_feet_per_second_squared(value: f32) -> f64 { value as f64 * FEET_PER_SECOND_SQUARED_PER_G }
+fn ratio_to_percent(value: f32) -> f64 { value as f64 * 100.0 }
+// SimConnect angles are positive nose down and left wing down
+fn inverted(value: f32) -> f64 { -(value as f64) }
+// Switches with an "auto" position count as on there too
+fn switched_on(value: f32) -> f64 { if value > 0.0 { 1.0 } else { 0.0 } }
+// SimConnect camera state 0 is the cockpit, everything else outside
+fn camera_state(external: f32) -> f64 { if external > 0.0 { 1.0 } else { 0.0 } }
+fn minutes_to_seconds(value: f32) -> f64 { value as f64 * 60.0 }
+// The GPS destination is the end of the plan once it's an airport (xplm_Nav_Airport)
+fn is_airport(nav_type: f32) -> f64 { if nav_type == 1.0 { 1.0 } else { 0.0 } }
+// `AMBIENT PRECIP STATE` mask: 2 none, 4 rain
+fn precipitation_state(rain_ratio: f32) -> f64 { if rain_ratio > 0.0 { 4.0 } else { 2.0 } }
+
+macro_rules! number {
+    ($name:expr, $dataref:expr, $convert:expr) => {
+        Variable { name: $name, source: Source::Number { dataref: $dataref, convert: $convert } }
+    };
+}
+
+/// The SimConnect variables simpa reads, in the units it asks for, mapped to X-Plane
+/// datarefs. Anything missing here, such as add-on L-vars, reads as zero.
+const VARIABLES: &[Variable] = &[
+    number!("PLANE ALTITUDE", "sim/flightmodel/position/elevation", meters_to_feet),
+    number!("PLANE ALT ABOVE GROUND", "sim/flightmodel/position/y_agl", meters_to_feet),
+    number!("PRESSURE ALTITUDE", "sim/flightmodel2/position/pressure_altitude", same),
+    number!("PLANE LATITUDE", "sim/flightmodel/position/latitude", same),
+    number!("PLANE LONGITUDE", "sim/flightmodel/position/longitude", same),
+    number!("PLANE BANK DEGREES", "sim/flightmodel/position/phi", inverted),
+    number!("PLANE PITCH DEGREES", "sim/flightmodel/position/theta", inverted),
+    number!("VERTICAL SPEED", "sim/flightmodel/position/vh_ind_fpm", same),
+    Variable {
+        name: "PLANE TOUCHDOWN NORMAL VELOCITY",
+        source: Source::LastAirborne {
+            dataref: "sim/flightmodel/position/vh_ind_fpm",
+            on_ground: "sim/flightmodel/failures/onground_any",
+            convert: per_minute_to_per_second,
+        },
+    },
+    number!("GROUND VELOCITY", "sim/flightmodel/position/groundspeed", meters_per_second_to_knots),
+    number!("GPS GROUND SPEED", "sim/flightmodel/position/groundspeed", meters_per_second_to_knots),
+    number!("AIRSPEED INDICATED", "sim/flightmodel/position/indicated_airspeed", same),
+    number!("G FORCE", "sim/flightmodel2/misc/gforce_normal", same),
+    number!("ACCELERATION BODY Y", "sim/flightmodel/forces/g_nrml", g_to_feet_per_second_squared),
+    number!("SIM ON GROUND", "sim/flightmodel/failures/onground_any", same),
+    number!("LIGHT BEACON", "sim/cockpit/electrical/beacon_lights_on", same),
+    number!("LIGHT LANDING", "sim/cockpit/electrical/landing_lights_on", same),
+    number!("CABIN SEATBELTS ALERT SWITCH", "sim/cockpit2/switches/fasten_seat_belts", switched_on),
+    number!("CABIN NO SMOKING ALERT SWITCH", "sim/cockpit2/switches/no_smoking", switched_on),
+    number!("EXIT OPEN:0", "sim/flightmodel2/misc/door_open_ratio[0]", ratio_to_percent),
+    number!("CAMERA STATE", "sim/graphics/view/view_is_external", camera_state),
+    number!("ZULU TIME", "sim/time/zulu_time_sec", same),
+    number!("GPS WP DISTANCE", "sim/cockpit2/radios/indicators/gps_dme_distance_nm", same),
+    // X-Plane's GPS only knows its active leg; while that leads to an airport the time to
+    // it is the time to the end of the plan, otherwise the plan reads as inactive
+    number!("GPS IS ACTIVE FLIGHT PLAN", "sim/cockpit/gps/destination_type", is_airport),
+    number!("GPS ETE", "sim/cockpit2/radios/indicators/gps_dme_time_min", minutes_to_seconds),
+    number!("AMBIENT TEMPERATURE", "sim/weather/temperature_ambient_c", same),
+    number!("AMBIENT WIND VELOCITY", "sim/cockpit2/gauges/indicators/wind_speed_kts", same),
+    number!("AMBIENT WIND DIRECTION", "sim/cockpit2/gauges/indicators/wind_heading_deg_mag", same),
+    number!("AMBIENT PRECIP STATE", "sim/weather/rain_percent", precipitation_state),
+    number!("AMBIENT VISIBILITY", "sim/weather/visibility_reported_m", same),
+    number!("SIMULATION RATE", "sim/time/sim_speed_actual", same),
+    Variable { name: "TITLE", source: Source::Text { dataref: "sim/aircraft/view/acf_ui_name" } },
+    Variable { name: "ATC MODEL", source: Source::Text { dataref: "sim/aircraft/view/acf_ICAO" } },
+    Variable { name: "GPS WP NEXT ID", source: Source::Text { dataref: "sim/cockpit2/radios/indicators/gps_nav_id" } },
+];
+
+/// The loaded .acf, relative to the X-Plane folder
+const AIRCRAFT_FILE: Variable = Variable {
+    name: "AIRCRAFT FILE",
+    source: Source::Text { dataref: "sim/aircraft/view/acf_relative_path" },
+};
+
+/// Dataref paths a variable is read from: one per character for text.
+fn datarefs_of(variable: &Variable) -> Vec<String> {
+    match variable.source {
+        Source::Number { dataref, .. } => vec![dataref.to_string()],
+        Source::Text { dataref } => (0..MAX_TEXT_LEN).map(|i| format!("{}[{}]", dataref, i)).collect(),
+        Source::LastAirborne { dataref, on_ground, .. } => vec![dataref.to_string(), on_ground.to_string()],
+    }
+}
+
+fn datatype_size(datatype: simconnect::SIMCONNECT_DATATYPE) -> usize {
+    match datatype {
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_INT32
+        | simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT32 => 4,
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING8 => 8,
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING32 => 32,
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING64 => 64,
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING128 => 128,
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING256 => 256,
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING260 => 260,
+        _ => 8,
+    }
+}
+
+fn is_text(datatype: simconnect::SIMCONNECT_DATATYPE) -> bool {
+    !matches!(datatype,
+        simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_INT32
+        | simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_INT64
+        | simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT32
+        | simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64)
+}
+
+struct Field {
+    datatype: simconnect::SIMCONNECT_DATATYPE,
+    variable: Option<&'static Variable>,
+    /// Into `Subscriptions::datarefs`
+    datarefs: Vec<usize>,
+    /// The latest airborne reading of a `LastAirborne` variable
+    last_airborne: Option<f32>,
+}
+
+struct Request {
+    /// None for a single reading
+    interval: Option<Duration>,
+    last_sent: Option<Instant>,
+}
+
+impl Request {
+    fn frequency(&self) -> i32 {
+        match self.interval {
+            Some(interval) if interval < Duration::from_secs(1) => 20,
+            _ => 2,
+        }
+    }
+}
+
+struct Dataref {
+    path: String,
+    frequency: i32,
+    value: Option<f32>,
+}
+
+enum Trigger {
+    /// Sends the value whenever it changes
+    Value,
+    Rising,
+    Falling,
+    /// The text changed after a full first reading; sent as the loaded file
+    File,
+}
+
+struct EventWatch {
+    id: u32,
+    trigger: Trigger,
+    datarefs: Vec<usize>,
+    last: Option<Vec<f32>>,
+}
+
+/// Watches X-Plane state standing in for a SimConnect system event.
+fn event_source(name: &str) -> Option<(Trigger, Vec<String>)> {
+    let crashed = || vec!["sim/flightmodel2/misc/has_crashed".to_string()];
+    match name {
+        "Pause" => Some((Trigger::Value, vec!["sim/time/paused".to_string()])),
+        "Crashed" => Some((Trigger::Rising, crashed())),
+        "CrashReset" => Some((Trigger::Falling, crashed())),
+        "AircraftLoaded" => Some((Trigger::File, datarefs_of(&AIRCRAFT_FILE))),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct Subscriptions {
+    definitions: HashMap<u32, Vec<Field>>,
+    requests: HashMap<u32, Request>,
+    /// Indexed by RREF index
+    datarefs: Vec<Dataref>,
+    events: Vec<EventWatch>,
+    pending: VecDeque<SimMessage>,
+    /// Backs the last `SimMessage::Data`
+    payload: Vec<u8>,
+    opened: bool,
+}
+
+impl Subscriptions {
+    fn dataref_index(&mut self, path: &str) -> usize {
+        match self.datarefs.iter().position(|dataref| dataref.path == path) {
+            Some(index) => index,
+            None => {
+                self.datarefs.push(Dataref { path: path.to_string(), frequency: 0, value: None });
+                self.datarefs.len() - 1
+            }
+        }
+    }
+
+    /// Frequency each dataref should be sent at for the current requests and events.
+    fn wanted_frequencies(&self) -> Vec<i32> {
+        let mut wanted = vec![0; self.datarefs.len()];
+        for (define_id, request) in &self.requests {
+            for field in self.definitions.get(define_id).into_iter().flatten() {
+                for &index in &field.datarefs {
+                    wanted[index] = wanted[index].max(request.frequency());
+                }
+            }
+        }
+        for watch in &self.events {
+            for &index in &watch.datarefs {
+                wanted[index] = wanted[index].max(EVENT_FREQUENCY);
+            }
+        }
+        wanted
+    }
+
+    /// Whether every mapped variable of the definition has been read. Definitions
+    /// without any mapped variable are never sent.
+    fn is_ready(&self, define_id: u32) -> bool {
+        let Some(fields) = self.definitions.get(&define_id) else {
+            return false;
+        };
+        let mut indices = fields.iter().flat_map(|field| field.datarefs.iter()).peekable();
+        indices.peek().is_some() && indices.all(|&index| self.datarefs[index].value.is_some())
+    }
+
+    /// Lays the definition's values out the way SimConnect would.
+    fn fill_payload(&mut self, define_id: u32) {
+        self.payload.clear();
+        for field in self.definitions.get(&define_id).into_iter().flatten() {
+            let size = datatype_size(field.datatype);
+            let values: Vec<f32> = field.datarefs.iter()
+                .map(|&index| self.datarefs[index].value.unwrap_or(0.0))
+                .collect();
+            if is_text(field.datatype) {
+                let mut text: Vec<u8> = values.iter().map(|&value| value as u8).take_while(|&byte| byte != 0).collect();
+                text.resize(size, 0);
+                text[size - 1] = 0;
+                self.payload.extend(text);
+                continue;
+            }
+            let value = match field.variable.map(|variable| &variable.source) {
+                Some(Source::Number { convert, .. }) => values.first().map(|&value| convert(value)).unwrap_or(0.0),
+                Some(Source::LastAirborne { convert, .. }) => field.last_airborne.map(convert).unwrap_or(0.0),
+                _ => 0.0,
+            };
+            match field.datatype {
+                simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_INT32 => self.payload.extend((value as i32).to_le_bytes()),
+                simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_INT64 => self.payload.extend((value as i64).to_le_bytes()),
+                simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT32 => self.payload.extend((value as f32).to_le_bytes()),
+                _ => self.payload.extend(value.to_le_bytes()),
+            }
+        }
+    }
+
+    /// Keeps the airborne readings of `LastAirborne` variables, so they hold their value
+    /// from just before touchdown once on the ground.
+    fn latch_airborne(&mut self) {
+        let datarefs = &self.datarefs;
+        for field in self.definitions.values_mut().flatten() {
+            if !matches!(field.variable.map(|variable| &variable.source), Some(Source::LastAirborne { .. })) {
+                continue;
+            }
+            let value = |i: usize| field.datarefs.get(i).and_then(|&index| datarefs[index].value);
+            if let (Some(value), Some(0.0)) = (value(0), value(1)) {
+                field.last_airborne = Some(value);
+            }
+        }
+    }
+
+    /// Queues the system events the latest values call for.
+    fn check_events(&mut self) {
+        for watch in &mut self.events {
+            let current: Option<Vec<f32>> = watch.datarefs.iter()
+                .map(|&index| self.datarefs[index].value)
+                .collect();
+            let Some(current) = current else {
+                continue;
+            };
+            let fired = match (&watch.trigger, &watch.last) {
+                (_, Some(last)) if *last == current => false,
+                (Trigger::Value, _) => true,
+                (Trigger::Rising, Some(_)) => current[0] != 0.0,
+                (Trigger::Falling, Some(_)) => current[0] == 0.0,
+                (Trigger::File, Some(_)) => true,
+                (_, None) => false,
+            };
+            if fired {
+                self.pending.push_back(match watch.trigger {
+                    Trigger::File => {
+                        let bytes: Vec<u8> = current.iter().map(|&value| value as u8).take_while(|&byte| byte != 0).collect();
+                        SimMessage::FileLoaded { id: watch.id, path: String::from_utf8_lossy(&bytes).into_owned() }
+                    },
+                    _ => SimMessage::Event { id: watch.id, data: current[0] as u32 },
+                });
+            }
+            watch.last = Some(current);
+        }
+    }
+}
+
+/// X-Plane over its UDP dataref protocol, presented as a SimConnect connection so the
+/// collection loop works unchanged.
+pub struct XPlaneConnection {
+    socket: UdpSocket,
+    subscriptions: RefCell<Subscriptions>,
+    last_packet: RefCell<Instant>,
+}
+
+impl XPlaneConnection {
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+        socket.connect(address).map_err(|e| format!("{}: {}", address, e))?;
+        socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+        Ok(XPlaneConnection {
+            socket,
+            subscriptions: RefCell::new(Subscriptions::default()),
+            last_packet: RefCell::new(Instant::now()),
+        })
+    }
+
+    /// Sends RREF requests for every dataref whose frequency changed.
+    fn update_subscriptions(&self) {
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        let wanted = subscriptions.wanted_frequencies();
+        for (index, frequency) in wanted.into_iter().enumerate() {
+            let dataref = &mut subscriptions.datarefs[index];
+            if dataref.frequency == frequency {
+                continue;
+            }
+            dataref.frequency = frequency;
+            if frequency == 0 {
+                // A later request must not be answered with what we had before
+                dataref.value = None;
+            }
+            if let Err(e) = self.socket.send(&rref_packet(frequency, index as i32, &dataref.path)) {
+                println!("Failed to subscribe to {}: {}", dataref.path, e);
+            }
+        }
+    }
+
+    fn receive(&self) {
+        let mut buffer = [0u8; 4096];
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        while let Ok(len) = self.socket.recv(&mut buffer) {
+            let Some(values) = parse_rref(&buffer[..len]) else {
+                continue;
+            };
+            *self.last_packet.borrow_mut() = Instant::now();
+            if !subscriptions.opened {
+                subscriptions.opened = true;
+                subscriptions.pending.push_back(SimMessage::Open);
+            }
+            for (index, value) in values {
+                if let Some(dataref) = subscriptions.datarefs.get_mut(index as usize) {
+                    if dataref.frequency > 0 {
+                        dataref.value = Some(value);
+                    }
+                }
+            }
+        }
+        subscriptions.latch_airborne();
+        subscriptions.check_events();
+    }
+
+    /// The first requested definition that is due, filled into the payload, and whether
+    /// that was its only reading.
+    fn next_due(&self) -> Option<(u32, bool)> {
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        let now = Instant::now();
+        let due = subscriptions.requests.iter()
+            .filter(|(_, request)| match (request.interval, request.last_sent) {
+                (_, None) => true,
+                (Some(interval), Some(sent)) => now.duration_since(sent) >= interval,
+                (None, Some(_)) => false,
+            })
+            .map(|(define_id, _)| *define_id)
+            .find(|define_id| subscriptions.is_ready(*define_id))?;
+
+        subscriptions.fill_payload(due);
+        let once = subscriptions.requests.get(&due).is_some_and(|request| request.interval.is_none());
+        if once {
+            subscriptions.requests.remove(&due);
+        } else if let Some(request) = subscriptions.requests.get_mut(&due) {
+            request.last_sent = Some(now);
+        }
+        Some((due, once))
+    }
+}
+
+impl SimConnection for XPlaneConnection {
+    fn add_data_definition(
+        &self,
+        define_id: u32,
+        name: &str,
+        _unit: &str,
+        datatype: simconnect::SIMCONNECT_DATATYPE,
+        _datum_id: u32,
+        _epsilon: f32
+    ) -> bool {
+        let variable = VARIABLES.iter().find(|variable| variable.name == name);
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        let datarefs = variable.map(datarefs_of).unwrap_or_default().iter()
+            .map(|path| subscriptions.dataref_index(path))
+            .collect();
+        subscriptions.definitions.entry(define_id).or_default().push(Field { datatype, variable, datarefs, last_airborne: None });
+        variable.is_some()
+    }
+
+    fn request_data_on_sim_object(
+        &self,
+        _request_id: u32,
+        define_id: u32,
+        _object_id: u32,
+        period: simconnect::SIMCONNECT_PERIOD,
+        _flags: u32,
+        _origin: u32,
+        _interval: u32,
+        _limit: u32
+    ) -> bool {
+        let interval = match period {
+            simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_NEVER => None,
+            simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_ONCE => Some(None),
+            simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND => Some(Some(Duration::from_secs(1))),
+            _ => Some(Some(Duration::from_millis(50))),
+        };
+        {
+            let mut subscriptions = self.subscriptions.borrow_mut();
+            match interval {
+                Some(interval) => {
+                    subscriptions.requests.insert(define_id, Request { interval, last_sent: None });
+                }
+                None => {
+                    subscriptions.requests.remove(&define_id);
+                }
+            }
+        }
+        self.update_subscriptions();
+        true
+    }
+
+    fn subscribe_to_system_event(&self, event_id: u32, name: &str) -> bool {
+        let Some((trigger, paths)) = event_source(name) else {
+            return false;
+        };
+        {
+            let mut subscriptions = self.subscriptions.borrow_mut();
+            let datarefs = paths.iter().map(|path| subscriptions.dataref_index(path)).collect();
+            subscriptions.events.push(EventWatch { id: event_id, trigger, datarefs, last: None });
+        }
+        self.update_subscriptions();
+        true
+    }
+
+    // X-Plane doesn't report key events over UDP
+    fn map_client_event_to_sim_event(&self, _event_id: u32, _name: &str) -> bool {
+        false
+    }
+
+    fn add_client_event_to_notification_group(&self, _group_id: u32, _event_id: u32, _maskable: bool) -> bool {
+        false
+    }
+
+    fn set_notification_group_priority(&self, _group_id: u32, _priority: u32) -> bool {
+        false
+    }
+
+    fn set_system_event_state(&self, _event_id: u32, _state: simconnect::SIMCONNECT_STATE) -> bool {
+        false
+    }
+
+    fn next_message(&self) -> Result<SimMessage, String> {
+        self.receive();
+        if let Some(message) = self.subscriptions.borrow_mut().pending.pop_front() {
+            return Ok(message);
+        }
+        if let Some((define_id, once)) = self.next_due() {
+            if once {
+                self.update_subscriptions();
+            }
+            let data = self.subscriptions.borrow().payload.as_ptr();
+            return Ok(SimMessage::Data { define_id, data });
+        }
+        if self.last_packet.borrow().elapsed() >= SILENCE_TIMEOUT {
+            return Ok(SimMessage::Quit);
+        }
+        Ok(SimMessage::Other)
+    }
+}
+
+impl Drop for XPlaneConnection {
+    /// Stops X-Plane sending to a socket that's going away.
+    fn drop(&mut self) {
+        for (index, dataref) in self.subscriptions.get_mut().datarefs.iter().enumerate() {
+            if dataref.frequency > 0 {
+                let _ = self.socket.send(&rref_packet(0, index as i32, &dataref.path));
+            }
+        }
+    }
+}
+
+fn send_packet(address: &str, packet: &[u8]) -> Result<(), String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket.send_to(packet, address).map_err(|e| format!("{}: {}", address, e))?;
+    Ok(())
+}
+
+/// Runs an X-Plane command, e.g. `sim/ground_ops/jetway`.
+#[tauri::command]
+pub fn send_xplane_command(state: State<Arc<SimConnectState>>, command: String) -> Result<(), String> {
+    let address = state.xplane.lock().unwrap().address.clone();
+    send_packet(&address, &cmnd_packet(&command))
+}
+
+/// Writes an X-Plane dataref.
+#[tauri::command]
+pub fn set_xplane_dataref(state: State<Arc<SimConnectState>>, dataref: String, value: f32) -> Result<(), String> {
+    let address = state.xplane.lock().unwrap().address.clone();
+    send_packet(&address, &dref_packet(&dataref, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A dataref subscription X-Plane got: RREF index, path and who asked.
+    type Subscription = (i32, String, std::net::SocketAddr);
+
+    /// Plays X-Plane: answers every RREF subscription with `value_for(path)`.
+    fn answer_subscriptions(stand_in: &UdpSocket, value_for: impl Fn(&str) -> f32) -> Vec<Subscription> {
+        let mut buffer = [0u8; 1024];
+        let mut subscriptions = Vec::new();
+        while let Ok((len, from)) = stand_in.recv_from(&mut buffer) {
+            let packet = &buffer[..len];
+            assert_eq!(&packet[..5], b"RREF\0");
+            assert_eq!(len, 5 + 8 + RREF_PATH_LEN);
+            let index = i32::from_le_bytes(packet[9..13].try_into().unwrap());
+            let path: String = packet[13..].iter().take_while(|&&byte| byte != 0).map(|&byte| byte as char).collect();
+            subscriptions.push((index, path, from));
+        }
+        send_values(stand_in, &subscriptions, value_for);
+        subscriptions
+    }
+
+    fn send_values(stand_in: &UdpSocket, subscriptions: &[Subscription], value_for: impl Fn(&str) -> f32) {
+        let mut reply = b"RREF,".to_vec();
+        for (index, path, from) in subscriptions {
+            reply.extend_from_slice(&index.to_le_bytes());
+            reply.extend_from_slice(&value_for(path).to_le_bytes());
+            stand_in.send_to(&reply, from).unwrap();
+            reply.truncate(5);
+        }
+    }
+
+    /// Reads `text` from a byte array dataref, one RREF per character.
+    fn text_value(path: &str, dataref: &str, text: &[u8]) -> f32 {
+        path.strip_prefix(dataref)
+            .and_then(|rest| rest.strip_prefix('['))
+            .and_then(|rest| rest.trim_end_matches(']').parse::<usize>().ok())
+            .and_then(|i| text.get(i))
+            .map_or(0.0, |&byte| byte as f32)
+    }
+
+    fn stand_in() -> (UdpSocket, XPlaneConnection) {
+        let stand_in = UdpSocket::bind("127.0.0.1:0").unwrap();
+        stand_in.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+        let connection = XPlaneConnection::connect(&stand_in.local_addr().unwrap().to_string()).unwrap();
+        (stand_in, connection)
+    }
+
+    fn next_data(connection: &XPlaneConnection) -> (u32, *const u8) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if let SimMessage::Data { define_id, data } = connection.next_message().unwrap() {
+                return (define_id, data);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("no data from the stand-in");
+    }
+
+    #[test]
+    fn packets_match_the_protocol() {
+        let packet = dref_packet("sim/cockpit/electrical/beacon_lights_on", 1.0);
+        assert_eq!(packet.len(), 509);
+        assert_eq!(&packet[..5], b"DREF\0");
+        assert_eq!(f32::from_le_bytes(packet[5..9].try_into().unwrap()), 1.0);
+        assert_eq!(cmnd_packet("sim/ground_ops/jetway"), b"CMND\0sim/ground_ops/jetway".to_vec());
+
+        let mut reply = b"RREF,".to_vec();
+        reply.extend_from_slice(&3i32.to_le_bytes());
+        reply.extend_from_slice(&2.5f32.to_le_bytes());
+        assert_eq!(parse_rref(&reply), Some(vec![(3, 2.5)]));
+        assert_eq!(parse_rref(b"DATA*"), None);
+    }
+
+    #[test]
+    fn definitions_are_filled_like_simconnect() {
+        let (stand_in, connection) = stand_in();
+        let float = simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64;
+        let int = simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_INT32;
+        assert!(connection.add_data_definition(1, "PLANE ALTITUDE", "Feet", float, 0, 0.0));
+        assert!(!connection.add_data_definition(1, "L:UNKNOWN", "Number", float, 0, 0.0));
+        assert!(connection.add_data_definition(1, "CABIN SEATBELTS ALERT SWITCH", "Bool", int, 0, 0.0));
+        connection.request_data_on_sim_object(1, 1, 0, simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_SECOND, 0, 0, 0, 0);
+
+        answer_subscriptions(&stand_in, |path| match path {
+            "sim/flightmodel/position/elevation" => 1000.0,
+            "sim/cockpit2/switches/fasten_seat_belts" => 2.0,
+            _ => 0.0,
+        });
+        assert!(matches!(connection.next_message(), Ok(SimMessage::Open)));
+
+        let (define_id, data) = next_data(&connection);
+        assert_eq!(define_id, 1);
+        unsafe {
+            let altitude = std::ptr::read_unaligned(data as *const f64);
+            let unknown = std::ptr::read_unaligned(data.add(8) as *const f64);
+            let seatbelts = std::ptr::read_unaligned(data.add(16) as *const i32);
+            assert!((altitude - 3280.84).abs() < 0.01);
+            assert_eq!(unknown, 0.0);
+            assert_eq!(seatbelts, 1);
+        }
+    }
+
+    #[test]
+    fn touchdown_rate_holds_the_last_airborne_reading() {
+        let (stand_in, connection) = stand_in();
+        let float = simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_FLOAT64;
+        connection.add_data_definition(9, "PLANE TOUCHDOWN NORMAL VELOCITY", "Feet per second", float, 0, 0.0);
+        connection.add_data_definition(9, "SIM ON GROUND", "Bool", float, 0, 0.0);
+        connection.request_data_on_sim_object(9, 9, 0, simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_VISUAL_FRAME, 0, 0, 0, 0);
+        let read = |data: *const u8| unsafe {
+            (std::ptr::read_unaligned(data as *const f64), std::ptr::read_unaligned(data.add(8) as *const f64))
+        };
+
+        let subscriptions = answer_subscriptions(&stand_in, |path| match path {
+            "sim/flightmodel/position/vh_ind_fpm" => -600.0,
+            _ => 0.0,
+        });
+        assert_eq!(read(next_data(&connection).1), (-10.0, 0.0));
+
+        // Rolling out, the live vertical speed is back to zero
+        send_values(&stand_in, &subscriptions, |path| match path {
+            "sim/flightmodel/failures/onground_any" => 1.0,
+            _ => 0.0,
+        });
+        let deadline = Instant::now() + Duration::from_secs(2);
+        loop {
+            let (touchdown, on_ground) = read(next_data(&connection).1);
+            if on_ground == 1.0 {
+                assert_eq!(touchdown, -10.0);
+                break;
+            }
+            assert!(Instant::now() < deadline, "never landed");
+        }
+    }
+
+    #[test]
+    fn text_and_events_come_through() {
+        let (stand_in, connection) = stand_in();
+        let string = simconnect::SIMCONNECT_DATATYPE_SIMCONNECT_DATATYPE_STRING256;
+        connection.add_data_definition(14, "TITLE", "String256", string, 0, 0.0);
+        connection.request_data_on_sim_object(14, 14, 0, simconnect::SIMCONNECT_PERIOD_SIMCONNECT_PERIOD_ONCE, 0, 0, 0, 0);
+        assert!(connection.subscribe_to_system_event(24, "Pause"));
+        assert!(!connection.subscribe_to_system_event(7, "Frame"));
+
+        let title = b"Cessna 172";
+        answer_subscriptions(&stand_in, |path| match path {
+            "sim/time/paused" => 1.0,
+            _ => text_value(path, "sim/aircraft/view/acf_ui_name", title),
+        });
+
+        let mut messages = Vec::new();
+        while messages.len() < 2 {
+            match connection.next_message().unwrap() {
+                SimMessage::Event { id, data } => messages.push(format!("event {} {}", id, data)),
+                SimMessage::Data { define_id, data } => {
+                    let text = unsafe { std::slice::from_raw_parts(data, 256) };
+                    assert_eq!(&text[..title.len() + 1], b"Cessna 172\0");
+                    messages.push(format!("data {}", define_id));
+                }
+                _ => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        messages.sort();
+        assert_eq!(messages, ["data 14", "event 24 1"]);
+    }
+
+    #[test]
+    fn aircraft_changes_come_through_as_loaded_files() {
+        let (stand_in, connection) = stand_in();
+        assert!(connection.subscribe_to_system_event(29, "AircraftLoaded"));
+
+        let dataref = "sim/aircraft/view/acf_relative_path";
+        let subscriptions = answer_subscriptions(&stand_in, |path| text_value(path, dataref, b"Aircraft/C172/c172.acf"));
+        let until_quiet = |connection: &XPlaneConnection| {
+            let deadline = Instant::now() + Duration::from_millis(500);
+            let mut loaded = Vec::new();
+            while Instant::now() < deadline {
+                if let SimMessage::FileLoaded { id, path } = connection.next_message().unwrap() {
+                    loaded.push((id, path));
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            loaded
+        };
+        // The first reading is the aircraft simpa connected to, not a change
+        assert!(until_quiet(&connection).is_empty());
+
+        send_values(&stand_in, &subscriptions, |path| text_value(path, dataref, b"Aircraft/B738/b738.acf"));
+        assert_eq!(until_quiet(&connection), [(29, "Aircraft/B738/b738.acf".to_string())]);
+    }
+}
@@ -318,6 +318,8 @@ function App() {
   const arriveSoonRef = useRef<HTMLAudioElement | null>(null);
   const landingSoonRef = useRef<HTMLAudioElement | null>(null);
   const weveArrivedRef = useRef<HTMLAudioElement | null>(null);
+  // Set once "We've arrived" played for this arrival, natively or here
+  const weveArrivedPlayedRef = useRef<boolean>(false);
   const almostReadyRef = useRef<HTMLAudioElement | null>(null);
   const seatsForDepartureRef = useRef<HTMLAudioElement | null>(null);
   const safetyVideoRef = useRef<HTMLAudioElement | null>(null);
@@ -362,6 +364,8 @@ function App() {
   const isSeatbeltAudioPlayingRef = useRef<boolean>(false);
   const lastSeatbeltEventTimeRef = useRef<number>(Date.now());
   const seatbeltEventDebounceTimeRef = useRef<number>(3000); // 3 seconds debounce
+  // The backend chimes on sign changes itself unless native chimes are switched off
+  const nativeChimesEnabledRef = useRef<boolean>(true);
 
   // Position debounce values
   const zoneStabilityRef = useRef<{
@@ -837,7 +841,8 @@ function App() {
           console.log(`- Has descended through 10k: ${hasDescendedThrough10kRef.current}`);
           
           // Check if we should play "We've arrived" announcement
-          if (newCount === 2 && hasDescendedThrough10kRef.current) {
+          if (newCount === 2 && hasDescendedThrough10kRef.current && !weveArrivedPlayedRef.current) {
+            weveArrivedPlayedRef.current = true;
             console.log('Conditions met! Playing "We\'ve arrived" announcement');
             if (weveArrivedRef.current) {
               // Use the helper function for consistent audio playback management
@@ -892,7 +897,8 @@ function App() {
   };
 
   // Function to handle seatbelt sign changes
-  const handleSeatbeltSignChange = (newState: boolean) => {
+  // `nativeChime` says whether the backend actually played the chime, when it's known
+  const handleSeatbeltSignChange = (newState: boolean, nativeChime?: boolean) => {
     console.log('=== Seatbelt Sign State Change ===');
     console.log(`New State: ${newState ? 'ON' : 'OFF'}`);
     console.log(`Previous State: ${lastSeatbeltStateRef.current ? 'ON' : 'OFF'}`);
@@ -909,6 +915,12 @@ function App() {
           console.log(`Incrementing count from ${prevCount} to ${newCount}`);
           seatbeltSignCountRef.current = newCount;
           
+          // The native chime already covers the sign change
+          if (nativeChime ?? nativeChimesEnabledRef.current) {
+            console.log('Native cabin chime played, skipping seatbelt audio');
+            return newCount;
+          }
+
           // If audio is currently playing, don't interrupt it
           if (isSeatbeltAudioPlayingRef.current) {
             console.log('Seatbelt audio already playing, ignoring event');
@@ -1460,9 +1472,9 @@ function App() {
         return;
       }
 
-      const data = event.payload as { state: boolean };
+      const data = event.payload as { state: boolean, native?: boolean };
       console.log('Seatbelt sign event received:', data);
-      handleSeatbeltSignChange(data.state);
+      handleSeatbeltSignChange(data.state, data.native);
     } catch (error) {
       console.error('Error handling seatbelt sign event:', error);
     }
@@ -1636,6 +1648,7 @@ function App() {
 
       const data = event.payload as { 
         alt?: number, 
+        altitudes?: { descendedThrough10k?: boolean },
         tenKAnnounced?: boolean,
         jetwayMoving?: boolean,
         jetwayState?: boolean,
@@ -1678,6 +1691,17 @@ function App() {
       // Debug for all property names in the payload
       console.log('SIMCONNECT DEBUG - All property names:', Object.keys(event.payload));
 
+      // The backend tracks the descent through 10k whatever altitude triggers are configured
+      const descendedThrough10k = data.altitudes?.descendedThrough10k;
+      if (typeof descendedThrough10k === 'boolean' && descendedThrough10k !== hasDescendedThrough10kRef.current) {
+        console.log(`Descended through 10k: ${descendedThrough10k}`);
+        hasDescendedThrough10kRef.current = descendedThrough10k;
+        setHasDescendedThrough10k(descendedThrough10k);
+        if (descendedThrough10k) {
+          weveArrivedPlayedRef.current = false;
+        }
+      }
+
       // Handle altitude data
       if (typeof data.alt === 'number' && !isNaN(data.alt)) {
         const currentAlt = data.alt;
@@ -1693,92 +1717,8 @@ function App() {
             altitude: currentAlt
           }));
           
-          // Check for 10k feet announcement during climb
-          if (lastAlt < 10000 && currentAlt >= 10000 && !tenKAnnouncedRef.current) {
-            console.log('Climbing through 10k feet - triggering announcement');
-            tenKAnnouncedRef.current = true;
-            hasDescendedThrough10kRef.current = false; // Reset descent flag
-            
-            // Play 10k feet announcement
-            if (tenKFeetRef.current) {
-              console.log('Playing 10k feet announcement');
-              const currentZ = flightState.zPosition || lastCameraZRef.current || 0;
-              const zone = getCurrentZone([currentZ, 0, 0]);
-              const baseVolume = getZoneVolume(zone);
-              const logVolume = toLogVolume(baseVolume * 100);
-              const positionVolume = (masterVolume / 100) * logVolume;
-              
-              tenKFeetRef.current.volume = positionVolume;
-              tenKFeetRef.current.currentTime = 0;
-              tenKFeetRef.current.play()
-                .then(() => {
-                  console.log('Successfully started playing 10k feet announcement');
-                  setIsPlaying(true);
-                  setCurrentAudioName('10k_feet');
-                  setCurrentAudioVolume(positionVolume * 100);
-                  setCurrentZone(zone);
-                })
-                .catch(error => {
-                  console.error('Error playing 10k feet announcement:', error);
-                });
-            }
-          }
-          
-          // Check for arrival announcements at 18,000 feet during descent
-          if (lastAlt > 18000 && currentAlt <= 18000 && !arriveSoonAnnouncedRef.current) {
-            console.log('Descending through 18,000 feet - triggering arrival soon announcement');
-            arriveSoonAnnouncedRef.current = true;
-            
-            // Play arrive soon announcement
-            if (arriveSoonRef.current) {
-              console.log('Playing arrive soon announcement');
-              const currentZ = flightState.zPosition || lastCameraZRef.current || 0;
-              const zone = getCurrentZone([currentZ, 0, 0]);
-              const baseVolume = getZoneVolume(zone);
-              const logVolume = toLogVolume(baseVolume * 100);
-              const positionVolume = (masterVolume / 100) * logVolume;
-              
-              arriveSoonRef.current.volume = positionVolume;
-              arriveSoonRef.current.currentTime = 0;
-              arriveSoonRef.current.play()
-                .then(() => {
-                  console.log('Successfully started playing arrive soon announcement');
-                  setIsPlaying(true);
-                  setCurrentAudioName('arrive_soon');
-                  setCurrentAudioVolume(positionVolume * 100);
-                  setCurrentZone(zone);
-                })
-                .catch(error => {
-                  console.error('Error playing arrive soon announcement:', error);
-                });
-            }
-          }
-          
-          // Check if we're descending through 10k feet
-          if (lastAlt > 10000 && currentAlt <= 10000) {
-            console.log('Descending through 10k feet');
-            hasDescendedThrough10kRef.current = true;
-            setHasDescendedThrough10k(true);
-            tenKAnnouncedRef.current = false; // Reset climb flag
-            
-            // Check for landing announcement at 10,000 feet during descent
-            if (!landingSoonAnnouncedRef.current) {
-              console.log('Below 10,000 feet - triggering landing soon announcement');
-              landingSoonAnnouncedRef.current = true;
-              
-              // Play landing soon announcement using the playAnnouncementWithVolume function instead
-              // which properly handles audio playback with cleanup
-              if (landingSoonRef.current) {
-                console.log('Playing landing soon announcement');
-                // Use the helper function for consistent audio playback management
-                playAnnouncementWithVolume(landingSoonRef, 'landing_soon', () => {
-                  console.log('Landing soon announcement completed');
-                  // Make sure the flag stays true even after playback ends
-                  landingSoonAnnouncedRef.current = true;
-                });
-              }
-            }
-          }
+          // The 10k feet, arrive soon and landing soon announcements are triggered by
+          // the backend against the departure/destination field and arrive as audio events
           
           // Update last altitude
           lastAltitudeRef.current = currentAlt;
@@ -2161,7 +2101,7 @@ function App() {
         return;
       }
 
-      const data = event.payload as { state: boolean, resetCount?: boolean };
+      const data = event.payload as { state: boolean, resetCount?: boolean, native?: boolean };
       console.log('Seatbelt sign event received:', data);
       
       // Reset the count if needed (e.g., during flight reset or new flight)
@@ -2171,7 +2111,7 @@ function App() {
         setSeatbeltSignCount(0);
       }
       
-      handleSeatbeltSignChange(data.state);
+      handleSeatbeltSignChange(data.state, data.native);
       
       // Also update the flight state
       setFlightState(prev => ({
@@ -2583,10 +2523,23 @@ function App() {
         const data = event.payload as {
           type: string;
           volume?: number;
+          clip?: string;
+          folder?: string;
+          native?: boolean;
         };
 
         console.log('Audio event received:', data);
 
+        // Already played by the native engine
+        if (data.native) {
+          if (data.type === 'weve_arrived') {
+            weveArrivedPlayedRef.current = true;
+          } else if (data.type === 'arrive_soon') {
+            arriveSoonAnnouncedRef.current = true;
+          }
+          return;
+        }
+
         if (data.type === 'safety_video') {
           if (safetyVideoRef.current) {
             console.log('Attempting to play safety video');
@@ -2659,6 +2612,29 @@ function App() {
           } else {
             console.error('Safety video audio element not found');
           }
+        } else if (data.type === '10k_feet') {
+          console.log('Climbing through 10k feet - triggering announcement');
+          tenKAnnouncedRef.current = true;
+          playAnnouncementWithVolume(tenKFeetRef, '10k_feet');
+        } else if (data.type === 'arrive_soon') {
+          console.log('Arrival PA not played natively - playing the recorded arrival soon announcement');
+          arriveSoonAnnouncedRef.current = true;
+          playAnnouncementWithVolume(arriveSoonRef, 'arrive_soon');
+        } else if (data.type === 'landing_soon') {
+          console.log('Descending through 10k feet - triggering landing soon announcement');
+          tenKAnnouncedRef.current = false; // Reset climb flag
+          landingSoonAnnouncedRef.current = true;
+          playAnnouncementWithVolume(landingSoonRef, 'landing_soon');
+        } else if (data.type === 'weve_arrived') {
+          if (!weveArrivedPlayedRef.current) {
+            weveArrivedPlayedRef.current = true;
+            playAnnouncementWithVolume(weveArrivedRef, 'weve_arrived');
+          }
+        } else if (data.clip) {
+          // The native engine couldn't play it; try a bundled copy
+          console.log(`Playing ${data.clip} from the bundled sounds`);
+          const fallbackRef = { current: new Audio(`/sounds/${data.folder ?? 'announcements'}/${data.clip}.wav`) };
+          playAnnouncementWithVolume(fallbackRef, data.clip);
         }
       } catch (error) {
         console.error('Error handling audio event:', error);
@@ -2837,11 +2813,23 @@ function App() {
     }
   };
 
+  const refreshChimeSettings = async () => {
+    try {
+      const settings = await invoke<{ enabled: boolean }>('get_chime_settings');
+      nativeChimesEnabledRef.current = settings.enabled;
+    } catch (error) {
+      console.error("Failed to get chime settings:", error);
+    }
+  };
+
   // Add SimConnect connection state listeners
   useEffect(() => {
+    refreshChimeSettings();
+
     const unlistenSimconnectOpen = listen('simconnect-open', () => {
       console.log("SimConnect connection opened");
       setSimConnectActive(true);
+      refreshChimeSettings();
     });
 
     const unlistenSimconnectQuit = listen('simconnect-quit', () => {